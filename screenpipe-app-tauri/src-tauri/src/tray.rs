@@ -5,7 +5,7 @@ use anyhow::Result;
 use once_cell::sync::Lazy;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIcon};
 use tauri::Emitter;
 use tauri::{
@@ -14,7 +14,7 @@ use tauri::{
 };
 use tauri_plugin_opener::OpenerExt;
 
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 // Track last known state to avoid unnecessary updates
 static LAST_MENU_STATE: Lazy<Mutex<MenuState>> = Lazy::new(|| Mutex::new(MenuState::default()));
@@ -185,6 +185,19 @@ fn handle_menu_event(app_handle: &AppHandle, event: tauri::menu::MenuEvent) {
     }
 }
 
+// A panic while the lock was held would otherwise poison it for good and
+// every later tick of the update loop would panic on it too
+fn lock_menu_state() -> MutexGuard<'static, MenuState> {
+    LAST_MENU_STATE.lock().unwrap_or_else(|poisoned| {
+        warn!("tray menu state lock was poisoned, resetting the menu state");
+        LAST_MENU_STATE.clear_poison();
+        let mut state = poisoned.into_inner();
+        // Whatever was half written is not trusted, the menu gets rebuilt
+        *state = MenuState::default();
+        state
+    })
+}
+
 async fn update_menu_if_needed(
     app: &AppHandle,
     update_item: &tauri::menu::MenuItem<Wry>,
@@ -197,7 +210,7 @@ async fn update_menu_if_needed(
 
     // Compare with last state
     let should_update = {
-        let mut last_state = LAST_MENU_STATE.lock().unwrap();
+        let mut last_state = lock_menu_state();
         if *last_state != new_state {
             *last_state = new_state.clone();
             true
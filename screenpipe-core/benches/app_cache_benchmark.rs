@@ -0,0 +1,42 @@
+// Compares looking up an application and its windows with and without the
+// cached application elements. Needs accessibility permissions and Finder running
+#[cfg(target_os = "macos")]
+mod app_cache_benchmark {
+    use criterion::{criterion_group, criterion_main, Criterion};
+    use screenpipe_core::Desktop;
+
+    fn snapshot_app(desktop: &Desktop) {
+        let app = desktop
+            .application("Finder")
+            .expect("Finder should be running");
+        let _ = app.children();
+    }
+
+    fn app_cache_benchmark(c: &mut Criterion) {
+        let desktop = Desktop::new(false, false).expect("Failed to create desktop");
+
+        let mut group = c.benchmark_group("app_cache");
+        group.sample_size(20);
+
+        group.bench_function("cold", |b| {
+            b.iter(|| {
+                desktop.clear_cache();
+                snapshot_app(&desktop);
+            })
+        });
+
+        desktop.clear_cache();
+        snapshot_app(&desktop);
+        group.bench_function("warm", |b| b.iter(|| snapshot_app(&desktop)));
+
+        group.finish();
+    }
+
+    criterion_group!(benches, app_cache_benchmark);
+    criterion_main!(benches);
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {
+    // Empty main for non-macOS platforms
+}
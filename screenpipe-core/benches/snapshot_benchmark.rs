@@ -0,0 +1,154 @@
+// cargo bench --bench snapshot_benchmark
+//
+// Synthetic trees only, no accessibility access needed. Real traversal is
+// covered by app_cache_benchmark on macOS
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use screenpipe_core::{
+    diff_snapshots, serialize_for_llm, Bounds, ElementSnapshot, LlmElements, LlmOptions, Role,
+};
+
+const TREE_SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn node(id: usize) -> ElementSnapshot {
+    let role = if id % 3 == 0 {
+        Role::Button
+    } else {
+        Role::StaticText
+    };
+    let mut node = ElementSnapshot::new(format!("ax_{}", id), role);
+    node.label = Some(format!("element {}", id));
+    node.value = Some(format!("value of element {}", id));
+    node.bounds = Some(Bounds {
+        x: 10.0,
+        y: id as f64,
+        width: 100.0,
+        height: 20.0,
+    });
+    node
+}
+
+// Breadth-first tree with ten children per element
+fn synthetic_tree(size: usize) -> ElementSnapshot {
+    fn build(id: usize, size: usize) -> ElementSnapshot {
+        let mut element = node(id);
+        element.children = (1..=10)
+            .map(|i| id * 10 + i)
+            .filter(|&child| child < size)
+            .map(|child| build(child, size))
+            .collect();
+        element
+    }
+    build(0, size)
+}
+
+// Change every hundredth value and drop about one leaf in a hundred
+fn mutate(tree: &ElementSnapshot) -> ElementSnapshot {
+    fn walk(element: &mut ElementSnapshot, counter: &mut usize) {
+        *counter += 1;
+        if *counter % 100 == 0 {
+            element.value = Some(format!("changed {}", counter));
+        }
+        element
+            .children
+            .retain(|child| !child.children.is_empty() || !child.id.ends_with("07"));
+        for child in &mut element.children {
+            walk(child, counter);
+        }
+    }
+
+    let mut tree = tree.clone();
+    walk(&mut tree, &mut 0);
+    tree
+}
+
+fn bench_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_diff");
+    group.sample_size(10);
+
+    for size in TREE_SIZES {
+        let old = synthetic_tree(size);
+        let new = mutate(&old);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| diff_snapshots(black_box(&old), black_box(&new)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_serde(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_serde");
+    group.sample_size(10);
+
+    for size in TREE_SIZES {
+        let tree = synthetic_tree(size);
+        let json = serde_json::to_string(&tree).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("serialize", size), &tree, |b, tree| {
+            b.iter(|| serde_json::to_string(black_box(tree)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("deserialize", size), &json, |b, json| {
+            b.iter(|| serde_json::from_str::<ElementSnapshot>(black_box(json)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+// Also prints the outline size next to the JSON size, the outline is meant to
+// save prompt tokens
+fn bench_llm_outline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_llm_outline");
+    group.sample_size(10);
+
+    let options = LlmOptions {
+        elements: LlmElements::All,
+        bounds: true,
+        ..LlmOptions::default()
+    };
+    for size in TREE_SIZES {
+        let tree = synthetic_tree(size);
+        let json = serde_json::to_string(&tree).unwrap().len();
+        let outline = serialize_for_llm(&tree, &options).len();
+        let compact = serialize_for_llm(&tree, &LlmOptions::default()).len();
+        println!(
+            "{} elements: json {} bytes, outline {} bytes ({:.0}%), default outline {} bytes ({:.0}%)",
+            size,
+            json,
+            outline,
+            outline as f64 * 100.0 / json as f64,
+            compact,
+            compact as f64 * 100.0 / json as f64
+        );
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &tree, |b, tree| {
+            b.iter(|| serialize_for_llm(black_box(tree), &options))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_lookup");
+
+    for size in TREE_SIZES {
+        let tree = synthetic_tree(size);
+        let last = format!("ax_{}", size - 1);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &last, |b, id| {
+            b.iter(|| tree.find(black_box(id)).is_some())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_diff,
+    bench_serde,
+    bench_llm_outline,
+    bench_lookup
+);
+criterion_main!(benches);
@@ -0,0 +1,411 @@
+//! Dump, watch, search and drive an app's accessibility tree from a terminal
+//!
+//! ```text
+//! ax-snapshot dump --app "Google Chrome" --max-depth 4 --format json
+//! ax-snapshot dump --app Finder --format html -o finder.html
+//! ax-snapshot watch --app Finder
+//! ax-snapshot find --app Slack --role button --label send
+//! ax-snapshot act --app Slack --id 3f2a9c --action click
+//! ax-snapshot diff before.json after.json
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use screenpipe_core::operator::{
+    compare_snapshots, diff_snapshots, export_tree, render_snapshot_html, AutomationError, Bounds,
+    ComparedElement, Desktop, ElementSnapshot, Role, SnapshotComparison, SnapshotOptions,
+    TreeFormat, UIElement,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "ax-snapshot",
+    about = "Inspect accessibility trees without the app"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print one snapshot
+    Dump {
+        #[command(flatten)]
+        target: Target,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the changes between snapshots as JSON lines until interrupted
+    Watch {
+        #[command(flatten)]
+        target: Target,
+        /// Milliseconds between snapshots
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+    /// List the elements matching a role and label
+    Find {
+        #[command(flatten)]
+        target: Target,
+        /// Our role name like "text_field", or a platform one like "AXButton"
+        #[arg(long)]
+        role: Option<String>,
+        /// Case insensitive substring of the label
+        #[arg(long)]
+        label: Option<String>,
+    },
+    /// Perform an action on an element from a snapshot
+    Act {
+        #[command(flatten)]
+        target: Target,
+        /// Element ID as printed by dump or find
+        #[arg(long)]
+        id: String,
+        /// click, double-click, right-click, focus, hover or a platform action
+        /// name like "AXPress"
+        #[arg(long, default_value = "click")]
+        action: String,
+        /// Type this text into the element instead of performing `action`
+        #[arg(long)]
+        text: Option<String>,
+    },
+    /// Compare two snapshots saved with `dump --format json`
+    Diff {
+        before: PathBuf,
+        after: PathBuf,
+        /// Print the comparison as JSON instead of one line per element
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args)]
+struct Target {
+    /// Application name
+    #[arg(long)]
+    app: String,
+    /// Title of a window of the app, the whole app when missing
+    #[arg(long)]
+    window: Option<String>,
+    /// How many levels below the app or window to include
+    #[arg(long, default_value_t = 5)]
+    max_depth: usize,
+    /// Skip elements that lie outside of their window
+    #[arg(long)]
+    visible_only: bool,
+    /// Points around the window that still count as inside it
+    #[arg(long, default_value_t = 50.0)]
+    visible_margin: f64,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Text,
+    /// A page to open in a browser, see `render_snapshot_html`
+    Html,
+    /// Graphviz graph, see `export_tree`
+    Dot,
+    /// Mermaid graph, see `export_tree`
+    Mermaid,
+}
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            if let AutomationError::PermissionDenied(_) = e {
+                eprintln!("{}", PERMISSION_GUIDANCE);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+const PERMISSION_GUIDANCE: &str = "\
+This terminal is not allowed to use accessibility APIs.
+On macOS, open System Settings > Privacy & Security > Accessibility, enable
+the terminal app running ax-snapshot, then restart the terminal.";
+
+fn run(cli: Cli) -> Result<(), AutomationError> {
+    // Comparing saved files needs no accessibility access
+    let desktop = || Desktop::new(false, false);
+    match cli.command {
+        Command::Dump {
+            target,
+            format,
+            output,
+        } => {
+            let snapshot = capture(&desktop()?, &target)?;
+            let rendered = match format {
+                Format::Json => serde_json::to_string_pretty(&snapshot)
+                    .map_err(|e| AutomationError::Internal(e.to_string()))?,
+                Format::Text => render_tree(&snapshot),
+                Format::Html => render_snapshot_html(&snapshot),
+                Format::Dot => export_tree(&snapshot, TreeFormat::Dot, None, target.max_depth)?,
+                Format::Mermaid => {
+                    export_tree(&snapshot, TreeFormat::Mermaid, None, target.max_depth)?
+                }
+            };
+            match output {
+                Some(path) => fs::write(&path, rendered).map_err(|e| {
+                    AutomationError::PlatformError(format!(
+                        "Failed to write {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?,
+                None => println!("{}", rendered),
+            }
+        }
+        Command::Watch { target, interval } => {
+            let desktop = desktop()?;
+            let mut previous = capture(&desktop, &target)?;
+            println!("{}", json_line(&previous));
+            loop {
+                thread::sleep(Duration::from_millis(interval));
+                let snapshot = match capture(&desktop, &target) {
+                    Ok(snapshot) => snapshot,
+                    // The app may be busy or restarting, keep watching
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        continue;
+                    }
+                };
+                let diff = diff_snapshots(&previous, &snapshot);
+                if !diff.is_empty() {
+                    println!("{}", json_line(&diff));
+                }
+                previous = snapshot;
+            }
+        }
+        Command::Find {
+            target,
+            role,
+            label,
+        } => {
+            let snapshot = capture(&desktop()?, &target)?;
+            let role = role.map(|role| Role::from_native(&role));
+            let label = label.map(|label| label.to_lowercase());
+            for element in snapshot.iter() {
+                let role_matches = role.as_ref().is_none_or(|role| &element.role == role);
+                let label_matches = label.as_ref().is_none_or(|label| {
+                    element
+                        .label
+                        .as_ref()
+                        .is_some_and(|l| l.to_lowercase().contains(label))
+                });
+                if role_matches && label_matches {
+                    println!("{}", describe(element));
+                }
+            }
+        }
+        Command::Act {
+            target,
+            id,
+            action,
+            text,
+        } => {
+            let desktop = desktop()?;
+            let root = target_root(&desktop, &target)?;
+            // Walked like `dump` with the same options, so IDs it printed with
+            // a dedup suffix find the same element
+            let element = ElementSnapshot::find_live(&root, &capture_options(&target), &id)
+                .ok_or_else(|| {
+                    AutomationError::ElementNotFound(format!("No element with ID {}", id))
+                })?;
+            match text {
+                Some(text) => element.type_text(&text)?,
+                None => match action.as_str() {
+                    "click" => {
+                        element.click()?;
+                    }
+                    "double-click" => {
+                        element.double_click()?;
+                    }
+                    "right-click" => element.right_click()?,
+                    "focus" => element.focus()?,
+                    "hover" => element.hover()?,
+                    other => element.perform_action(other)?,
+                },
+            }
+        }
+        Command::Diff {
+            before,
+            after,
+            json,
+        } => {
+            let comparison = compare_snapshots(&read_snapshot(&before)?, &read_snapshot(&after)?);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&comparison)
+                        .map_err(|e| AutomationError::Internal(e.to_string()))?
+                );
+            } else if !comparison.is_empty() {
+                println!("{}", render_comparison(&comparison));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_snapshot(path: &Path) -> Result<ElementSnapshot, AutomationError> {
+    let invalid = |message: String| {
+        AutomationError::InvalidArgument(format!("{}: {}", path.display(), message))
+    };
+    let json = fs::read(path).map_err(|e| invalid(e.to_string()))?;
+    ElementSnapshot::from_json(&json).map_err(|e| invalid(e.to_string()))
+}
+
+fn target_root(desktop: &Desktop, target: &Target) -> Result<UIElement, AutomationError> {
+    let app = desktop.application(&target.app)?;
+    match &target.window {
+        Some(title) => app
+            .locator(format!("window:{}", title).as_str())?
+            .first()?
+            .ok_or_else(|| {
+                AutomationError::ElementNotFound(format!("No window titled '{}'", title))
+            }),
+        None => Ok(app),
+    }
+}
+
+fn capture_options(target: &Target) -> SnapshotOptions {
+    SnapshotOptions {
+        max_depth: target.max_depth,
+        visible_only: target.visible_only,
+        visible_margin: target.visible_margin,
+        ..SnapshotOptions::default()
+    }
+}
+
+fn capture(desktop: &Desktop, target: &Target) -> Result<ElementSnapshot, AutomationError> {
+    let root = target_root(desktop, target)?;
+    let (mut snapshot, stats) =
+        ElementSnapshot::capture_with_stats(&root, &capture_options(target));
+    for error in &stats.errors {
+        eprintln!(
+            "warning: children of {} missing: {}",
+            error.element, error.message
+        );
+    }
+    if let Ok(layout) = desktop.displays() {
+        snapshot.assign_displays(&layout);
+    }
+    Ok(snapshot)
+}
+
+fn describe(element: &ElementSnapshot) -> String {
+    let mut line = format!("{} {}", element.id, element.role);
+    if let Some(label) = &element.label {
+        line.push_str(&format!(" {:?}", label));
+    }
+    if element.redacted {
+        line.push_str(" = <redacted>");
+    } else if let Some(value) = &element.value {
+        line.push_str(&format!(" = {:?}", value));
+        if element.value_truncated {
+            line.push_str(" (truncated)");
+        }
+    }
+    if let Some(bounds) = &element.bounds {
+        line.push_str(&format!(
+            " @ {},{} {}x{}",
+            bounds.x, bounds.y, bounds.width, bounds.height
+        ));
+    }
+    line
+}
+
+fn render_tree(snapshot: &ElementSnapshot) -> String {
+    fn render(element: &ElementSnapshot, depth: usize, out: &mut String) {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(&describe(element));
+        if element.unresponsive {
+            out.push_str(" [unresponsive]");
+        }
+        out.push('\n');
+        for child in &element.children {
+            render(child, depth + 1, out);
+        }
+    }
+
+    let mut out = String::new();
+    render(snapshot, 0, &mut out);
+    out.pop();
+    out
+}
+
+// One line per element: + added, - removed, > moved, ~ changed
+fn render_comparison(comparison: &SnapshotComparison) -> String {
+    fn name(element: &ComparedElement) -> String {
+        match &element.label {
+            Some(label) => format!("{} {} {:?}", element.id, element.role, label),
+            None => format!("{} {}", element.id, element.role),
+        }
+    }
+    fn bounds(bounds: &Option<Bounds>) -> String {
+        bounds.map_or("none".to_string(), |b| {
+            format!("{},{} {}x{}", b.x, b.y, b.width, b.height)
+        })
+    }
+
+    let mut lines = Vec::new();
+    for element in &comparison.added {
+        lines.push(format!("+ {}", name(element)));
+    }
+    for element in &comparison.removed {
+        lines.push(format!("- {}", name(element)));
+    }
+    for moved in &comparison.moved {
+        let mut line = format!("> {}:", name(&moved.element));
+        if moved.old_parent != moved.new_parent {
+            line.push_str(&format!(
+                " parent {} -> {}",
+                moved.old_parent.as_deref().unwrap_or("none"),
+                moved.new_parent.as_deref().unwrap_or("none")
+            ));
+        }
+        if moved.old_bounds != moved.new_bounds {
+            line.push_str(&format!(
+                " bounds {} -> {}",
+                bounds(&moved.old_bounds),
+                bounds(&moved.new_bounds)
+            ));
+        }
+        lines.push(line);
+    }
+    for changed in &comparison.changed {
+        let fields: Vec<String> = changed
+            .fields
+            .iter()
+            .map(|(field, change)| format!("{} {} -> {}", field, change.old, change.new))
+            .collect();
+        lines.push(format!(
+            "~ {}: {}",
+            name(&changed.element),
+            fields.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
+
+fn json_line<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
@@ -0,0 +1,374 @@
+//! Running AppleScript and Shortcuts
+//!
+//! Apps like Mail or OmniFocus expose far more through their scripting
+//! dictionary than through accessibility. Scripts run through `osascript`,
+//! which prints the result in AppleScript source form, and that is converted
+//! to JSON.
+
+use crate::operator::AutomationError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// What to run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum AppleScriptSource {
+    /// AppleScript source, its `on run argv` handler gets the arguments
+    Source(String),
+    /// Name of a shortcut from the Shortcuts app, run with the arguments as
+    /// its input
+    Shortcut(String),
+}
+
+/// Run `source` with `args`, killing it after `timeout`
+///
+/// Returns the result converted to JSON: strings, numbers, booleans, lists
+/// and records map to their JSON counterparts, `missing value` to null, and
+/// anything else, like dates or object references, to its source text.
+///
+/// The first script sent to an app asks the user to allow it, a refusal is
+/// `AutomationError::AppleEventsDenied`.
+pub fn run_apple_script(
+    source: &AppleScriptSource,
+    args: &[String],
+    timeout: Duration,
+) -> Result<Value, AutomationError> {
+    let script = match source {
+        AppleScriptSource::Source(source) => source.clone(),
+        AppleScriptSource::Shortcut(name) => shortcut_script(name, !args.is_empty()),
+    };
+    let output = osascript(&script, args, timeout)?;
+    Ok(parse_result(output.trim_end_matches('\n')))
+}
+
+#[cfg(target_os = "macos")]
+fn osascript(script: &str, args: &[String], timeout: Duration) -> Result<String, AutomationError> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Instant;
+
+    let mut child = Command::new("osascript")
+        // Results in source form, so strings come back quoted
+        .args(["-s", "s", "-e", script])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AutomationError::PlatformError(format!("Failed to run osascript: {}", e)))?;
+
+    // Read while waiting, a full pipe would block the script
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut text = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut text);
+            }
+            text
+        })
+    };
+    let stdout = read(
+        child
+            .stdout
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+    );
+    let stderr = read(
+        child
+            .stderr
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+    );
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < timeout => thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(AutomationError::Timeout(format!(
+                    "script did not finish within {:?}",
+                    timeout
+                )));
+            }
+            Err(e) => return Err(AutomationError::PlatformError(e.to_string())),
+        }
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if status.success() {
+        Ok(stdout)
+    } else {
+        Err(script_error(stderr.trim()))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn osascript(
+    _script: &str,
+    _args: &[String],
+    _timeout: Duration,
+) -> Result<String, AutomationError> {
+    Err(AutomationError::UnsupportedPlatform(
+        "AppleScript is only available on macOS".to_string(),
+    ))
+}
+
+fn shortcut_script(name: &str, with_input: bool) -> String {
+    let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+    let input = if with_input { " with input argv" } else { "" };
+    format!(
+        "on run argv\ntell application \"Shortcuts Events\" to run shortcut \"{}\"{}\nend run",
+        name, input
+    )
+}
+
+// osascript reports errors as "<line>: execution error: <message> (<code>)"
+fn script_error(stderr: &str) -> AutomationError {
+    let code = stderr
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once('('))
+        .and_then(|(_, code)| code.parse::<i32>().ok());
+    let message = stderr.to_string();
+    match code {
+        // errAEEventNotPermitted, the user refused or was never asked
+        Some(-1743) => AutomationError::AppleEventsDenied(message),
+        // errAETimeout, the app did not answer
+        Some(-1712) => AutomationError::Timeout(message),
+        // errAEEventFailed for a missing object, errAENoSuchObject
+        Some(-1728) => AutomationError::ElementNotFound(message),
+        // Syntax errors
+        Some(-2740) | Some(-2741) => AutomationError::InvalidArgument(message),
+        _ => AutomationError::PlatformError(message),
+    }
+}
+
+/// Convert a result in AppleScript source form to JSON, the text itself when
+/// it is not well formed
+fn parse_result(text: &str) -> Value {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+    match parser.value() {
+        Some(value) if parser.at_end() => value,
+        _ => Value::String(text.to_string()),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.position == self.chars.len()
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match self.peek()? {
+            '"' => self.string().map(Value::String),
+            '{' => self.list_or_record(),
+            _ => Some(self.word()),
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.position += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek()? {
+                '"' => {
+                    self.position += 1;
+                    return Some(text);
+                }
+                '\\' => {
+                    self.position += 1;
+                    text.push(match self.peek()? {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                other => text.push(other),
+            }
+            self.position += 1;
+        }
+    }
+
+    // "{}" is read as an empty list
+    fn list_or_record(&mut self) -> Option<Value> {
+        self.position += 1;
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Some(Value::Array(Vec::new()));
+        }
+
+        let record = self.label_ahead().is_some();
+        let mut items = Vec::new();
+        let mut fields = Map::new();
+        loop {
+            if record {
+                let label = self.label_ahead()?;
+                self.position += label.chars().count() + 1;
+                fields.insert(label.trim_matches('|').trim().to_string(), self.value()?);
+            } else {
+                items.push(self.value()?);
+            }
+            self.skip_whitespace();
+            match self.peek()? {
+                ',' => self.position += 1,
+                '}' => {
+                    self.position += 1;
+                    break;
+                }
+                _ => return None,
+            }
+            self.skip_whitespace();
+        }
+        Some(if record {
+            Value::Object(fields)
+        } else {
+            Value::Array(items)
+        })
+    }
+
+    // The label of a record field starting here, like "name" in {name:"x"} or
+    // "|my key|", None when this is a list item
+    fn label_ahead(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let rest = &self.chars[self.position..];
+        let end = if rest.first() == Some(&'|') {
+            rest[1..].iter().position(|&c| c == '|')? + 2
+        } else {
+            rest.iter()
+                .position(|&c| matches!(c, ':' | ',' | '{' | '}' | '"'))?
+        };
+        (rest.get(end) == Some(&':') && end > 0).then(|| rest[..end].iter().collect())
+    }
+
+    // Numbers, booleans and missing value, otherwise the text up to the end of
+    // the item, such as `date "Monday, 1 January 2024"`
+    fn word(&mut self) -> Value {
+        let start = self.position;
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => {
+                    if self.string().is_none() {
+                        break;
+                    }
+                    continue;
+                }
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                ',' if depth == 0 => break,
+                _ => {}
+            }
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        let text = text.trim();
+
+        match text {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "missing value" => Value::Null,
+            _ => {
+                if let Ok(number) = text.parse::<i64>() {
+                    Value::from(number)
+                } else if let Some(number) = text.parse::<f64>().ok().filter(|n| n.is_finite()) {
+                    Value::from(number)
+                } else {
+                    Value::String(text.to_string())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parses_source_form() {
+        assert_eq!(parse_result("\"Inbox\""), json!("Inbox"));
+        assert_eq!(parse_result("\"say \\\"hi\\\"\\n\""), json!("say \"hi\"\n"));
+        assert_eq!(parse_result("42"), json!(42));
+        assert_eq!(parse_result("1.5E+3"), json!(1500.0));
+        assert_eq!(parse_result("true"), json!(true));
+        assert_eq!(parse_result("missing value"), json!(null));
+        assert_eq!(parse_result(""), json!(""));
+        assert_eq!(
+            parse_result("{\"a\", {1, 2}, {}, missing value}"),
+            json!(["a", [1, 2], [], null])
+        );
+        assert_eq!(
+            parse_result("{name:\"Inbox\", unread count:3, |my key|:{true}}"),
+            json!({"name": "Inbox", "unread count": 3, "my key": [true]})
+        );
+    }
+
+    #[test]
+    fn test_keeps_unknown_values_as_text() {
+        assert_eq!(
+            parse_result("{date \"Monday, 1 January 2024 at 10:00:00\", application \"Mail\"}"),
+            json!([
+                "date \"Monday, 1 January 2024 at 10:00:00\"",
+                "application \"Mail\""
+            ])
+        );
+        // Not well formed, returned whole
+        assert_eq!(parse_result("{1, 2"), json!("{1, 2"));
+        assert_eq!(parse_result("\"open"), json!("\"open"));
+    }
+
+    #[test]
+    fn test_classifies_errors() {
+        let error = |stderr: &str| script_error(stderr).kind();
+        assert_eq!(
+            error("0:42: execution error: Not authorized to send Apple events to Mail. (-1743)"),
+            "apple_events_denied"
+        );
+        assert_eq!(
+            error("0:10: execution error: Mail got an error: AppleEvent timed out. (-1712)"),
+            "timeout"
+        );
+        assert_eq!(
+            error("0:5: syntax error: Expected end of line but found identifier. (-2741)"),
+            "invalid_argument"
+        );
+        assert_eq!(error("something else"), "platform_error");
+    }
+
+    #[test]
+    fn test_shortcut_script_escapes_name() {
+        assert_eq!(
+            shortcut_script("Say \"hi\"", true),
+            "on run argv\ntell application \"Shortcuts Events\" to run shortcut \"Say \\\"hi\\\"\" with input argv\nend run"
+        );
+        assert!(!shortcut_script("Plain", false).contains("with input"));
+    }
+}
@@ -0,0 +1,462 @@
+//! Display layout and conversion between screen coordinate spaces
+//!
+//! Element bounds are in global coordinates: points from the top-left corner of
+//! the primary display, with y growing downwards. Displays placed left of or
+//! above the primary display have negative coordinates.
+//!
+//! Points only match pixels on displays with a scale factor of 1. Retina
+//! displays have two pixels per point, and a window straddling a Retina panel
+//! and an external display covers a different number of pixels on each, so
+//! converting to pixels always goes through a specific display.
+//!
+//! Cocoa window APIs measure from the bottom-left corner of the primary display
+//! with y growing upwards instead. Values crossing that boundary should go
+//! through `CocoaPoint` and the conversions on `DisplayLayout` rather than be
+//! passed around as bare numbers.
+
+use crate::operator::{AutomationError, Bounds};
+use serde::{Deserialize, Serialize};
+
+/// A display and the area it covers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DisplayInfo {
+    /// Platform ID of the display
+    pub id: u32,
+    /// Area covered by the display, in global coordinates
+    pub bounds: Bounds,
+    /// Whether this is the display global coordinates start from
+    pub primary: bool,
+    /// Pixels per point, 2 on Retina displays
+    pub scale_factor: f64,
+}
+
+/// Origin that a point is measured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "display", rename_all = "snake_case")]
+pub enum CoordinateSpace {
+    /// Points from the top-left corner of the primary display
+    Global,
+    /// Points from the top-left corner of the display with this ID
+    Display(u32),
+    /// Pixels from the top-left corner of the display with this ID
+    Physical(u32),
+}
+
+/// A point from the accessibility API, which already uses global coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A point from Cocoa window APIs, from the bottom-left corner of the primary
+/// display with y growing upwards
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CocoaPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A point in global coordinates, the space of element bounds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GlobalPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl From<AxPoint> for GlobalPoint {
+    fn from(point: AxPoint) -> Self {
+        Self {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+impl From<GlobalPoint> for AxPoint {
+    fn from(point: GlobalPoint) -> Self {
+        Self {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+/// Arrangement of the connected displays
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DisplayLayout {
+    pub displays: Vec<DisplayInfo>,
+}
+
+impl DisplayLayout {
+    pub fn new(displays: Vec<DisplayInfo>) -> Self {
+        Self { displays }
+    }
+
+    /// Find a display by ID
+    pub fn display(&self, id: u32) -> Option<&DisplayInfo> {
+        self.displays.iter().find(|display| display.id == id)
+    }
+
+    /// Display containing a point given in global coordinates
+    pub fn display_at(&self, x: f64, y: f64) -> Option<&DisplayInfo> {
+        self.displays.iter().find(|display| {
+            let b = display.bounds;
+            x >= b.x && x < b.x + b.width && y >= b.y && y < b.y + b.height
+        })
+    }
+
+    /// Display showing the largest part of a rectangle given in global coordinates
+    pub fn display_for(&self, bounds: &Bounds) -> Option<&DisplayInfo> {
+        self.displays
+            .iter()
+            .map(|display| (display, overlap_area(&display.bounds, bounds)))
+            .filter(|(_, area)| *area > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(display, _)| display)
+    }
+
+    /// Convert a point from one coordinate space to another
+    pub fn convert_point(
+        &self,
+        from: CoordinateSpace,
+        to: CoordinateSpace,
+        x: f64,
+        y: f64,
+    ) -> Result<(f64, f64), AutomationError> {
+        let (x, y) = match from {
+            CoordinateSpace::Global => (x, y),
+            CoordinateSpace::Display(id) => {
+                let display = self.require(id)?;
+                (x + display.bounds.x, y + display.bounds.y)
+            }
+            CoordinateSpace::Physical(id) => {
+                let display = self.require(id)?;
+                let scale = display.scale();
+                (x / scale + display.bounds.x, y / scale + display.bounds.y)
+            }
+        };
+        match to {
+            CoordinateSpace::Global => Ok((x, y)),
+            CoordinateSpace::Display(id) => {
+                let display = self.require(id)?;
+                Ok((x - display.bounds.x, y - display.bounds.y))
+            }
+            CoordinateSpace::Physical(id) => {
+                let display = self.require(id)?;
+                let scale = display.scale();
+                Ok((
+                    (x - display.bounds.x) * scale,
+                    (y - display.bounds.y) * scale,
+                ))
+            }
+        }
+    }
+
+    /// Convert a rectangle in global coordinates to pixels of the display
+    /// showing most of it
+    ///
+    /// Returns the display ID along with the rectangle, or None when the
+    /// rectangle is outside every display.
+    pub fn to_physical(&self, bounds: &Bounds) -> Option<(u32, Bounds)> {
+        let display = self.display_for(bounds)?;
+        let scale = display.scale();
+        Some((
+            display.id,
+            Bounds {
+                x: (bounds.x - display.bounds.x) * scale,
+                y: (bounds.y - display.bounds.y) * scale,
+                width: bounds.width * scale,
+                height: bounds.height * scale,
+            },
+        ))
+    }
+
+    /// Convert a point from Cocoa to global coordinates
+    pub fn from_cocoa(&self, point: CocoaPoint) -> Result<GlobalPoint, AutomationError> {
+        Ok(GlobalPoint {
+            x: point.x,
+            y: self.primary_height()? - point.y,
+        })
+    }
+
+    /// Convert a point from global to Cocoa coordinates
+    pub fn to_cocoa(&self, point: GlobalPoint) -> Result<CocoaPoint, AutomationError> {
+        Ok(CocoaPoint {
+            x: point.x,
+            y: self.primary_height()? - point.y,
+        })
+    }
+
+    /// Convert a Cocoa frame, whose origin is its bottom-left corner, to
+    /// global bounds
+    pub fn bounds_from_cocoa(&self, frame: &Bounds) -> Result<Bounds, AutomationError> {
+        // The frame's top edge becomes the origin
+        let top_left = self.from_cocoa(CocoaPoint {
+            x: frame.x,
+            y: frame.y + frame.height,
+        })?;
+        Ok(Bounds {
+            x: top_left.x,
+            y: top_left.y,
+            ..*frame
+        })
+    }
+
+    /// Convert global bounds to a Cocoa frame
+    pub fn bounds_to_cocoa(&self, bounds: &Bounds) -> Result<Bounds, AutomationError> {
+        let bottom_left = self.to_cocoa(GlobalPoint {
+            x: bounds.x,
+            y: bounds.y + bounds.height,
+        })?;
+        Ok(Bounds {
+            x: bottom_left.x,
+            y: bottom_left.y,
+            ..*bounds
+        })
+    }
+
+    // Cocoa flips y around the primary display's height, whichever display a
+    // point is on
+    fn primary_height(&self) -> Result<f64, AutomationError> {
+        self.displays
+            .iter()
+            .find(|display| display.primary)
+            .map(|display| display.bounds.height)
+            .ok_or_else(|| {
+                AutomationError::InvalidArgument(
+                    "display layout has no primary display".to_string(),
+                )
+            })
+    }
+
+    fn require(&self, id: u32) -> Result<&DisplayInfo, AutomationError> {
+        self.display(id)
+            .ok_or_else(|| AutomationError::InvalidArgument(format!("unknown display {}", id)))
+    }
+}
+
+impl DisplayInfo {
+    // Some virtual displays report no mode, treat them as 1x
+    fn scale(&self) -> f64 {
+        if self.scale_factor > 0.0 {
+            self.scale_factor
+        } else {
+            1.0
+        }
+    }
+}
+
+fn overlap_area(a: &Bounds, b: &Bounds) -> f64 {
+    let width = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+    let height = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+    if width > 0.0 && height > 0.0 {
+        width * height
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CoordinateSpace::{Display, Global, Physical};
+
+    fn display(id: u32, bounds: (f64, f64, f64, f64)) -> DisplayInfo {
+        DisplayInfo {
+            id,
+            bounds: Bounds::from(bounds),
+            primary: id == 1,
+            scale_factor: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_secondary_left_of_primary() {
+        let layout = DisplayLayout::new(vec![
+            display(1, (0.0, 0.0, 1512.0, 982.0)),
+            display(2, (-1920.0, 0.0, 1920.0, 1080.0)),
+        ]);
+
+        assert_eq!(layout.display_at(-10.0, 500.0).map(|d| d.id), Some(2));
+        assert_eq!(layout.display_at(10.0, 500.0).map(|d| d.id), Some(1));
+        assert_eq!(
+            layout
+                .convert_point(Global, Display(2), -1900.0, 40.0)
+                .unwrap(),
+            (20.0, 40.0)
+        );
+        assert_eq!(
+            layout
+                .convert_point(Display(2), Global, 20.0, 40.0)
+                .unwrap(),
+            (-1900.0, 40.0)
+        );
+    }
+
+    #[test]
+    fn test_vertically_stacked_displays() {
+        let layout = DisplayLayout::new(vec![
+            display(1, (0.0, 0.0, 1440.0, 900.0)),
+            display(2, (-240.0, -1080.0, 1920.0, 1080.0)),
+        ]);
+
+        assert_eq!(layout.display_at(100.0, -1.0).map(|d| d.id), Some(2));
+        assert_eq!(layout.display_at(100.0, 0.0).map(|d| d.id), Some(1));
+        assert_eq!(
+            layout
+                .convert_point(Display(2), Display(1), 240.0, 1080.0)
+                .unwrap(),
+            (0.0, 0.0)
+        );
+        assert!(layout.display_at(1500.0, 10.0).is_none());
+    }
+
+    #[test]
+    fn test_mixed_display_sizes() {
+        // A laptop panel next to a larger external display, top edges aligned
+        let layout = DisplayLayout::new(vec![
+            display(1, (0.0, 0.0, 1512.0, 982.0)),
+            display(2, (1512.0, -200.0, 2560.0, 1440.0)),
+        ]);
+
+        // A window straddling both belongs to the one showing more of it
+        let straddling = Bounds::from((1400.0, 100.0, 400.0, 300.0));
+        assert_eq!(layout.display_for(&straddling).map(|d| d.id), Some(2));
+        let mostly_laptop = Bounds::from((1200.0, 100.0, 400.0, 300.0));
+        assert_eq!(layout.display_for(&mostly_laptop).map(|d| d.id), Some(1));
+        let offscreen = Bounds::from((-500.0, 0.0, 100.0, 100.0));
+        assert!(layout.display_for(&offscreen).is_none());
+    }
+
+    #[test]
+    fn test_retina_laptop_with_external_display() {
+        let mut laptop = display(1, (0.0, 0.0, 1512.0, 982.0));
+        laptop.scale_factor = 2.0;
+        let layout = DisplayLayout::new(vec![laptop, display(2, (1512.0, 0.0, 1920.0, 1080.0))]);
+
+        // A window straddling both, mostly on the laptop
+        let window = Bounds::from((1212.0, 100.0, 400.0, 300.0));
+        let (id, physical) = layout.to_physical(&window).unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(physical, Bounds::from((2424.0, 200.0, 800.0, 600.0)));
+
+        // Its right edge lies on the external display, at 1x
+        assert_eq!(
+            layout
+                .convert_point(Global, Physical(2), 1612.0, 400.0)
+                .unwrap(),
+            (100.0, 400.0)
+        );
+        // and the same point seen from the laptop is off its right edge, at 2x
+        assert_eq!(
+            layout
+                .convert_point(Global, Physical(1), 1612.0, 400.0)
+                .unwrap(),
+            (3224.0, 800.0)
+        );
+        assert_eq!(
+            layout
+                .convert_point(Physical(1), Physical(2), 3224.0, 800.0)
+                .unwrap(),
+            (100.0, 400.0)
+        );
+    }
+
+    #[test]
+    fn test_missing_scale_factor_counts_as_1x() {
+        let mut virtual_display = display(1, (0.0, 0.0, 800.0, 600.0));
+        virtual_display.scale_factor = 0.0;
+        let layout = DisplayLayout::new(vec![virtual_display]);
+        assert_eq!(
+            layout
+                .convert_point(Global, Physical(1), 10.0, 20.0)
+                .unwrap(),
+            (10.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn test_cocoa_flip_known_values() {
+        let layout = DisplayLayout::new(vec![
+            display(1, (0.0, 0.0, 1512.0, 982.0)),
+            display(2, (-200.0, -1080.0, 1920.0, 1080.0)),
+        ]);
+
+        // The top-left corner of the primary display is its Cocoa height
+        let top_left = layout.to_cocoa(AxPoint { x: 0.0, y: 0.0 }.into()).unwrap();
+        assert_eq!(top_left, CocoaPoint { x: 0.0, y: 982.0 });
+        // The display above has Cocoa coordinates beyond the primary height
+        let above = layout.to_cocoa(GlobalPoint { x: 0.0, y: -1080.0 }).unwrap();
+        assert_eq!(above, CocoaPoint { x: 0.0, y: 2062.0 });
+
+        // A 100pt tall window at the top of the primary display
+        let frame = Bounds::from((10.0, 882.0, 300.0, 100.0));
+        assert_eq!(
+            layout.bounds_from_cocoa(&frame).unwrap(),
+            Bounds::from((10.0, 0.0, 300.0, 100.0))
+        );
+    }
+
+    #[test]
+    fn test_cocoa_round_trips_across_layouts() {
+        let layouts = [
+            vec![display(1, (0.0, 0.0, 1512.0, 982.0))],
+            vec![
+                display(1, (0.0, 0.0, 1440.0, 900.0)),
+                display(2, (-1920.0, 300.0, 1920.0, 1080.0)),
+            ],
+            vec![
+                display(2, (-240.0, -1080.0, 1920.0, 1080.0)),
+                display(1, (0.0, 0.0, 2560.0, 1440.0)),
+                display(3, (2560.0, 1440.0, 1024.0, 768.0)),
+            ],
+        ];
+
+        for layout in layouts.into_iter().map(DisplayLayout::new) {
+            for x in (-2000..4000).step_by(137) {
+                for y in (-1500..2500).step_by(89) {
+                    let point = GlobalPoint {
+                        x: x as f64 + 0.5,
+                        y: y as f64 + 0.25,
+                    };
+                    let cocoa = layout.to_cocoa(point).unwrap();
+                    assert_eq!(cocoa.x, point.x);
+                    assert_eq!(layout.from_cocoa(cocoa).unwrap(), point);
+
+                    let bounds = Bounds::from((point.x, point.y, 120.0, 45.5));
+                    let frame = layout.bounds_to_cocoa(&bounds).unwrap();
+                    // Flipping y swaps which edge is the origin
+                    assert_eq!(frame.y + frame.height, cocoa.y);
+                    assert_eq!(layout.bounds_from_cocoa(&frame).unwrap(), bounds);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cocoa_needs_primary_display() {
+        let mut external = display(2, (0.0, 0.0, 100.0, 100.0));
+        external.primary = false;
+        let layout = DisplayLayout::new(vec![external]);
+        assert!(layout.from_cocoa(CocoaPoint { x: 0.0, y: 0.0 }).is_err());
+    }
+
+    #[test]
+    fn test_unknown_display() {
+        let layout = DisplayLayout::new(vec![display(1, (0.0, 0.0, 100.0, 100.0))]);
+        assert!(matches!(
+            layout.convert_point(Global, Display(7), 0.0, 0.0),
+            Err(AutomationError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_coordinate_space_serialization() {
+        let space: CoordinateSpace =
+            serde_json::from_str(r#"{"kind": "display", "display": 2}"#).unwrap();
+        assert_eq!(space, Display(2));
+        let space: CoordinateSpace = serde_json::from_str(r#"{"kind": "global"}"#).unwrap();
+        assert_eq!(space, Global);
+    }
+}
@@ -0,0 +1,959 @@
+//! Structural diffs between element snapshots
+//!
+//! Diffs are keyed by element ID so a client holding a full copy of a tree can
+//! stay in sync by applying small updates instead of receiving the whole tree
+//! again after every change.
+
+use crate::operator::snapshot::{Bounds, ElementSnapshot};
+use crate::operator::{AutomationError, Role};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// A single change between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SnapshotChange {
+    /// A subtree that is new or was moved to another parent. Without a parent
+    /// the element replaces the root
+    Added {
+        parent_id: Option<String>,
+        index: usize,
+        element: ElementSnapshot,
+    },
+    /// A subtree that is gone from its previous position
+    Removed { id: String },
+    /// Fields of an element that changed, with their new values
+    Changed {
+        id: String,
+        fields: BTreeMap<String, Value>,
+    },
+    /// The children of an element were reordered
+    Reordered { id: String, children: Vec<String> },
+}
+
+/// Ordered list of changes turning one snapshot into another
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub changes: Vec<SnapshotChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// What to send a client that already has the previous snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum SnapshotUpdate {
+    Full(ElementSnapshot),
+    Diff(SnapshotDiff),
+    /// Nothing changed since the previous snapshot
+    Unchanged,
+}
+
+/// Compute the changes turning `old` into `new`
+pub fn diff_snapshots(old: &ElementSnapshot, new: &ElementSnapshot) -> SnapshotDiff {
+    if old.id != new.id {
+        return SnapshotDiff {
+            changes: vec![SnapshotChange::Added {
+                parent_id: None,
+                index: 0,
+                element: new.clone(),
+            }],
+        };
+    }
+
+    let old_index = index_tree(old);
+    let new_index = index_tree(new);
+
+    // Identical subtrees hash the same, so an unchanged tree costs one lookup
+    if old_index[old.id.as_str()].hash == new_index[new.id.as_str()].hash {
+        return SnapshotDiff::default();
+    }
+
+    let mut changes = Vec::new();
+    collect_removed(old, &old_index, &new_index, &mut changes);
+    collect_added_and_changed(new, &old_index, &new_index, &mut changes);
+
+    SnapshotDiff { changes }
+}
+
+/// Apply a diff produced by [`diff_snapshots`] to a copy of the old snapshot
+pub fn apply_diff(base: &mut ElementSnapshot, diff: &SnapshotDiff) -> Result<(), AutomationError> {
+    for change in &diff.changes {
+        match change {
+            SnapshotChange::Removed { id } => {
+                if base.id == *id {
+                    return Err(AutomationError::InvalidArgument(
+                        "cannot remove the root of a snapshot".to_string(),
+                    ));
+                }
+                base.remove_descendant(id).ok_or_else(|| {
+                    AutomationError::ElementNotFound(format!("element '{}' not in snapshot", id))
+                })?;
+            }
+            SnapshotChange::Added {
+                parent_id: None,
+                element,
+                ..
+            } => {
+                *base = element.clone();
+            }
+            SnapshotChange::Added {
+                parent_id: Some(parent_id),
+                index,
+                element,
+            } => {
+                let parent = find_or_err(base, parent_id)?;
+                let index = (*index).min(parent.children.len());
+                parent.children.insert(index, element.clone());
+            }
+            SnapshotChange::Changed { id, fields } => {
+                let element = find_or_err(base, id)?;
+                for (name, value) in fields {
+                    set_field(element, name, value)?;
+                }
+            }
+            SnapshotChange::Reordered { id, children } => {
+                let element = find_or_err(base, id)?;
+                let order: HashMap<&str, usize> = children
+                    .iter()
+                    .enumerate()
+                    .map(|(position, id)| (id.as_str(), position))
+                    .collect();
+                element.children.sort_by_key(|child| {
+                    order.get(child.id.as_str()).copied().unwrap_or(usize::MAX)
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Pick the smaller of a diff and a full copy of `current`, or report that
+/// nothing changed
+///
+/// Callers streaming updates should still send a full snapshot every now and
+/// then so clients that missed an update can resync.
+pub fn snapshot_update(
+    previous: Option<&ElementSnapshot>,
+    current: &ElementSnapshot,
+) -> SnapshotUpdate {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return SnapshotUpdate::Full(current.clone()),
+    };
+
+    if previous.content_hash() == current.content_hash() {
+        return SnapshotUpdate::Unchanged;
+    }
+
+    let diff = diff_snapshots(previous, current);
+    let diff_size = serde_json::to_vec(&diff).map_or(usize::MAX, |bytes| bytes.len());
+    let full_size = serde_json::to_vec(current).map_or(0, |bytes| bytes.len());
+
+    if diff_size >= full_size {
+        SnapshotUpdate::Full(current.clone())
+    } else {
+        SnapshotUpdate::Diff(diff)
+    }
+}
+
+/// The element a comparison entry is about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparedElement {
+    pub id: String,
+    pub role: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl From<&ElementSnapshot> for ComparedElement {
+    fn from(element: &ElementSnapshot) -> Self {
+        Self {
+            id: element.id.clone(),
+            role: element.role.clone(),
+            label: element.label.clone(),
+        }
+    }
+}
+
+/// An element with a new parent or new bounds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovedElement {
+    #[serde(flatten)]
+    pub element: ComparedElement,
+    /// ID of the parent, None for the root
+    pub old_parent: Option<String>,
+    pub new_parent: Option<String>,
+    pub old_bounds: Option<Bounds>,
+    pub new_bounds: Option<Bounds>,
+}
+
+/// Old and new value of a snapshot field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub old: Value,
+    pub new: Value,
+}
+
+/// An element with fields other than its bounds changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangedElement {
+    #[serde(flatten)]
+    pub element: ComparedElement,
+    pub fields: BTreeMap<String, FieldChange>,
+}
+
+/// Per element summary of what differs between two snapshots, for people
+/// rather than for patching a copy like [`SnapshotDiff`]
+///
+/// An element in both snapshots under another parent or at other bounds is
+/// moved, not removed and added again. Elements are keyed by ID, so an app
+/// that gives the same control a new ID shows it as removed and added.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotComparison {
+    pub added: Vec<ComparedElement>,
+    pub removed: Vec<ComparedElement>,
+    pub moved: Vec<MovedElement>,
+    pub changed: Vec<ChangedElement>,
+}
+
+impl SnapshotComparison {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+/// Compare `old` and `new` element by element
+///
+/// Built from [`diff_snapshots`], so it sees the same changes as streamed
+/// updates. Children that only changed order without moving are not reported.
+pub fn compare_snapshots(old: &ElementSnapshot, new: &ElementSnapshot) -> SnapshotComparison {
+    let old_index = index_parents(old);
+    let new_index = index_parents(new);
+    let mut comparison = SnapshotComparison::default();
+
+    let mut compare = |old: &ElementSnapshot,
+                       old_parent: Option<&str>,
+                       new: &ElementSnapshot,
+                       new_parent: Option<&str>| {
+        let mut fields = changed_fields(old, new);
+        let moved = fields.remove("bounds").is_some() || old_parent != new_parent;
+        if moved {
+            comparison.moved.push(MovedElement {
+                element: new.into(),
+                old_parent: old_parent.map(str::to_string),
+                new_parent: new_parent.map(str::to_string),
+                old_bounds: old.bounds,
+                new_bounds: new.bounds,
+            });
+        }
+        if !fields.is_empty() {
+            let mut old_fields = changed_fields(new, old);
+            comparison.changed.push(ChangedElement {
+                element: new.into(),
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let old = old_fields.remove(&name).unwrap_or(Value::Null);
+                        (name, FieldChange { old, new: value })
+                    })
+                    .collect(),
+            });
+        }
+    };
+
+    for change in diff_snapshots(old, new).changes {
+        match change {
+            // Elements of the subtree still in the new tree were moved, and
+            // are reported where they were added
+            SnapshotChange::Removed { id } => {
+                let Some((subtree, _)) = old_index.get(id.as_str()) else {
+                    continue;
+                };
+                comparison.removed.extend(
+                    subtree
+                        .iter()
+                        .filter(|element| !new_index.contains_key(element.id.as_str()))
+                        .map(ComparedElement::from),
+                );
+            }
+            SnapshotChange::Added { element, .. } => {
+                for element in element.iter() {
+                    let new_parent = new_index
+                        .get(element.id.as_str())
+                        .and_then(|(_, parent)| *parent);
+                    match old_index.get(element.id.as_str()) {
+                        Some((old, old_parent)) => compare(old, *old_parent, element, new_parent),
+                        None => comparison.added.push(element.into()),
+                    }
+                }
+            }
+            SnapshotChange::Changed { id, .. } => {
+                if let (Some((old, parent)), Some((new, _))) =
+                    (old_index.get(id.as_str()), new_index.get(id.as_str()))
+                {
+                    compare(old, *parent, new, *parent);
+                }
+            }
+            SnapshotChange::Reordered { .. } => {}
+        }
+    }
+
+    // A new root replaces the whole tree without removing the old one
+    if old.id != new.id {
+        comparison.removed.extend(
+            old.iter()
+                .filter(|element| !new_index.contains_key(element.id.as_str()))
+                .map(ComparedElement::from),
+        );
+    }
+
+    comparison
+}
+
+fn index_parents(root: &ElementSnapshot) -> HashMap<&str, (&ElementSnapshot, Option<&str>)> {
+    let mut index = HashMap::new();
+    let mut stack = vec![(root, None)];
+    while let Some((element, parent_id)) = stack.pop() {
+        index.insert(element.id.as_str(), (element, parent_id));
+        for child in &element.children {
+            stack.push((child, Some(element.id.as_str())));
+        }
+    }
+    index
+}
+
+/// A change a user would notice, found by comparing two snapshots
+///
+/// Focus and selection are not part of snapshots, so there are no events for
+/// them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum SnapshotEvent {
+    WindowOpened {
+        id: String,
+        label: Option<String>,
+    },
+    WindowClosed {
+        id: String,
+        label: Option<String>,
+    },
+    ElementValueChanged {
+        id: String,
+        role: Role,
+        value: Option<String>,
+    },
+}
+
+impl SnapshotEvent {
+    /// Name of the event, e.g. "window-opened"
+    pub fn name(&self) -> &'static str {
+        match self {
+            SnapshotEvent::WindowOpened { .. } => "window-opened",
+            SnapshotEvent::WindowClosed { .. } => "window-closed",
+            SnapshotEvent::ElementValueChanged { .. } => "element-value-changed",
+        }
+    }
+}
+
+/// Windows that opened or closed and values that changed between `old` and
+/// `new`
+///
+/// Closed windows come first, then the rest in pre-order of `new`. Elements
+/// that only moved to another parent are not reported.
+pub fn snapshot_events(old: &ElementSnapshot, new: &ElementSnapshot) -> Vec<SnapshotEvent> {
+    let old_index: HashMap<&str, &ElementSnapshot> = old
+        .iter()
+        .map(|element| (element.id.as_str(), element))
+        .collect();
+    let new_index: HashMap<&str, &ElementSnapshot> = new
+        .iter()
+        .map(|element| (element.id.as_str(), element))
+        .collect();
+
+    let mut events: Vec<SnapshotEvent> = old
+        .iter()
+        .filter(|element| {
+            element.role == Role::Window && !new_index.contains_key(element.id.as_str())
+        })
+        .map(|element| SnapshotEvent::WindowClosed {
+            id: element.id.clone(),
+            label: element.label.clone(),
+        })
+        .collect();
+
+    for element in new.iter() {
+        match old_index.get(element.id.as_str()) {
+            None if element.role == Role::Window => events.push(SnapshotEvent::WindowOpened {
+                id: element.id.clone(),
+                label: element.label.clone(),
+            }),
+            Some(previous) if previous.value != element.value => {
+                events.push(SnapshotEvent::ElementValueChanged {
+                    id: element.id.clone(),
+                    role: element.role.clone(),
+                    value: element.value.clone(),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+struct IndexedElement<'a> {
+    element: &'a ElementSnapshot,
+    parent_id: Option<&'a str>,
+    hash: u64,
+}
+
+impl IndexedElement<'_> {
+    // Nothing below this element changed, it can be skipped entirely
+    fn unchanged_in(&self, other: &HashMap<&str, IndexedElement<'_>>) -> bool {
+        other
+            .get(self.element.id.as_str())
+            .is_some_and(|other| other.parent_id == self.parent_id && other.hash == self.hash)
+    }
+}
+
+fn index_tree(root: &ElementSnapshot) -> HashMap<&str, IndexedElement<'_>> {
+    let mut hashes = root.subtree_hashes();
+    let mut index = HashMap::new();
+    let mut stack = vec![(root, None)];
+    while let Some((element, parent_id)) = stack.pop() {
+        let hash = hashes.remove(element.id.as_str()).unwrap_or_default();
+        index.insert(
+            element.id.as_str(),
+            IndexedElement {
+                element,
+                parent_id,
+                hash,
+            },
+        );
+        for child in &element.children {
+            stack.push((child, Some(element.id.as_str())));
+        }
+    }
+    index
+}
+
+// An element counts as removed when it no longer exists under the same parent;
+// descendants of a removed element go with it
+fn collect_removed(
+    root: &ElementSnapshot,
+    old_index: &HashMap<&str, IndexedElement<'_>>,
+    new_index: &HashMap<&str, IndexedElement<'_>>,
+    changes: &mut Vec<SnapshotChange>,
+) {
+    // Explicit stack, snapshots can be deeper than the call stack allows
+    let mut stack: Vec<(&ElementSnapshot, Option<&str>, bool)> = vec![(root, None, false)];
+    while let Some((element, parent_id, ancestor_removed)) = stack.pop() {
+        if !ancestor_removed && old_index[element.id.as_str()].unchanged_in(new_index) {
+            continue;
+        }
+
+        let removed = !ancestor_removed
+            && new_index
+                .get(element.id.as_str())
+                .is_none_or(|new| new.parent_id != parent_id);
+
+        if removed {
+            changes.push(SnapshotChange::Removed {
+                id: element.id.clone(),
+            });
+        }
+
+        for child in element.children.iter().rev() {
+            stack.push((
+                child,
+                Some(element.id.as_str()),
+                ancestor_removed || removed,
+            ));
+        }
+    }
+}
+
+// Walks the new tree in pre-order so that, when applied in sequence, every
+// insertion index refers to siblings that are already in place
+fn collect_added_and_changed(
+    root: &ElementSnapshot,
+    old_index: &HashMap<&str, IndexedElement<'_>>,
+    new_index: &HashMap<&str, IndexedElement<'_>>,
+    changes: &mut Vec<SnapshotChange>,
+) {
+    // Children are pushed in reverse so they come off the stack in order
+    let mut stack: Vec<(&ElementSnapshot, Option<&str>, usize)> = vec![(root, None, 0)];
+    while let Some((element, parent_id, index)) = stack.pop() {
+        let old = old_index
+            .get(element.id.as_str())
+            .filter(|old| old.parent_id == parent_id);
+
+        let old = match old {
+            Some(_) if new_index[element.id.as_str()].unchanged_in(old_index) => continue,
+            Some(old) => old.element,
+            None => {
+                changes.push(SnapshotChange::Added {
+                    parent_id: parent_id.map(str::to_string),
+                    index,
+                    element: element.clone(),
+                });
+                continue;
+            }
+        };
+
+        let fields = changed_fields(old, element);
+        if !fields.is_empty() {
+            changes.push(SnapshotChange::Changed {
+                id: element.id.clone(),
+                fields,
+            });
+        }
+
+        // Only compare children present in both trees, additions and removals
+        // are already covered by their own changes
+        let kept_old: Vec<&str> = old
+            .children
+            .iter()
+            .map(|child| child.id.as_str())
+            .filter(|id| {
+                new_index
+                    .get(id)
+                    .is_some_and(|new| new.parent_id == Some(element.id.as_str()))
+            })
+            .collect();
+        let kept_new: Vec<&str> = element
+            .children
+            .iter()
+            .map(|child| child.id.as_str())
+            .filter(|id| {
+                old_index
+                    .get(id)
+                    .is_some_and(|old| old.parent_id == Some(element.id.as_str()))
+            })
+            .collect();
+        if kept_old != kept_new {
+            changes.push(SnapshotChange::Reordered {
+                id: element.id.clone(),
+                children: element.children.iter().map(|c| c.id.clone()).collect(),
+            });
+        }
+
+        for (child_index, child) in element.children.iter().enumerate().rev() {
+            stack.push((child, Some(element.id.as_str()), child_index));
+        }
+    }
+}
+
+fn changed_fields(old: &ElementSnapshot, new: &ElementSnapshot) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+
+    if old.role != new.role {
+        fields.insert("role".to_string(), serde_json::json!(new.role));
+    }
+    if old.native_role != new.native_role {
+        fields.insert(
+            "native_role".to_string(),
+            Value::String(new.native_role.clone()),
+        );
+    }
+    if old.label != new.label {
+        fields.insert("label".to_string(), serde_json::json!(new.label));
+    }
+    if old.value != new.value {
+        fields.insert("value".to_string(), serde_json::json!(new.value));
+    }
+    if old.value_truncated != new.value_truncated {
+        fields.insert(
+            "value_truncated".to_string(),
+            Value::Bool(new.value_truncated),
+        );
+    }
+    if old.original_value_length != new.original_value_length {
+        fields.insert(
+            "original_value_length".to_string(),
+            serde_json::json!(new.original_value_length),
+        );
+    }
+    if old.description != new.description {
+        fields.insert(
+            "description".to_string(),
+            serde_json::json!(new.description),
+        );
+    }
+    if old.redacted != new.redacted {
+        fields.insert("redacted".to_string(), Value::Bool(new.redacted));
+    }
+    if old.bounds != new.bounds {
+        fields.insert("bounds".to_string(), serde_json::json!(new.bounds));
+    }
+    if old.display != new.display {
+        fields.insert("display".to_string(), serde_json::json!(new.display));
+    }
+    if old.unresponsive != new.unresponsive {
+        fields.insert("unresponsive".to_string(), Value::Bool(new.unresponsive));
+    }
+    if old.collapsed != new.collapsed {
+        fields.insert("collapsed".to_string(), serde_json::json!(new.collapsed));
+    }
+
+    fields
+}
+
+fn set_field(
+    element: &mut ElementSnapshot,
+    name: &str,
+    value: &Value,
+) -> Result<(), AutomationError> {
+    let invalid = |e: serde_json::Error| {
+        AutomationError::InvalidArgument(format!("invalid value for field '{}': {}", name, e))
+    };
+
+    match name {
+        "role" => element.role = serde_json::from_value(value.clone()).map_err(invalid)?,
+        "native_role" => {
+            element.native_role = serde_json::from_value(value.clone()).map_err(invalid)?
+        }
+        "label" => element.label = serde_json::from_value(value.clone()).map_err(invalid)?,
+        "value" => element.value = serde_json::from_value(value.clone()).map_err(invalid)?,
+        "value_truncated" => {
+            element.value_truncated = serde_json::from_value(value.clone()).map_err(invalid)?
+        }
+        "original_value_length" => {
+            element.original_value_length =
+                serde_json::from_value(value.clone()).map_err(invalid)?
+        }
+        "description" => {
+            element.description = serde_json::from_value(value.clone()).map_err(invalid)?
+        }
+        "redacted" => element.redacted = serde_json::from_value(value.clone()).map_err(invalid)?,
+        "bounds" => element.bounds = serde_json::from_value(value.clone()).map_err(invalid)?,
+        "display" => element.display = serde_json::from_value(value.clone()).map_err(invalid)?,
+        "unresponsive" => {
+            element.unresponsive = serde_json::from_value(value.clone()).map_err(invalid)?
+        }
+        "collapsed" => {
+            element.collapsed = serde_json::from_value(value.clone()).map_err(invalid)?
+        }
+        _ => {
+            return Err(AutomationError::InvalidArgument(format!(
+                "unknown snapshot field '{}'",
+                name
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+fn find_or_err<'a>(
+    base: &'a mut ElementSnapshot,
+    id: &str,
+) -> Result<&'a mut ElementSnapshot, AutomationError> {
+    base.find_mut(id).ok_or_else(|| {
+        AutomationError::ElementNotFound(format!("element '{}' not in snapshot", id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::Role;
+
+    fn node(id: &str, label: &str, children: Vec<ElementSnapshot>) -> ElementSnapshot {
+        let mut node = ElementSnapshot::new(id, Role::Group);
+        node.label = Some(label.to_string());
+        node.children = children;
+        node
+    }
+
+    // Built bottom up, one level per iteration
+    fn chain(depth: usize) -> ElementSnapshot {
+        let mut element = node(&format!("n{}", depth - 1), "", vec![]);
+        for level in (0..depth - 1).rev() {
+            element = node(&format!("n{}", level), "", vec![element]);
+        }
+        element
+    }
+
+    fn assert_round_trip(old: &ElementSnapshot, new: &ElementSnapshot) {
+        let diff = diff_snapshots(old, new);
+        let mut patched = old.clone();
+        apply_diff(&mut patched, &diff).unwrap();
+        assert_eq!(&patched, new);
+    }
+
+    #[test]
+    fn test_identical_trees_have_empty_diff() {
+        let tree = node("root", "r", vec![node("a", "a", vec![])]);
+        assert!(diff_snapshots(&tree, &tree).is_empty());
+    }
+
+    #[test]
+    fn test_changed_attribute_names() {
+        let old = node("root", "r", vec![node("a", "a", vec![])]);
+        let mut new = old.clone();
+        new.children[0].value = Some("hello".to_string());
+
+        let diff = diff_snapshots(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        match &diff.changes[0] {
+            SnapshotChange::Changed { id, fields } => {
+                assert_eq!(id, "a");
+                assert_eq!(fields.keys().collect::<Vec<_>>(), vec!["value"]);
+            }
+            other => panic!("unexpected change: {:?}", other),
+        }
+        assert_round_trip(&old, &new);
+    }
+
+    #[test]
+    fn test_added_removed_and_reordered_round_trip() {
+        let old = node(
+            "root",
+            "r",
+            vec![
+                node("a", "a", vec![node("a1", "a1", vec![])]),
+                node("b", "b", vec![]),
+                node("c", "c", vec![]),
+            ],
+        );
+        let new = node(
+            "root",
+            "r",
+            vec![
+                node("c", "c", vec![node("c1", "c1", vec![])]),
+                node("x", "x", vec![]),
+                node("a", "a changed", vec![]),
+            ],
+        );
+        assert_round_trip(&old, &new);
+    }
+
+    #[test]
+    fn test_moved_subtree_round_trip() {
+        let old = node(
+            "root",
+            "r",
+            vec![
+                node(
+                    "a",
+                    "a",
+                    vec![node("m", "m", vec![node("m1", "m1", vec![])])],
+                ),
+                node("b", "b", vec![]),
+            ],
+        );
+        let new = node(
+            "root",
+            "r",
+            vec![
+                node("a", "a", vec![]),
+                node(
+                    "b",
+                    "b",
+                    vec![node("m", "m", vec![node("m1", "m1", vec![])])],
+                ),
+            ],
+        );
+        assert_round_trip(&old, &new);
+    }
+
+    #[test]
+    fn test_deep_chain_round_trip() {
+        let old = chain(10_000);
+        let mut new = chain(10_000);
+        let deepest = new.find_mut("n9999").unwrap();
+        deepest.label = Some("changed".to_string());
+        deepest.children.push(node("leaf", "leaf", vec![]));
+
+        let diff = diff_snapshots(&old, &new);
+        assert_eq!(diff.changes.len(), 2);
+
+        // Compared by hash, the derived Clone and PartialEq recurse
+        let mut patched = chain(10_000);
+        apply_diff(&mut patched, &diff).unwrap();
+        assert_eq!(patched.content_hash(), new.content_hash());
+    }
+
+    #[test]
+    fn test_collapsed_group_becoming_informative_round_trip() {
+        let mut old = node(
+            "root",
+            "r",
+            vec![node("wrapper", "", vec![node("button", "b", vec![])])],
+        );
+        old.children[0].label = None;
+        old.collapse_groups();
+        assert!(old.find("wrapper").is_none());
+
+        let new = node(
+            "root",
+            "r",
+            vec![node("wrapper", "Send", vec![node("button", "b", vec![])])],
+        );
+        assert_round_trip(&old, &new);
+        assert_round_trip(&new, &old);
+    }
+
+    #[test]
+    fn test_new_root_replaces_tree() {
+        let old = node("root", "r", vec![]);
+        let new = node("other", "o", vec![node("a", "a", vec![])]);
+        assert_round_trip(&old, &new);
+    }
+
+    #[test]
+    fn test_update_falls_back_to_full_snapshot() {
+        let old = node("root", "r", vec![]);
+        let new = node("other", "o", vec![]);
+        assert!(matches!(
+            snapshot_update(Some(&old), &new),
+            SnapshotUpdate::Full(_)
+        ));
+        assert!(matches!(
+            snapshot_update(None, &new),
+            SnapshotUpdate::Full(_)
+        ));
+        assert!(matches!(
+            snapshot_update(Some(&new), &new.clone()),
+            SnapshotUpdate::Unchanged
+        ));
+
+        let mut big = node(
+            "root",
+            "r",
+            (0..50).map(|i| node(&i.to_string(), "x", vec![])).collect(),
+        );
+        let previous = big.clone();
+        big.children[3].label = Some("y".to_string());
+        assert!(matches!(
+            snapshot_update(Some(&previous), &big),
+            SnapshotUpdate::Diff(_)
+        ));
+    }
+
+    #[test]
+    fn test_compare_tells_moves_from_additions() {
+        let old = node(
+            "root",
+            "r",
+            vec![
+                node("toolbar", "t", vec![node("send", "Send", vec![])]),
+                node("field", "To", vec![]),
+                node("gone", "Gone", vec![node("gone_child", "", vec![])]),
+            ],
+        );
+        let mut new = node(
+            "root",
+            "r",
+            vec![
+                node("toolbar", "t", vec![node("new", "New", vec![])]),
+                node("footer", "f", vec![node("send", "Send now", vec![])]),
+                node("field", "To", vec![]),
+            ],
+        );
+        new.children[2].bounds = Some(Bounds::from((0.0, 0.0, 10.0, 10.0)));
+
+        let comparison = compare_snapshots(&old, &new);
+        let ids = |elements: &[ComparedElement]| {
+            let mut ids: Vec<String> = elements.iter().map(|e| e.id.clone()).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&comparison.added), vec!["footer", "new"]);
+        assert_eq!(ids(&comparison.removed), vec!["gone", "gone_child"]);
+
+        let mut moved = comparison.moved.clone();
+        moved.sort_by(|a, b| a.element.id.cmp(&b.element.id));
+        assert_eq!(moved.len(), 2);
+        assert_eq!(moved[0].element.id, "field");
+        assert_eq!(moved[0].old_parent, moved[0].new_parent);
+        assert_eq!(moved[0].old_bounds, None);
+        assert!(moved[0].new_bounds.is_some());
+        assert_eq!(moved[1].element.id, "send");
+        assert_eq!(moved[1].old_parent.as_deref(), Some("toolbar"));
+        assert_eq!(moved[1].new_parent.as_deref(), Some("footer"));
+
+        // The label changed along with the move
+        assert_eq!(comparison.changed.len(), 1);
+        assert_eq!(comparison.changed[0].element.id, "send");
+        assert_eq!(
+            comparison.changed[0].fields.keys().collect::<Vec<_>>(),
+            vec!["label"]
+        );
+
+        assert!(compare_snapshots(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_compare_with_new_root() {
+        let old = node("root", "r", vec![node("a", "a", vec![])]);
+        let new = node("other", "o", vec![node("a", "a", vec![])]);
+        let comparison = compare_snapshots(&old, &new);
+        assert_eq!(comparison.added.len(), 1);
+        assert_eq!(comparison.added[0].id, "other");
+        assert_eq!(comparison.removed.len(), 1);
+        assert_eq!(comparison.removed[0].id, "root");
+        assert_eq!(comparison.moved.len(), 1);
+        assert_eq!(comparison.moved[0].new_parent.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn test_events_for_windows_and_values() {
+        let window = |id: &str, children| {
+            let mut window = node(id, id, children);
+            window.role = Role::Window;
+            window
+        };
+        let old = node(
+            "app",
+            "app",
+            vec![
+                window("main", vec![node("field", "f", vec![])]),
+                window("prefs", vec![]),
+                node("sidebar", "s", vec![window("moved", vec![])]),
+            ],
+        );
+        let mut new = node(
+            "app",
+            "app",
+            vec![
+                window("main", vec![node("field", "f", vec![])]),
+                window("about", vec![]),
+                window("moved", vec![]),
+            ],
+        );
+        new.children[0].children[0].value = Some("typed".to_string());
+
+        let names: Vec<(&str, String)> = snapshot_events(&old, &new)
+            .iter()
+            .map(|event| {
+                let id = match event {
+                    SnapshotEvent::WindowOpened { id, .. }
+                    | SnapshotEvent::WindowClosed { id, .. }
+                    | SnapshotEvent::ElementValueChanged { id, .. } => id.clone(),
+                };
+                (event.name(), id)
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("window-closed", "prefs".to_string()),
+                ("element-value-changed", "field".to_string()),
+                ("window-opened", "about".to_string()),
+            ]
+        );
+        assert!(snapshot_events(&new, &new).is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use crate::operator::errors::AutomationError;
 use crate::operator::selector::Selector;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::ops::Range;
 
 use super::{ClickResult, Locator};
 
@@ -19,6 +20,9 @@ pub struct UIElementAttributes {
     pub value: Option<String>,
     pub description: Option<String>,
     pub properties: HashMap<String, Option<serde_json::Value>>,
+    /// The element holds secret text, such as a password field, so its value
+    /// and selection were never read
+    pub redacted: bool,
 }
 
 /// Interface for platform-specific element implementations
@@ -46,6 +50,24 @@ pub(crate) trait UIElementImpl: Send + Sync + Debug {
     fn as_any(&self) -> &dyn std::any::Any;
     fn create_locator(&self, selector: Selector) -> Result<Locator, AutomationError>;
     fn scroll(&self, direction: &str, amount: f64) -> Result<(), AutomationError>;
+    /// Identity of the underlying platform element, equal for any two handles
+    /// to the same element, or None where the platform cannot tell
+    fn native_identity(&self) -> Option<u64> {
+        None
+    }
+    /// Selected range of the element's text in chars, None when the element
+    /// has no selection
+    fn selected_text_range(&self) -> Result<Option<Range<usize>>, AutomationError> {
+        Err(AutomationError::UnsupportedOperation(
+            "text selection is not supported on this platform".to_string(),
+        ))
+    }
+    /// Select a range of the element's text given in chars
+    fn select_text_range(&self, _range: Range<usize>) -> Result<(), AutomationError> {
+        Err(AutomationError::UnsupportedOperation(
+            "text selection is not supported on this platform".to_string(),
+        ))
+    }
 
     // Add a method to clone the box
     fn clone_box(&self) -> Box<dyn UIElementImpl>;
@@ -113,7 +135,13 @@ impl UIElement {
     }
 
     /// Type text into this element
+    ///
+    /// NUL characters are dropped, platform text APIs either reject them or
+    /// stop at the first one.
     pub fn type_text(&self, text: &str) -> Result<(), AutomationError> {
+        if text.contains('\0') {
+            return self.inner.type_text(&text.replace('\0', ""));
+        }
         self.inner.type_text(text)
     }
 
@@ -157,6 +185,11 @@ impl UIElement {
         self.inner.as_any()
     }
 
+    /// Identity of the underlying platform element, see `UIElementImpl::native_identity`
+    pub(crate) fn native_identity(&self) -> Option<u64> {
+        self.inner.native_identity()
+    }
+
     /// Find elements matching the selector within this element
     pub fn locator(&self, selector: impl Into<Selector>) -> Result<Locator, AutomationError> {
         let selector = selector.into();
@@ -167,6 +200,25 @@ impl UIElement {
     pub fn scroll(&self, direction: &str, amount: f64) -> Result<(), AutomationError> {
         self.inner.scroll(direction, amount)
     }
+
+    /// Get the selected range of this element's text
+    ///
+    /// Offsets count chars, not the UTF-16 code units platform APIs use, so
+    /// they can index `text.chars()` directly. An empty range is a caret.
+    pub fn selected_text_range(&self) -> Result<Option<Range<usize>>, AutomationError> {
+        self.inner.selected_text_range()
+    }
+
+    /// Select a range of this element's text, offsets count chars
+    pub fn select_text_range(&self, range: Range<usize>) -> Result<(), AutomationError> {
+        if range.start > range.end {
+            return Err(AutomationError::InvalidArgument(format!(
+                "invalid text range {}..{}",
+                range.start, range.end
+            )));
+        }
+        self.inner.select_text_range(range)
+    }
 }
 
 impl PartialEq for UIElement {
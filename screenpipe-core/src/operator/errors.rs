@@ -1,6 +1,9 @@
+use serde::Serialize;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+/// Errors from UI automation, serialized as `{"kind": "element_not_found", "message": ...}`
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
 pub enum AutomationError {
     #[error("Element not found: {0}")]
     ElementNotFound(String),
@@ -11,6 +14,11 @@ pub enum AutomationError {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    /// The user did not allow sending Apple events to an app, which macOS
+    /// asks for separately from accessibility access
+    #[error("Apple events not allowed: {0}")]
+    AppleEventsDenied(String),
+
     #[error("Platform-specific error: {0}")]
     PlatformError(String),
 
@@ -26,3 +34,46 @@ pub enum AutomationError {
     #[error("Internal error: {0}")]
     Internal(String),
 }
+
+impl AutomationError {
+    /// Name of the variant as it appears in the serialized `kind`, for callers
+    /// that branch on the kind of error rather than its message
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AutomationError::ElementNotFound(_) => "element_not_found",
+            AutomationError::Timeout(_) => "timeout",
+            AutomationError::PermissionDenied(_) => "permission_denied",
+            AutomationError::AppleEventsDenied(_) => "apple_events_denied",
+            AutomationError::PlatformError(_) => "platform_error",
+            AutomationError::UnsupportedOperation(_) => "unsupported_operation",
+            AutomationError::UnsupportedPlatform(_) => "unsupported_platform",
+            AutomationError::InvalidArgument(_) => "invalid_argument",
+            AutomationError::Internal(_) => "internal",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_matches_serialized_tag() {
+        let errors = [
+            AutomationError::ElementNotFound("a".to_string()),
+            AutomationError::Timeout("a".to_string()),
+            AutomationError::PermissionDenied("a".to_string()),
+            AutomationError::AppleEventsDenied("a".to_string()),
+            AutomationError::PlatformError("a".to_string()),
+            AutomationError::UnsupportedOperation("a".to_string()),
+            AutomationError::UnsupportedPlatform("a".to_string()),
+            AutomationError::InvalidArgument("a".to_string()),
+            AutomationError::Internal("a".to_string()),
+        ];
+        for error in errors {
+            let value = serde_json::to_value(&error).unwrap();
+            assert_eq!(value["kind"], error.kind());
+            assert_eq!(value["message"], "a");
+        }
+    }
+}
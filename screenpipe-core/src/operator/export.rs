@@ -0,0 +1,520 @@
+//! Snapshots rendered for people rather than programs
+//!
+//! The HTML page is self-contained, so it can be attached to a bug report and
+//! opened offline. Graphs are meant for pasting the part of a tree around a
+//! misbehaving element into an issue, so they are capped in size.
+
+use crate::operator::snapshot::ElementSnapshot;
+use crate::operator::{AutomationError, Role};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Graph languages `export_tree` can write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeFormat {
+    /// Graphviz DOT
+    Dot,
+    Mermaid,
+}
+
+/// Most elements written to a graph, the rest are summed up in a note
+pub const MAX_GRAPH_NODES: usize = 500;
+
+/// Write the subtree of `snapshot` below `root_id`, or all of it, as a graph
+///
+/// Nodes are labeled "role: label (id)". Inputs are filled, windows drawn
+/// bold and redacted elements dashed. Elements deeper than `max_depth` below
+/// the root, or past the first `MAX_GRAPH_NODES`, are left out and counted in
+/// a note node.
+pub fn export_tree(
+    snapshot: &ElementSnapshot,
+    format: TreeFormat,
+    root_id: Option<&str>,
+    max_depth: usize,
+) -> Result<String, AutomationError> {
+    let root = match root_id {
+        Some(id) => snapshot.find(id).ok_or_else(|| {
+            AutomationError::ElementNotFound(format!("No element with ID {}", id))
+        })?,
+        None => snapshot,
+    };
+
+    let mut nodes: Vec<(&ElementSnapshot, Option<usize>)> = Vec::new();
+    let mut omitted = 0;
+    let mut stack = vec![(root, None, 0)];
+    while let Some((element, parent, depth)) = stack.pop() {
+        if nodes.len() == MAX_GRAPH_NODES {
+            omitted += element.element_count();
+            continue;
+        }
+        let index = nodes.len();
+        nodes.push((element, parent));
+        if depth == max_depth {
+            omitted += element.element_count() - 1;
+            continue;
+        }
+        for child in element.children.iter().rev() {
+            stack.push((child, Some(index), depth + 1));
+        }
+    }
+
+    let mut out = String::new();
+    match format {
+        TreeFormat::Dot => {
+            out.push_str("digraph snapshot {\n  node [shape=box, fontname=\"Helvetica\"];\n");
+            for (index, (element, parent)) in nodes.iter().enumerate() {
+                let mut attributes =
+                    vec![format!("label=\"{}\"", escape_dot(&node_label(element)))];
+                let mut styles = Vec::new();
+                if element.role.is_input() {
+                    styles.push("filled");
+                    attributes.push("fillcolor=\"#fff6d6\"".to_string());
+                }
+                if element.role == Role::Window {
+                    attributes.push("penwidth=2".to_string());
+                }
+                if element.redacted {
+                    styles.push("dashed");
+                    attributes.push("fontcolor=\"#666666\"".to_string());
+                }
+                if !styles.is_empty() {
+                    attributes.push(format!("style=\"{}\"", styles.join(",")));
+                }
+                let _ = writeln!(out, "  n{} [{}];", index, attributes.join(", "));
+                if let Some(parent) = parent {
+                    let _ = writeln!(out, "  n{} -> n{};", parent, index);
+                }
+            }
+            if omitted > 0 {
+                let _ = writeln!(
+                    out,
+                    "  omitted [label=\"{} more elements\", shape=note];",
+                    omitted
+                );
+            }
+            out.push_str("}\n");
+        }
+        TreeFormat::Mermaid => {
+            out.push_str("graph TD\n");
+            for (index, (element, parent)) in nodes.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "  n{}[\"{}\"]",
+                    index,
+                    escape_mermaid(&node_label(element))
+                );
+                if let Some(parent) = parent {
+                    let _ = writeln!(out, "  n{} --> n{}", parent, index);
+                }
+                let class = if element.redacted {
+                    Some("redacted")
+                } else if element.role.is_input() {
+                    Some("input")
+                } else if element.role == Role::Window {
+                    Some("window")
+                } else {
+                    None
+                };
+                if let Some(class) = class {
+                    let _ = writeln!(out, "  class n{} {}", index, class);
+                }
+            }
+            if omitted > 0 {
+                let _ = writeln!(out, "  omitted[/\"{} more elements\"/]", omitted);
+            }
+            out.push_str("  classDef input fill:#fff6d6,stroke:#cc9900\n");
+            out.push_str("  classDef window stroke-width:3px\n");
+            out.push_str("  classDef redacted stroke-dasharray:4 4,color:#666666\n");
+        }
+    }
+    Ok(out)
+}
+
+fn node_label(element: &ElementSnapshot) -> String {
+    match &element.label {
+        Some(label) => format!("{}: {} ({})", element.role, label, element.id),
+        None => format!("{} ({})", element.role, element.id),
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Mermaid labels are quoted and take HTML entities, but no backslash escapes
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', " ")
+}
+
+/// Render `snapshot` as a standalone HTML page
+///
+/// The page holds a collapsible tree with roles, labels, values and bounds, a
+/// search box filtering it, and a canvas drawing every element's bounds to
+/// scale, colored by role. Elements taking input are highlighted in both,
+/// redacted and truncated values are marked as such.
+pub fn render_snapshot_html(snapshot: &ElementSnapshot) -> String {
+    let mut tree = String::new();
+    render_html_node(snapshot, &mut tree);
+
+    let rects: Vec<CanvasRect> = snapshot
+        .iter()
+        .filter_map(|element| {
+            let bounds = element.bounds?;
+            Some(CanvasRect {
+                id: &element.id,
+                role: element.role.as_str(),
+                x: bounds.x,
+                y: bounds.y,
+                width: bounds.width,
+                height: bounds.height,
+                input: element.role.is_input(),
+            })
+        })
+        .collect();
+    // Keeps a label like "</script>" from ending the script early
+    let rects = serde_json::to_string(&rects)
+        .unwrap_or_else(|_| "[]".to_string())
+        .replace('<', "\\u003c");
+
+    let title = escape_html(snapshot.label.as_deref().unwrap_or(&snapshot.id));
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>{HTML_STYLE}</style>
+</head>
+<body>
+<header>
+<h1>{title}</h1>
+<span>{count} elements</span>
+<input id="search" type="search" placeholder="Filter by role, label, value or ID">
+</header>
+<main>
+<ul class="tree">{tree}</ul>
+<canvas id="frames" width="800" height="600"></canvas>
+</main>
+<script id="rects" type="application/json">{rects}</script>
+<script>{HTML_SCRIPT}</script>
+</body>
+</html>
+"#,
+        count = snapshot.element_count(),
+    )
+}
+
+#[derive(Debug, Serialize)]
+struct CanvasRect<'a> {
+    id: &'a str,
+    role: &'a str,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    input: bool,
+}
+
+fn render_html_node(element: &ElementSnapshot, out: &mut String) {
+    let mut line = format!(
+        r#"<span class="role" style="color:{}">{}</span>"#,
+        role_color(&element.role),
+        escape_html(element.role.as_str())
+    );
+    if let Some(label) = &element.label {
+        let _ = write!(
+            line,
+            r#" <span class="label">{}</span>"#,
+            escape_html(label)
+        );
+    }
+    if element.redacted {
+        line.push_str(r#" <span class="badge redacted">redacted</span>"#);
+    } else if let Some(value) = &element.value {
+        let _ = write!(
+            line,
+            r#" = <span class="value">{}</span>"#,
+            escape_html(value)
+        );
+        if element.value_truncated {
+            let _ = write!(
+                line,
+                r#" <span class="badge truncated">truncated from {} chars</span>"#,
+                element.original_value_length.unwrap_or_default()
+            );
+        }
+    }
+    if element.role.is_input() {
+        line.push_str(r#" <span class="badge input">input</span>"#);
+    }
+    if element.unresponsive {
+        line.push_str(r#" <span class="badge unresponsive">unresponsive</span>"#);
+    }
+    if let Some(bounds) = &element.bounds {
+        let _ = write!(
+            line,
+            r#" <span class="bounds">{},{} {}&times;{}</span>"#,
+            bounds.x, bounds.y, bounds.width, bounds.height
+        );
+    }
+    let _ = write!(
+        line,
+        r#" <span class="id">{}</span>"#,
+        escape_html(&element.id)
+    );
+
+    let searchable = [
+        Some(element.role.as_str()),
+        element.label.as_deref(),
+        element.value.as_deref(),
+        Some(element.id.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_lowercase();
+    let class = if element.role.is_input() {
+        "node input"
+    } else {
+        "node"
+    };
+    let _ = write!(
+        out,
+        r#"<li data-id="{}" data-search="{}">"#,
+        escape_html(&element.id),
+        escape_html(&searchable)
+    );
+    if element.children.is_empty() {
+        let _ = write!(out, r#"<div class="{}">{}</div>"#, class, line);
+    } else {
+        let _ = write!(
+            out,
+            r#"<details open><summary class="{}">{}</summary><ul>"#,
+            class, line
+        );
+        for child in &element.children {
+            render_html_node(child, out);
+        }
+        out.push_str("</ul></details>");
+    }
+    out.push_str("</li>");
+}
+
+// Stable per role, so the same role has the same color in tree and canvas
+fn role_color(role: &Role) -> String {
+    let hash = role.as_str().bytes().fold(7u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as u32)
+    });
+    format!("hsl({}, 60%, 40%)", hash % 360)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = r#"
+body { font: 13px -apple-system, system-ui, sans-serif; margin: 0; }
+header { display: flex; gap: 12px; align-items: center; padding: 8px 12px; border-bottom: 1px solid #ddd; position: sticky; top: 0; background: #fff; }
+header h1 { font-size: 15px; margin: 0; }
+header input { flex: 1; padding: 4px 8px; }
+main { display: flex; gap: 12px; padding: 12px; align-items: flex-start; }
+.tree, .tree ul { list-style: none; padding-left: 16px; margin: 0; }
+.tree { flex: 1; padding-left: 0; }
+.node { cursor: pointer; white-space: nowrap; }
+.node.input { background: #fff6d6; }
+.node.selected { outline: 2px solid #d33; }
+.role { font-weight: 600; }
+.value { color: #0a6; }
+.bounds, .id { color: #999; font-size: 11px; }
+.badge { border-radius: 3px; padding: 0 4px; font-size: 11px; color: #fff; }
+.badge.input { background: #c90; }
+.badge.redacted { background: #666; }
+.badge.truncated { background: #d70; }
+.badge.unresponsive { background: #d33; }
+li.hidden { display: none; }
+canvas { border: 1px solid #ddd; position: sticky; top: 52px; }
+"#;
+
+const HTML_SCRIPT: &str = r#"
+const rects = JSON.parse(document.getElementById('rects').textContent);
+const canvas = document.getElementById('frames');
+const ctx = canvas.getContext('2d');
+let selected = null;
+
+function color(role) {
+  let hash = 7;
+  for (const byte of new TextEncoder().encode(role)) hash = (Math.imul(hash, 31) + byte) >>> 0;
+  return `hsl(${hash % 360}, 60%, 40%)`;
+}
+
+function draw() {
+  ctx.clearRect(0, 0, canvas.width, canvas.height);
+  if (!rects.length) return;
+  let minX = Infinity, minY = Infinity, maxX = -Infinity, maxY = -Infinity;
+  for (const r of rects) {
+    minX = Math.min(minX, r.x);
+    minY = Math.min(minY, r.y);
+    maxX = Math.max(maxX, r.x + r.width);
+    maxY = Math.max(maxY, r.y + r.height);
+  }
+  const scale = Math.min(canvas.width / (maxX - minX || 1), canvas.height / (maxY - minY || 1));
+  for (const r of rects) {
+    const x = (r.x - minX) * scale, y = (r.y - minY) * scale, w = r.width * scale, h = r.height * scale;
+    if (r.input) {
+      ctx.fillStyle = 'rgba(255, 200, 0, 0.25)';
+      ctx.fillRect(x, y, w, h);
+    }
+    ctx.strokeStyle = r.id === selected ? '#d33' : color(r.role);
+    ctx.lineWidth = r.id === selected ? 3 : 1;
+    ctx.strokeRect(x, y, w, h);
+  }
+}
+
+document.querySelectorAll('.node').forEach(node => node.addEventListener('click', event => {
+  document.querySelectorAll('.node.selected').forEach(n => n.classList.remove('selected'));
+  node.classList.add('selected');
+  selected = node.closest('li').dataset.id;
+  draw();
+  event.preventDefault();
+}));
+
+document.getElementById('search').addEventListener('input', event => {
+  const query = event.target.value.toLowerCase();
+  const items = [...document.querySelectorAll('.tree li')];
+  items.forEach(li => li.classList.toggle('hidden', query !== ''));
+  if (query === '') return;
+  for (const li of items) {
+    if (!li.dataset.search.includes(query)) continue;
+    // Show the match with its ancestors and its subtree
+    li.querySelectorAll('li').forEach(child => child.classList.remove('hidden'));
+    for (let node = li; node; node = node.parentElement.closest('.tree li')) {
+      node.classList.remove('hidden');
+      const details = node.querySelector(':scope > details');
+      if (details) details.open = true;
+    }
+  }
+});
+
+draw();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::snapshot::Bounds;
+
+    fn element(id: &str, role: Role, children: Vec<ElementSnapshot>) -> ElementSnapshot {
+        let mut element = ElementSnapshot::new(id, role);
+        element.bounds = Some(Bounds::from((0.0, 0.0, 100.0, 20.0)));
+        element.children = children;
+        element
+    }
+
+    #[test]
+    fn test_html_marks_inputs_and_hidden_values() {
+        let mut password = element("password", Role::TextField, vec![]);
+        password.redacted = true;
+        let mut notes = element("notes", Role::TextArea, vec![]);
+        notes.value = Some("a".repeat(10));
+        notes.value_truncated = true;
+        notes.original_value_length = Some(5000);
+        let mut window = element("window", Role::Window, vec![password, notes]);
+        window.label = Some("Login <admin>".to_string());
+
+        let html = render_snapshot_html(&window);
+        assert!(html.contains("<title>Login &lt;admin&gt;</title>"));
+        assert!(html.contains(r#"<span class="badge redacted">redacted</span>"#));
+        assert!(html.contains("truncated from 5000 chars"));
+        assert_eq!(html.matches(r#"class="badge input""#).count(), 2);
+        assert!(html.contains(r#"<details open><summary class="node">"#));
+        assert!(html.contains("3 elements"));
+    }
+
+    #[test]
+    fn test_graph_styles_and_labels() {
+        let mut password = element("password", Role::TextField, vec![]);
+        password.redacted = true;
+        password.label = Some("Say \"hi\"".to_string());
+        let window = element(
+            "window",
+            Role::Window,
+            vec![element("ok", Role::Button, vec![]), password],
+        );
+
+        let dot = export_tree(&window, TreeFormat::Dot, None, 10).unwrap();
+        assert!(dot.starts_with("digraph snapshot {"));
+        assert!(dot.contains(r#"n0 [label="window (window)", penwidth=2];"#));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains(
+            r##"n2 [label="text_field: Say \"hi\" (password)", fillcolor="#fff6d6", fontcolor="#666666", style="filled,dashed"];"##
+        ));
+        assert!(!dot.contains("omitted"));
+
+        let mermaid = export_tree(&window, TreeFormat::Mermaid, None, 10).unwrap();
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert!(mermaid.contains(r#"n2["text_field: Say #quot;hi#quot; (password)"]"#));
+        assert!(mermaid.contains("n0 --> n2"));
+        assert!(mermaid.contains("class n0 window"));
+        assert!(mermaid.contains("class n2 redacted"));
+    }
+
+    #[test]
+    fn test_graph_limits() {
+        let deep = element(
+            "root",
+            Role::Group,
+            vec![element(
+                "a",
+                Role::Group,
+                vec![element(
+                    "b",
+                    Role::Group,
+                    vec![element("c", Role::Group, vec![])],
+                )],
+            )],
+        );
+        let dot = export_tree(&deep, TreeFormat::Dot, Some("a"), 1).unwrap();
+        assert!(dot.contains(r#"n0 [label="group (a)"];"#));
+        assert!(dot.contains(r#"n1 [label="group (b)"];"#));
+        assert!(dot.contains(r#"omitted [label="1 more elements", shape=note];"#));
+        assert!(matches!(
+            export_tree(&deep, TreeFormat::Dot, Some("missing"), 1),
+            Err(AutomationError::ElementNotFound(_))
+        ));
+
+        let wide = element(
+            "root",
+            Role::Group,
+            (0..MAX_GRAPH_NODES + 10)
+                .map(|i| element(&i.to_string(), Role::Button, vec![]))
+                .collect(),
+        );
+        let mermaid = export_tree(&wide, TreeFormat::Mermaid, None, 5).unwrap();
+        assert_eq!(mermaid.matches(" --> ").count(), MAX_GRAPH_NODES - 1);
+        assert!(mermaid.contains(r#"omitted[/"11 more elements"/]"#));
+    }
+
+    #[test]
+    fn test_html_escapes_labels() {
+        let mut button = element("button", Role::Button, vec![]);
+        button.label = Some(r#"</script><img src=x onerror="alert(1)">"#.to_string());
+        let html = render_snapshot_html(&button);
+        assert!(!html.contains("<img"));
+        assert_eq!(html.matches("</script>").count(), 2);
+    }
+}
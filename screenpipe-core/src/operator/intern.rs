@@ -0,0 +1,187 @@
+//! Compact snapshot encoding with a shared string table
+//!
+//! Object keys and role names repeat for every element of a snapshot. The
+//! interned form stores each of them once and refers to them by index.
+
+use crate::operator::snapshot::ElementSnapshot;
+use crate::operator::AutomationError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// A snapshot whose object keys and roles point into `strings`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InternedSnapshot {
+    pub strings: Vec<String>,
+    pub root: Value,
+}
+
+impl InternedSnapshot {
+    /// Encode a snapshot
+    pub fn new(snapshot: &ElementSnapshot) -> Result<Self, AutomationError> {
+        let value = serde_json::to_value(snapshot).map_err(|e| {
+            AutomationError::Internal(format!("failed to serialize snapshot: {}", e))
+        })?;
+
+        let mut table = StringTable::default();
+        let root = table.intern(value);
+        Ok(Self {
+            strings: table.strings,
+            root,
+        })
+    }
+
+    /// Resolve the string table and decode the original snapshot
+    pub fn into_snapshot(self) -> Result<ElementSnapshot, AutomationError> {
+        let value = resolve(self.root, &self.strings)?;
+        serde_json::from_value(value).map_err(|e| {
+            AutomationError::InvalidArgument(format!("invalid interned snapshot: {}", e))
+        })
+    }
+}
+
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, usize>,
+}
+
+impl StringTable {
+    fn index(&mut self, string: String) -> usize {
+        if let Some(&index) = self.indexes.get(&string) {
+            return index;
+        }
+        let index = self.strings.len();
+        self.strings.push(string.clone());
+        self.indexes.insert(string, index);
+        index
+    }
+
+    fn intern(&mut self, value: Value) -> Value {
+        match value {
+            Value::Object(object) => {
+                let mut interned = Map::new();
+                for (key, value) in object {
+                    let value = match value {
+                        Value::String(role) if is_role_key(&key) => Value::from(self.index(role)),
+                        value => self.intern(value),
+                    };
+                    interned.insert(self.index(key).to_string(), value);
+                }
+                Value::Object(interned)
+            }
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|item| self.intern(item)).collect())
+            }
+            value => value,
+        }
+    }
+}
+
+fn is_role_key(key: &str) -> bool {
+    key == "role" || key == "native_role"
+}
+
+fn resolve(value: Value, strings: &[String]) -> Result<Value, AutomationError> {
+    let lookup = |index: usize| {
+        strings.get(index).cloned().ok_or_else(|| {
+            AutomationError::InvalidArgument(format!("string index {} out of range", index))
+        })
+    };
+
+    match value {
+        Value::Object(object) => {
+            let mut resolved = Map::new();
+            for (key, value) in object {
+                let key = key
+                    .parse::<usize>()
+                    .map_err(|_| {
+                        AutomationError::InvalidArgument(format!("invalid string index '{}'", key))
+                    })
+                    .and_then(lookup)?;
+                let value = match value {
+                    Value::Number(index) if is_role_key(&key) => {
+                        let index = index.as_u64().ok_or_else(|| {
+                            AutomationError::InvalidArgument(format!(
+                                "invalid role index {}",
+                                index
+                            ))
+                        })?;
+                        Value::String(lookup(index as usize)?)
+                    }
+                    value => resolve(value, strings)?,
+                };
+                resolved.insert(key, value);
+            }
+            Ok(Value::Object(resolved))
+        }
+        Value::Array(items) => Ok(Value::Array(
+            items
+                .into_iter()
+                .map(|item| resolve(item, strings))
+                .collect::<Result<_, _>>()?,
+        )),
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn synthetic_tree(size: usize) -> ElementSnapshot {
+        let children: Vec<Value> = (1..size)
+            .map(|i| {
+                json!({
+                    "id": format!("ax_{}", i),
+                    "role": if i % 2 == 0 { "AXStaticText" } else { "AXButton" },
+                    "label": format!("element {}", i),
+                    "bounds": {"x": 10.0, "y": i as f64, "width": 100.0, "height": 20.0},
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "id": "ax_0",
+            "role": "AXWindow",
+            "children": children,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = synthetic_tree(50);
+        let interned = InternedSnapshot::new(&snapshot).unwrap();
+        let decoded: InternedSnapshot =
+            serde_json::from_str(&serde_json::to_string(&interned).unwrap()).unwrap();
+        assert_eq!(decoded.into_snapshot().unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_interned_payload_is_smaller() {
+        let snapshot = synthetic_tree(1000);
+        let plain = serde_json::to_vec(&snapshot).unwrap().len();
+        let interned = serde_json::to_vec(&InternedSnapshot::new(&snapshot).unwrap())
+            .unwrap()
+            .len();
+        assert!(
+            interned * 10 < plain * 8,
+            "interned {} bytes, plain {} bytes",
+            interned,
+            plain
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_index() {
+        let interned = InternedSnapshot {
+            strings: vec!["id".to_string()],
+            root: json!({"0": "ax_0", "5": 1}),
+        };
+        assert!(matches!(
+            interned.into_snapshot(),
+            Err(AutomationError::InvalidArgument(_))
+        ));
+    }
+}
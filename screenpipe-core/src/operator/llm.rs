@@ -0,0 +1,294 @@
+//! Snapshots written compactly for language model prompts
+//!
+//! The JSON form of a snapshot spends most of its size on field names,
+//! fractional frames and empty containers. The outline written here keeps one
+//! line per element, like `btn "Send" #a3f2`, indented under its parent, with
+//! the element ID last so the ID a model picks can be acted on as is.
+
+use crate::operator::snapshot::ElementSnapshot;
+use crate::operator::Role;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Which elements `serialize_for_llm` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmElements {
+    All,
+    /// Interactive elements and elements with a label or value
+    Labeled,
+    /// Buttons, menu items and inputs
+    Interactive,
+}
+
+/// Options for `serialize_for_llm`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmOptions {
+    pub elements: LlmElements,
+    /// Write frames, rounded to whole points, as `@x,y wxh`
+    pub bounds: bool,
+    pub values: bool,
+    /// Values longer than this many characters are cut and end with "…"
+    pub max_value_length: usize,
+}
+
+impl Default for LlmOptions {
+    fn default() -> Self {
+        Self {
+            elements: LlmElements::Labeled,
+            bounds: false,
+            values: true,
+            max_value_length: 40,
+        }
+    }
+}
+
+/// Write `snapshot` as an indented outline, one element per line
+///
+/// Lines read `role "label" =value @x,y wxh #id [flags]`, leaving out what
+/// the element lacks or `options` excludes. Elements filtered out by
+/// `options.elements` are skipped and their children move up to the nearest
+/// written ancestor. The root is always written.
+pub fn serialize_for_llm(snapshot: &ElementSnapshot, options: &LlmOptions) -> String {
+    let mut out = String::new();
+    write_element(&mut out, snapshot, options, 0);
+    out
+}
+
+fn write_element(out: &mut String, element: &ElementSnapshot, options: &LlmOptions, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+    out.push_str(short_role(&element.role));
+    if let Some(label) = element.label.as_deref().filter(|label| !label.is_empty()) {
+        out.push(' ');
+        write_quoted(out, label, usize::MAX, false);
+    }
+    if options.values {
+        if let Some(value) = element.value.as_deref().filter(|value| !value.is_empty()) {
+            out.push_str(" =");
+            write_quoted(
+                out,
+                value,
+                options.max_value_length,
+                element.value_truncated,
+            );
+        }
+    }
+    if options.bounds {
+        if let Some(bounds) = &element.bounds {
+            let _ = write!(
+                out,
+                " @{},{} {}x{}",
+                bounds.x.round(),
+                bounds.y.round(),
+                bounds.width.round(),
+                bounds.height.round()
+            );
+        }
+    }
+    let _ = write!(out, " #{}", element.id);
+    if element.redacted {
+        out.push_str(" [redacted]");
+    }
+    if element.unresponsive {
+        out.push_str(" [unresponsive]");
+    }
+    out.push('\n');
+
+    write_children(out, element, options, depth + 1);
+}
+
+fn write_children(out: &mut String, element: &ElementSnapshot, options: &LlmOptions, depth: usize) {
+    for child in &element.children {
+        if is_written(child, options.elements) {
+            write_element(out, child, options, depth);
+        } else {
+            write_children(out, child, options, depth);
+        }
+    }
+}
+
+fn is_written(element: &ElementSnapshot, elements: LlmElements) -> bool {
+    let interactive =
+        element.role.is_input() || matches!(element.role, Role::Button | Role::MenuItem);
+    let has_text = |text: &Option<String>| text.as_deref().is_some_and(|text| !text.is_empty());
+    match elements {
+        LlmElements::All => true,
+        LlmElements::Labeled => interactive || has_text(&element.label) || has_text(&element.value),
+        LlmElements::Interactive => interactive,
+    }
+}
+
+// Short names cost fewer tokens and read just as well to a model
+fn short_role(role: &Role) -> &str {
+    match role {
+        Role::Button => "btn",
+        Role::TextField => "field",
+        Role::TextArea => "textarea",
+        Role::CheckBox => "checkbox",
+        Role::MenuItem => "item",
+        Role::WebArea => "web",
+        Role::StaticText => "text",
+        other => other.as_str(),
+    }
+}
+
+// Quoted on one line, cut to `max_length` characters
+fn write_quoted(out: &mut String, text: &str, max_length: usize, truncated: bool) {
+    out.push('"');
+    let mut chars = text.chars();
+    for c in chars.by_ref().take(max_length) {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' | '\t' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    if truncated || chars.next().is_some() {
+        out.push('…');
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::snapshot::Bounds;
+    use std::collections::HashSet;
+
+    fn element(
+        id: &str,
+        role: Role,
+        label: Option<&str>,
+        children: Vec<ElementSnapshot>,
+    ) -> ElementSnapshot {
+        let mut element = ElementSnapshot::new(id, role);
+        element.label = label.map(str::to_string);
+        element.bounds = Some(Bounds::from((10.4, 20.6, 99.5, 24.2)));
+        element.children = children;
+        element
+    }
+
+    fn window() -> ElementSnapshot {
+        let mut body = element("a1b2", Role::TextArea, Some("Body"), vec![]);
+        body.value = Some("Hello \"team\",\nsee the notes attached below".to_string());
+        let mut password = element("c3d4", Role::TextField, Some("Password"), vec![]);
+        password.redacted = true;
+        element(
+            "w0",
+            Role::Window,
+            Some("Compose"),
+            vec![element(
+                "g1",
+                Role::Group,
+                None,
+                vec![
+                    element("g2", Role::Group, None, vec![body, password]),
+                    element("t5", Role::StaticText, Some("Draft saved"), vec![]),
+                    element("a3f2", Role::Button, Some("Send"), vec![]),
+                ],
+            )],
+        )
+    }
+
+    // Every "#id" in the outline, in order
+    fn printed_ids(outline: &str) -> Vec<&str> {
+        outline
+            .lines()
+            .filter_map(|line| line.split(" #").last()?.split(' ').next())
+            .collect()
+    }
+
+    #[test]
+    fn test_outline_format() {
+        let outline = serialize_for_llm(&window(), &LlmOptions::default());
+        assert_eq!(
+            outline,
+            "window \"Compose\" #w0\n\
+             \x20 textarea \"Body\" =\"Hello \\\"team\\\",\\nsee the notes attached bel…\" #a1b2\n\
+             \x20 field \"Password\" #c3d4 [redacted]\n\
+             \x20 text \"Draft saved\" #t5\n\
+             \x20 btn \"Send\" #a3f2\n"
+        );
+
+        let options = LlmOptions {
+            elements: LlmElements::Interactive,
+            bounds: true,
+            values: false,
+            ..LlmOptions::default()
+        };
+        let outline = serialize_for_llm(&window(), &options);
+        assert_eq!(outline.lines().count(), 4);
+        assert!(outline.contains("  btn \"Send\" @10,21 100x24 #a3f2\n"));
+        assert!(!outline.contains("Draft saved"));
+        assert!(!outline.contains('='));
+
+        let options = LlmOptions {
+            elements: LlmElements::All,
+            ..LlmOptions::default()
+        };
+        let outline = serialize_for_llm(&window(), &options);
+        assert!(outline.contains("\n  group #g1\n    group #g2\n      textarea"));
+    }
+
+    #[test]
+    fn test_printed_ids_exist_in_snapshot() {
+        let snapshot = window();
+        let all: HashSet<&str> = snapshot.iter().map(|element| element.id.as_str()).collect();
+
+        for elements in [
+            LlmElements::All,
+            LlmElements::Labeled,
+            LlmElements::Interactive,
+        ] {
+            for bounds in [false, true] {
+                let options = LlmOptions {
+                    elements,
+                    bounds,
+                    ..LlmOptions::default()
+                };
+                let outline = serialize_for_llm(&snapshot, &options);
+                let ids = printed_ids(&outline);
+                assert_eq!(ids.len(), outline.lines().count());
+                for id in ids {
+                    assert!(all.contains(id), "{} not in snapshot", id);
+                    assert!(snapshot.find(id).is_some());
+                }
+            }
+        }
+
+        let outline = serialize_for_llm(
+            &snapshot,
+            &LlmOptions {
+                elements: LlmElements::All,
+                ..LlmOptions::default()
+            },
+        );
+        assert_eq!(printed_ids(&outline).len(), all.len());
+    }
+
+    #[test]
+    fn test_smaller_than_json() {
+        let snapshot = window();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let options = LlmOptions {
+            elements: LlmElements::All,
+            bounds: true,
+            ..LlmOptions::default()
+        };
+        let outline = serialize_for_llm(&snapshot, &options);
+        assert!(
+            outline.len() * 3 < json.len(),
+            "{} vs {}",
+            outline.len(),
+            json.len()
+        );
+
+        let compact = serialize_for_llm(&snapshot, &LlmOptions::default());
+        assert!(compact.len() < outline.len());
+    }
+}
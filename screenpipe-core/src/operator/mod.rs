@@ -5,18 +5,53 @@
 
 use std::sync::Arc;
 
+mod applescript;
+mod coords;
+mod diff;
 mod element;
 mod errors;
+mod export;
+mod intern;
+mod llm;
 mod locator;
+mod ocr;
 pub mod platforms;
+mod redact;
+mod role;
+mod script;
 mod selector;
+mod snapshot;
 #[cfg(test)]
 mod tests;
+mod text;
 
+pub use applescript::{run_apple_script, AppleScriptSource};
+pub use coords::{AxPoint, CocoaPoint, CoordinateSpace, DisplayInfo, DisplayLayout, GlobalPoint};
+pub use diff::{
+    apply_diff, compare_snapshots, diff_snapshots, snapshot_events, snapshot_update,
+    ChangedElement, ComparedElement, FieldChange, MovedElement, SnapshotChange,
+    SnapshotComparison, SnapshotDiff, SnapshotEvent, SnapshotUpdate,
+};
 pub use element::{UIElement, UIElementAttributes};
 pub use errors::AutomationError;
+pub use export::{export_tree, render_snapshot_html, TreeFormat, MAX_GRAPH_NODES};
+pub use intern::InternedSnapshot;
+pub use llm::{serialize_for_llm, LlmElements, LlmOptions};
 pub use locator::Locator;
+pub use ocr::{correlate_ocr, OcrBlock, OcrCandidate, OcrMatch};
+#[cfg(feature = "backend-mock")]
+pub use platforms::mock::{mock_engine, use_mock_engine, MockAction, MockEngine, MockValueChange};
+pub use redact::set_redacted_elements;
+pub use role::Role;
+pub use script::{
+    replay_script, AutomationScript, ElementMatcher, ReplayOptions, ReplayReport, ScriptAction,
+    ScriptRecorder, ScriptStep, StepOutcome,
+};
 pub use selector::Selector;
+pub use snapshot::{
+    Bounds, CaptureError, CaptureStats, ElementSnapshot, SnapshotIter, SnapshotOptions,
+};
+pub use text::{char_range_to_utf16, utf16_range_to_char};
 
 // Define a new struct to hold click result information - move to module level
 pub struct ClickResult {
@@ -39,6 +74,11 @@ impl Desktop {
         Ok(Self { engine })
     }
 
+    /// Create an instance driving `engine`, e.g. a `MockEngine` in tests
+    pub fn with_engine(engine: Arc<dyn platforms::AccessibilityEngine>) -> Self {
+        Self { engine }
+    }
+
     /// Get the root UI element representing the entire desktop
     pub fn root(&self) -> UIElement {
         self.engine.get_root_element()
@@ -61,16 +101,60 @@ impl Desktop {
 
     /// Find an application by name
     pub fn application(&self, name: &str) -> Result<UIElement, AutomationError> {
+        reject_nul(name, "application name")?;
         self.engine.get_application_by_name(name)
     }
 
+    /// Get the arrangement of the connected displays
+    pub fn displays(&self) -> Result<DisplayLayout, AutomationError> {
+        self.engine.get_displays().map(DisplayLayout::new)
+    }
+
     /// Open an application by name
     pub fn open_application(&self, app_name: &str) -> Result<UIElement, AutomationError> {
+        reject_nul(app_name, "application name")?;
         self.engine.open_application(app_name)
     }
 
     /// Open a URL in a specified browser (or default browser if None)
     pub fn open_url(&self, url: &str, browser: Option<&str>) -> Result<UIElement, AutomationError> {
+        reject_nul(url, "url")?;
+        if let Some(browser) = browser {
+            reject_nul(browser, "browser name")?;
+        }
         self.engine.open_url(url, browser)
     }
+
+    /// Drop cached application handles, e.g. when elements keep coming back stale
+    pub fn clear_cache(&self) {
+        self.engine.clear_cache()
+    }
+}
+
+// Names end up in C strings and process arguments, where an interior NUL either
+// truncates them or fails deep inside the platform code
+fn reject_nul(value: &str, what: &str) -> Result<(), AutomationError> {
+    if value.contains('\0') {
+        return Err(AutomationError::InvalidArgument(format!(
+            "{} contains a NUL character",
+            what
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod nul_tests {
+    use super::*;
+
+    #[test]
+    fn test_reject_nul() {
+        assert!(reject_nul("Safari", "application name").is_ok());
+        for name in ["\0", "Saf\0ari", "Safari\0", "\0\0\u{feff}"] {
+            assert!(matches!(
+                reject_nul(name, "application name"),
+                Err(AutomationError::InvalidArgument(_))
+            ));
+        }
+    }
 }
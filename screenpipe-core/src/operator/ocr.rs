@@ -0,0 +1,377 @@
+//! Joining OCR text with the elements showing it
+//!
+//! OCR finds text anywhere on screen but cannot act on it, the accessibility
+//! tree can act but misses text drawn by canvases and images. Matching the two
+//! by position and text turns an OCR hit into an element to click or type into.
+
+use crate::operator::snapshot::{Bounds, ElementSnapshot};
+use crate::operator::{AutomationError, CoordinateSpace, DisplayLayout};
+use serde::{Deserialize, Serialize};
+
+/// Text recognized in a screenshot and where it was found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrBlock {
+    pub text: String,
+    pub bounds: Bounds,
+}
+
+/// An element that may show the text of an OCR block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrCandidate {
+    pub id: String,
+    /// From 0 to 1, half overlap of the two rectangles and half text similarity
+    pub score: f64,
+    /// Whether the element takes input, see `Role::is_input`
+    pub is_input: bool,
+}
+
+/// The elements matching one OCR block, best first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrMatch {
+    /// Index of the block in the slice passed to `correlate_ocr`
+    pub block: usize,
+    /// More than one when other elements score close to the best, empty when
+    /// nothing matched
+    pub candidates: Vec<OcrCandidate>,
+}
+
+impl OcrMatch {
+    pub fn best(&self) -> Option<&OcrCandidate> {
+        self.candidates.first()
+    }
+
+    pub fn is_ambiguous(&self) -> bool {
+        self.candidates.len() > 1
+    }
+}
+
+/// Candidates scoring less than this are dropped
+const MIN_SCORE: f64 = 0.25;
+/// Candidates scoring within this of the best are kept alongside it
+const AMBIGUITY_MARGIN: f64 = 0.1;
+const MAX_CANDIDATES: usize = 3;
+
+/// Match each OCR block to the elements of `snapshot` showing its text
+///
+/// Block bounds are in `space`, e.g. `CoordinateSpace::Physical(display)` for
+/// OCR run on a screenshot of a whole display, and are converted through
+/// `layout` to the global coordinates of element bounds. Returns one match per
+/// block, in order.
+pub fn correlate_ocr(
+    snapshot: &ElementSnapshot,
+    blocks: &[OcrBlock],
+    space: CoordinateSpace,
+    layout: &DisplayLayout,
+) -> Result<Vec<OcrMatch>, AutomationError> {
+    let elements: Vec<(&ElementSnapshot, &Bounds, Vec<String>)> = snapshot
+        .iter()
+        .filter_map(|element| {
+            let bounds = element.bounds.as_ref()?;
+            let texts = [&element.label, &element.value, &element.description]
+                .into_iter()
+                .flatten()
+                .map(|text| normalize(text))
+                .filter(|text| !text.is_empty())
+                .collect();
+            Some((element, bounds, texts))
+        })
+        .collect();
+
+    blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let bounds = to_global(&block.bounds, space, layout)?;
+            let text = normalize(&block.text);
+
+            let mut candidates: Vec<OcrCandidate> = elements
+                .iter()
+                .filter_map(|(element, element_bounds, texts)| {
+                    let overlap = iou(&bounds, element_bounds);
+                    if overlap == 0.0 {
+                        return None;
+                    }
+                    let similarity = texts
+                        .iter()
+                        .map(|element_text| text_similarity(&text, element_text))
+                        .fold(0.0, f64::max);
+                    Some(OcrCandidate {
+                        id: element.id.clone(),
+                        score: (overlap + similarity) / 2.0,
+                        is_input: element.role.is_input(),
+                    })
+                })
+                .filter(|candidate| candidate.score >= MIN_SCORE)
+                .collect();
+
+            candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+            if let Some(best) = candidates.first().map(|candidate| candidate.score) {
+                candidates.retain(|candidate| candidate.score >= best - AMBIGUITY_MARGIN);
+            }
+            candidates.truncate(MAX_CANDIDATES);
+
+            Ok(OcrMatch {
+                block: index,
+                candidates,
+            })
+        })
+        .collect()
+}
+
+fn to_global(
+    bounds: &Bounds,
+    space: CoordinateSpace,
+    layout: &DisplayLayout,
+) -> Result<Bounds, AutomationError> {
+    if space == CoordinateSpace::Global {
+        return Ok(*bounds);
+    }
+    let (x, y) = layout.convert_point(space, CoordinateSpace::Global, bounds.x, bounds.y)?;
+    let (right, bottom) = layout.convert_point(
+        space,
+        CoordinateSpace::Global,
+        bounds.x + bounds.width,
+        bounds.y + bounds.height,
+    )?;
+    Ok(Bounds {
+        x,
+        y,
+        width: right - x,
+        height: bottom - y,
+    })
+}
+
+// Intersection over union, 1 for identical rectangles
+fn iou(a: &Bounds, b: &Bounds) -> f64 {
+    let width = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+    let height = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+    if width <= 0.0 || height <= 0.0 {
+        return 0.0;
+    }
+    let intersection = width * height;
+    intersection / (a.width * a.height + b.width * b.height - intersection)
+}
+
+// Lowercase words separated by single spaces, OCR gets case and punctuation
+// wrong more often than letters
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Similarity of two normalized texts from 0 to 1
+///
+/// OCR often reads a single word out of a longer label, so text found whole
+/// inside the other counts as a full match. Otherwise the Dice coefficient of
+/// their character pairs, which tolerates a few misread letters.
+fn text_similarity(ocr: &str, element: &str) -> f64 {
+    if ocr.is_empty() || element.is_empty() {
+        return 0.0;
+    }
+    // Too short to tell a word from a coincidence
+    if ocr.chars().count() >= 3 && element.contains(ocr) {
+        return 1.0;
+    }
+
+    let pairs = |text: &str| -> Vec<(char, char)> {
+        let chars: Vec<char> = text.chars().collect();
+        chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    };
+    let ocr_pairs = pairs(ocr);
+    let mut element_pairs = pairs(element);
+    if ocr_pairs.is_empty() || element_pairs.is_empty() {
+        return if ocr == element { 1.0 } else { 0.0 };
+    }
+
+    let total = ocr_pairs.len() + element_pairs.len();
+    let mut shared = 0;
+    for pair in ocr_pairs {
+        if let Some(position) = element_pairs.iter().position(|other| *other == pair) {
+            element_pairs.swap_remove(position);
+            shared += 1;
+        }
+    }
+    2.0 * shared as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::{DisplayInfo, Role};
+
+    fn element(
+        id: &str,
+        role: Role,
+        label: Option<&str>,
+        bounds: (f64, f64, f64, f64),
+        children: Vec<ElementSnapshot>,
+    ) -> ElementSnapshot {
+        let mut element = ElementSnapshot::new(id, role);
+        element.label = label.map(str::to_string);
+        element.bounds = Some(Bounds::from(bounds));
+        element.children = children;
+        element
+    }
+
+    fn block(text: &str, bounds: (f64, f64, f64, f64)) -> OcrBlock {
+        OcrBlock {
+            text: text.to_string(),
+            bounds: Bounds::from(bounds),
+        }
+    }
+
+    fn window() -> ElementSnapshot {
+        element(
+            "window",
+            Role::Window,
+            Some("Compose"),
+            (0.0, 0.0, 800.0, 600.0),
+            vec![
+                element(
+                    "to",
+                    Role::TextField,
+                    Some("To"),
+                    (10.0, 10.0, 300.0, 24.0),
+                    vec![],
+                ),
+                element(
+                    "send",
+                    Role::Button,
+                    Some("Send message"),
+                    (700.0, 550.0, 90.0, 30.0),
+                    vec![],
+                ),
+                element(
+                    "archive",
+                    Role::Button,
+                    Some("Archive"),
+                    (600.0, 550.0, 90.0, 30.0),
+                    vec![],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_matches_by_position_and_text() {
+        let blocks = [
+            block("Send", (705.0, 555.0, 40.0, 20.0)),
+            block("Archlve", (602.0, 552.0, 80.0, 26.0)),
+            block("To:", (12.0, 12.0, 20.0, 20.0)),
+        ];
+        let matches = correlate_ocr(
+            &window(),
+            &blocks,
+            CoordinateSpace::Global,
+            &DisplayLayout::default(),
+        )
+        .unwrap();
+
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[0].best().unwrap().id, "send");
+        assert!(!matches[0].best().unwrap().is_input);
+        assert_eq!(matches[1].best().unwrap().id, "archive");
+        assert_eq!(matches[2].best().unwrap().id, "to");
+        assert!(matches[2].best().unwrap().is_input);
+    }
+
+    #[test]
+    fn test_close_scores_are_ambiguous() {
+        // Straddles two unlabeled buttons, too little overlap to match either
+        let snapshot = element(
+            "window",
+            Role::Window,
+            None,
+            (0.0, 0.0, 800.0, 600.0),
+            vec![
+                element("left", Role::Button, None, (0.0, 0.0, 100.0, 40.0), vec![]),
+                element(
+                    "right",
+                    Role::Button,
+                    None,
+                    (100.0, 0.0, 100.0, 40.0),
+                    vec![],
+                ),
+            ],
+        );
+        let matches = correlate_ocr(
+            &snapshot,
+            &[block("", (50.0, 0.0, 100.0, 40.0))],
+            CoordinateSpace::Global,
+            &DisplayLayout::default(),
+        )
+        .unwrap();
+        assert!(matches[0].candidates.is_empty());
+
+        // Straddles two buttons with the same label
+        let matches = correlate_ocr(
+            &element(
+                "window",
+                Role::Window,
+                None,
+                (0.0, 0.0, 800.0, 600.0),
+                vec![
+                    element(
+                        "a",
+                        Role::Button,
+                        Some("OK"),
+                        (0.0, 0.0, 100.0, 40.0),
+                        vec![],
+                    ),
+                    element(
+                        "b",
+                        Role::Button,
+                        Some("OK"),
+                        (90.0, 0.0, 100.0, 40.0),
+                        vec![],
+                    ),
+                ],
+            ),
+            &[block("OK", (45.0, 0.0, 100.0, 40.0))],
+            CoordinateSpace::Global,
+            &DisplayLayout::default(),
+        )
+        .unwrap();
+        assert!(matches[0].is_ambiguous());
+        let mut ids: Vec<&str> = matches[0]
+            .candidates
+            .iter()
+            .map(|candidate| candidate.id.as_str())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_converts_pixels_to_points() {
+        let layout = DisplayLayout::new(vec![DisplayInfo {
+            id: 1,
+            bounds: Bounds::from((0.0, 0.0, 1512.0, 982.0)),
+            primary: true,
+            scale_factor: 2.0,
+        }]);
+        // The send button in Retina pixels
+        let matches = correlate_ocr(
+            &window(),
+            &[block("Send message", (1400.0, 1100.0, 180.0, 60.0))],
+            CoordinateSpace::Physical(1),
+            &layout,
+        )
+        .unwrap();
+        let best = matches[0].best().unwrap();
+        assert_eq!(best.id, "send");
+        assert!(best.score > 0.99, "{}", best.score);
+    }
+
+    #[test]
+    fn test_text_similarity() {
+        assert_eq!(text_similarity("send", "send message"), 1.0);
+        assert_eq!(text_similarity("ok", "book"), 0.5);
+        assert!(text_similarity("archlve", "archive") > 0.6);
+        assert_eq!(text_similarity("cancel", "save"), 0.0);
+        assert_eq!(normalize("  To:  Jane-Doe "), "to jane doe");
+    }
+}
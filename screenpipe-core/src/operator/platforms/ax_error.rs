@@ -0,0 +1,171 @@
+//! AXError codes returned by the macOS accessibility API
+//!
+//! Most calls return one of these instead of throwing. Some mean the element
+//! simply lacks what was asked for, others that the app hung or that access was
+//! revoked, and callers need to tell these apart.
+
+use crate::operator::AutomationError;
+use thiserror::Error;
+
+/// A non-zero AXError, see `AXError.h`
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxError {
+    /// kAXErrorFailure, a system error such as failing to allocate memory
+    #[error("system failure")]
+    Failure,
+    /// kAXErrorIllegalArgument
+    #[error("illegal argument")]
+    IllegalArgument,
+    /// kAXErrorInvalidUIElement, the element no longer exists
+    #[error("invalid UI element")]
+    InvalidUIElement,
+    /// kAXErrorInvalidUIElementObserver
+    #[error("invalid UI element observer")]
+    InvalidUIElementObserver,
+    /// kAXErrorCannotComplete, the app did not answer, also returned when the
+    /// messaging timeout expires
+    #[error("cannot complete")]
+    CannotComplete,
+    /// kAXErrorAttributeUnsupported
+    #[error("attribute unsupported")]
+    AttributeUnsupported,
+    /// kAXErrorActionUnsupported
+    #[error("action unsupported")]
+    ActionUnsupported,
+    /// kAXErrorNotificationUnsupported
+    #[error("notification unsupported")]
+    NotificationUnsupported,
+    /// kAXErrorNotImplemented, the app does not implement accessibility
+    #[error("not implemented")]
+    NotImplemented,
+    /// kAXErrorNotificationAlreadyRegistered
+    #[error("notification already registered")]
+    NotificationAlreadyRegistered,
+    /// kAXErrorNotificationNotRegistered
+    #[error("notification not registered")]
+    NotificationNotRegistered,
+    /// kAXErrorAPIDisabled, the process is not trusted for accessibility
+    #[error("accessibility API disabled")]
+    ApiDisabled,
+    /// kAXErrorNoValue, the attribute exists but is empty
+    #[error("no value")]
+    NoValue,
+    /// kAXErrorParameterizedAttributeUnsupported
+    #[error("parameterized attribute unsupported")]
+    ParameterizedAttributeUnsupported,
+    /// kAXErrorNotEnoughPrecision
+    #[error("not enough precision")]
+    NotEnoughPrecision,
+    /// A code missing from `AXError.h` when this was written
+    #[error("unknown AXError {0}")]
+    Unknown(i32),
+}
+
+impl AxError {
+    /// The error for an AXError code, None for kAXErrorSuccess
+    pub fn from_code(code: i32) -> Option<Self> {
+        Some(match code {
+            0 => return None,
+            -25200 => AxError::Failure,
+            -25201 => AxError::IllegalArgument,
+            -25202 => AxError::InvalidUIElement,
+            -25203 => AxError::InvalidUIElementObserver,
+            -25204 => AxError::CannotComplete,
+            -25205 => AxError::AttributeUnsupported,
+            -25206 => AxError::ActionUnsupported,
+            -25207 => AxError::NotificationUnsupported,
+            -25208 => AxError::NotImplemented,
+            -25209 => AxError::NotificationAlreadyRegistered,
+            -25210 => AxError::NotificationNotRegistered,
+            -25211 => AxError::ApiDisabled,
+            -25212 => AxError::NoValue,
+            -25213 => AxError::ParameterizedAttributeUnsupported,
+            -25214 => AxError::NotEnoughPrecision,
+            code => AxError::Unknown(code),
+        })
+    }
+
+    /// The raw AXError code
+    pub fn code(self) -> i32 {
+        match self {
+            AxError::Failure => -25200,
+            AxError::IllegalArgument => -25201,
+            AxError::InvalidUIElement => -25202,
+            AxError::InvalidUIElementObserver => -25203,
+            AxError::CannotComplete => -25204,
+            AxError::AttributeUnsupported => -25205,
+            AxError::ActionUnsupported => -25206,
+            AxError::NotificationUnsupported => -25207,
+            AxError::NotImplemented => -25208,
+            AxError::NotificationAlreadyRegistered => -25209,
+            AxError::NotificationNotRegistered => -25210,
+            AxError::ApiDisabled => -25211,
+            AxError::NoValue => -25212,
+            AxError::ParameterizedAttributeUnsupported => -25213,
+            AxError::NotEnoughPrecision => -25214,
+            AxError::Unknown(code) => code,
+        }
+    }
+
+    /// Whether the element just has nothing to return, as opposed to a failure
+    pub fn is_missing_value(self) -> bool {
+        matches!(
+            self,
+            AxError::AttributeUnsupported
+                | AxError::NoValue
+                | AxError::ParameterizedAttributeUnsupported
+        )
+    }
+
+    /// Convert to an `AutomationError`, with `context` saying what failed
+    pub fn into_automation_error(self, context: &str) -> AutomationError {
+        let message = format!("{}: {} ({})", context, self, self.code());
+        match self {
+            AxError::ApiDisabled => AutomationError::PermissionDenied(message),
+            AxError::CannotComplete => AutomationError::Timeout(message),
+            AxError::InvalidUIElement => AutomationError::ElementNotFound(message),
+            AxError::IllegalArgument => AutomationError::InvalidArgument(message),
+            AxError::AttributeUnsupported
+            | AxError::ActionUnsupported
+            | AxError::NotificationUnsupported
+            | AxError::NotImplemented
+            | AxError::ParameterizedAttributeUnsupported => {
+                AutomationError::UnsupportedOperation(message)
+            }
+            _ => AutomationError::PlatformError(message),
+        }
+    }
+}
+
+impl From<AxError> for AutomationError {
+    fn from(error: AxError) -> Self {
+        error.into_automation_error("Accessibility request failed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_round_trip() {
+        assert_eq!(AxError::from_code(0), None);
+        for code in -25214..=-25200 {
+            let error = AxError::from_code(code).unwrap();
+            assert!(!matches!(error, AxError::Unknown(_)));
+            assert_eq!(error.code(), code);
+        }
+        assert_eq!(AxError::from_code(-1), Some(AxError::Unknown(-1)));
+        assert_eq!(AxError::Unknown(-1).code(), -1);
+    }
+
+    #[test]
+    fn test_automation_error_kinds() {
+        let kind = |error: AxError| AutomationError::from(error).kind();
+        assert_eq!(kind(AxError::ApiDisabled), "permission_denied");
+        assert_eq!(kind(AxError::CannotComplete), "timeout");
+        assert_eq!(kind(AxError::InvalidUIElement), "element_not_found");
+        assert_eq!(kind(AxError::AttributeUnsupported), "unsupported_operation");
+        assert_eq!(kind(AxError::Unknown(-1)), "platform_error");
+    }
+}
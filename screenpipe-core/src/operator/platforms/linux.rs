@@ -99,6 +99,7 @@ impl UIElementImpl for LinuxUIElement {
             value: None,
             description: None,
             properties: std::collections::HashMap::new(),
+            redacted: false,
         }
     }
 
@@ -1,28 +1,36 @@
+use crate::operator::platforms::ax_error::AxError;
 use crate::operator::platforms::AccessibilityEngine;
+use crate::operator::redact;
+use crate::operator::text::{char_range_to_utf16, utf16_range_to_char};
 use crate::operator::ClickResult;
 use crate::operator::{
-    element::UIElementImpl, AutomationError, Locator, Selector, UIElement, UIElementAttributes,
+    element::UIElementImpl, AutomationError, Bounds, DisplayInfo, Locator, Selector, UIElement,
+    UIElementAttributes,
 };
 
 use accessibility::AXUIElementAttributes;
 use accessibility::{AXAttribute, AXUIElement};
 use anyhow::Result;
 use core_foundation::array::{
-    CFArrayGetCount, CFArrayGetTypeID, CFArrayGetValueAtIndex, __CFArray,
+    CFArray, CFArrayGetCount, CFArrayGetTypeID, CFArrayGetValueAtIndex, CFArrayRef, __CFArray,
 };
-use core_foundation::base::{CFGetTypeID, TCFType};
+use core_foundation::base::{CFGetTypeID, CFHash, CFRange, CFType, CFTypeID, TCFType};
 use core_foundation::boolean::CFBoolean;
 use core_foundation::dictionary::CFDictionary;
 use core_foundation::string::CFString;
-use core_graphics::display::{CGPoint, CGSize};
+use core_graphics::display::{CGDisplay, CGPoint, CGSize};
 use core_graphics::event::{CGEvent, CGEventFlags, CGKeyCode};
 use core_graphics::event_source::CGEventSource;
+use once_cell::sync::Lazy;
 use serde_json::{self, Value};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{debug, trace};
 
 use super::tree_search::{
@@ -39,6 +47,60 @@ extern "C" {
     ) -> i32;
 }
 
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXUIElementSetMessagingTimeout(
+        element: *mut ::std::os::raw::c_void,
+        timeout_in_seconds: f32,
+    ) -> i32;
+
+    fn AXUIElementCopyMultipleAttributeValues(
+        element: *mut ::std::os::raw::c_void,
+        attributes: CFArrayRef,
+        options: u32,
+        values: *mut CFArrayRef,
+    ) -> i32;
+
+    fn AXValueGetType(value: *const ::std::os::raw::c_void) -> u32;
+
+    fn AXValueGetTypeID() -> CFTypeID;
+
+    fn AXValueCreate(
+        type_: u32,
+        value: *const ::std::os::raw::c_void,
+    ) -> *const ::std::os::raw::c_void;
+}
+
+// How long to wait for an application to answer an accessibility request,
+// so a hung app cannot stall a whole traversal
+static MESSAGING_TIMEOUT_MS: AtomicU64 = AtomicU64::new(1000);
+
+/// Set how long accessibility requests wait for an unresponsive application.
+/// Applies to cached applications and to engines created after the call
+pub fn set_messaging_timeout(timeout: Duration) {
+    MESSAGING_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+    for entry in APPLICATION_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+    {
+        apply_messaging_timeout(&entry.element);
+    }
+}
+
+fn apply_messaging_timeout(element: &ThreadSafeAXUIElement) {
+    let timeout = MESSAGING_TIMEOUT_MS.load(Ordering::Relaxed) as f32 / 1000.0;
+    let result = unsafe {
+        AXUIElementSetMessagingTimeout(
+            element.0.as_concrete_TypeRef() as *mut ::std::os::raw::c_void,
+            timeout,
+        )
+    };
+    if let Some(error) = AxError::from_code(result) {
+        debug!("Failed to set messaging timeout: {}", error);
+    }
+}
+
 // Add these extern "C" declarations if not already present
 extern "C" {
     fn AXValueGetValue(
@@ -51,6 +113,8 @@ extern "C" {
 // Add these constant definitions instead - these are the official values from Apple's headers
 const K_AXVALUE_CGPOINT_TYPE: u32 = 1;
 const K_AXVALUE_CGSIZE_TYPE: u32 = 2;
+const K_AXVALUE_CFRANGE_TYPE: u32 = 4;
+const K_AXVALUE_AXERROR_TYPE: u32 = 5;
 
 // Add these constant definitions for key codes
 const KEY_RETURN: u16 = 36;
@@ -108,6 +172,20 @@ impl fmt::Debug for ThreadSafeAXUIElement {
     }
 }
 
+// Application element and name of a running process
+struct CachedApplication {
+    element: ThreadSafeAXUIElement,
+    name: Option<String>,
+    // Tells the process apart from a later one reusing its pid
+    launched_at: Option<f64>,
+}
+
+// Keyed by pid, entries are checked against the launch date of the process
+// running under it. The server creates a new engine for every request, so the
+// cache has to outlive any single MacOSEngine
+static APPLICATION_CACHE: Lazy<Mutex<HashMap<i32, CachedApplication>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 pub struct MacOSEngine {
     system_wide: ThreadSafeAXUIElement,
     use_background_apps: bool,
@@ -127,13 +205,13 @@ impl MacOSEngine {
             }
 
             let check_attr = CFString::new("AXTrustedCheckOptionPrompt");
+            // Bound to a local so the dictionary outlives the call
             let options = CFDictionary::from_CFType_pairs(&[(
                 check_attr.as_CFType(),
                 CFBoolean::true_value().as_CFType(),
-            )])
-            .as_concrete_TypeRef();
+            )]);
 
-            AXIsProcessTrustedWithOptions(options)
+            AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
         };
 
         if !accessibility_enabled {
@@ -142,8 +220,12 @@ impl MacOSEngine {
             ));
         }
 
+        // The system-wide timeout is the default for every element
+        let system_wide = ThreadSafeAXUIElement::system_wide();
+        apply_messaging_timeout(&system_wide);
+
         Ok(Self {
-            system_wide: ThreadSafeAXUIElement::system_wide(),
+            system_wide,
             use_background_apps,
             activate_app,
         })
@@ -155,13 +237,13 @@ impl MacOSEngine {
         let is_valid = match ax_element.0.role() {
             Ok(_) => true,
             Err(e) => {
-                debug!("Warning: Potentially invalid AXUIElement: {:?}", e);
+                trace!("Warning: Potentially invalid AXUIElement: {:?}", e);
                 false
             }
         };
 
         if !is_valid {
-            debug!("Warning: Wrapping possibly invalid AXUIElement");
+            trace!("Warning: Wrapping possibly invalid AXUIElement");
         }
 
         UIElement::new(Box::new(MacOSUIElement {
@@ -172,7 +254,7 @@ impl MacOSEngine {
     }
 
     // Add this new method to refresh the accessibility tree
-    #[allow(clippy::unexpected_cfg_condition)]
+    #[allow(unexpected_cfgs)]
     pub fn refresh_accessibility_tree(
         &self,
         app_name: Option<&str>,
@@ -315,6 +397,54 @@ impl MacOSEngine {
     }
 }
 
+// Read several attributes in a single call to the application. Attributes the
+// element does not support come back as AXError placeholders and are left out
+fn copy_attribute_values(
+    element: &ThreadSafeAXUIElement,
+    names: &[CFString],
+) -> HashMap<String, CFType> {
+    let mut values = HashMap::new();
+    if names.is_empty() {
+        return values;
+    }
+
+    let names_array = CFArray::from_CFTypes(names);
+    let mut result: CFArrayRef = std::ptr::null();
+    let error = unsafe {
+        AXUIElementCopyMultipleAttributeValues(
+            element.0.as_concrete_TypeRef() as *mut ::std::os::raw::c_void,
+            names_array.as_concrete_TypeRef(),
+            0,
+            &mut result,
+        )
+    };
+    if let Some(error) = AxError::from_code(error) {
+        trace!("Failed to copy attribute values: {}", error);
+        return values;
+    }
+    if result.is_null() {
+        return values;
+    }
+
+    // Follows the create rule, the array is released when dropped
+    let result: CFArray<CFType> = unsafe { CFArray::wrap_under_create_rule(result) };
+    for (name, value) in names.iter().zip(result.iter()) {
+        if !is_ax_error_value(&value) {
+            values.insert(name.to_string(), (*value).clone());
+        }
+    }
+
+    values
+}
+
+fn is_ax_error_value(value: &CFType) -> bool {
+    unsafe {
+        let value_ref = value.as_CFTypeRef();
+        CFGetTypeID(value_ref) == AXValueGetTypeID()
+            && AXValueGetType(value_ref) == K_AXVALUE_AXERROR_TYPE
+    }
+}
+
 // Helper function to get PID from an AXUIElement
 fn get_pid_for_element(element: &ThreadSafeAXUIElement) -> i32 {
     // Use accessibility API to get the PID
@@ -339,6 +469,86 @@ fn get_pid_for_element(element: &ThreadSafeAXUIElement) -> i32 {
     }
 }
 
+// Application names never change for a pid, so only ask for the label once
+fn cached_application_name(app: &UIElement) -> String {
+    let pid = match app.as_any().downcast_ref::<MacOSUIElement>() {
+        Some(element) => get_pid_for_element(&element.element),
+        None => return app.attributes().label.unwrap_or_default(),
+    };
+
+    if let Some(name) = APPLICATION_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&pid)
+        .and_then(|entry| entry.name.clone())
+    {
+        return name;
+    }
+
+    // Query outside of the lock, this is a round trip to the application
+    let name = app.attributes().label.unwrap_or_default();
+    if let Some(entry) = APPLICATION_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_mut(&pid)
+    {
+        entry.name = Some(name.clone());
+    }
+    name
+}
+
+// Drop cached applications whose process has exited, or whose pid now
+// belongs to another process, their elements would only return
+// kAXErrorInvalidUIElement
+fn prune_terminated_applications(cache: &mut HashMap<i32, CachedApplication>) {
+    cache.retain(|pid, entry| match process_launch_date(*pid) {
+        Some(launched_at) if entry.launched_at == launched_at => true,
+        Some(_) => {
+            debug!("Dropping cached application for reused pid {}", pid);
+            false
+        }
+        None => {
+            debug!("Dropping cached application for terminated pid {}", pid);
+            false
+        }
+    });
+}
+
+// Seconds since the reference date the process under `pid` was launched,
+// None when no application runs under it and Some(None) when it did not say
+#[allow(unexpected_cfgs)]
+fn process_launch_date(pid: i32) -> Option<Option<f64>> {
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let app: *mut objc::runtime::Object =
+            msg_send![class!(NSRunningApplication), runningApplicationWithProcessIdentifier: pid];
+        if app.is_null() {
+            return None;
+        }
+        let date: *mut objc::runtime::Object = msg_send![app, launchDate];
+        if date.is_null() {
+            return Some(None);
+        }
+        let seconds: f64 = msg_send![date, timeIntervalSinceReferenceDate];
+        Some(Some(seconds))
+    }
+}
+
+// Drop the cached application `element` belongs to when it is that
+// application's own element, after the app answered kAXErrorInvalidUIElement
+fn forget_invalid_application(element: &ThreadSafeAXUIElement) {
+    let pid = get_pid_for_element(element);
+    let mut cache = APPLICATION_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if cache
+        .get(&pid)
+        .is_some_and(|entry| entry.element.0 == element.0)
+    {
+        debug!("Dropping invalid cached application for pid {}", pid);
+        cache.remove(&pid);
+    }
+}
+
 // Modified to return Vec<String> for multiple possible role matches
 fn map_generic_role_to_macos_roles(role: &str) -> Vec<String> {
     match role.to_lowercase().as_str() {
@@ -407,7 +617,7 @@ fn macos_role_to_generic_role(role: &str) -> Vec<String> {
     }
 }
 // Helper function to get PIDs of running applications using NSWorkspace
-#[allow(clippy::unexpected_cfg_condition)]
+#[allow(unexpected_cfgs)]
 fn get_running_application_pids(use_background_apps: bool) -> Result<Vec<i32>, AutomationError> {
     // Implementation using Objective-C bridging
     unsafe {
@@ -472,26 +682,63 @@ impl AccessibilityEngine for MacOSEngine {
 
         debug!("Found {} running applications", pids.len());
 
-        // Create AXUIElements for each application
-        let mut app_elements = Vec::new();
-        for pid in pids {
-            trace!("Creating AXUIElement for application with PID: {}", pid);
-            let app_element = ThreadSafeAXUIElement::application(pid);
-
-            app_elements.push(self.wrap_element(app_element));
-        }
+        // Reuse the AXUIElements of applications we have seen before
+        let ax_elements: Vec<ThreadSafeAXUIElement> = {
+            let mut cache = APPLICATION_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+            prune_terminated_applications(&mut cache);
+
+            pids.into_iter()
+                .map(|pid| {
+                    let entry = cache.entry(pid).or_insert_with(|| {
+                        trace!("Creating AXUIElement for application with PID: {}", pid);
+                        let element = ThreadSafeAXUIElement::application(pid);
+                        apply_messaging_timeout(&element);
+                        CachedApplication {
+                            element,
+                            name: None,
+                            launched_at: process_launch_date(pid).flatten(),
+                        }
+                    });
+                    entry.element.clone()
+                })
+                .collect()
+        };
 
-        Ok(app_elements)
+        Ok(ax_elements
+            .into_iter()
+            .map(|ax_element| self.wrap_element(ax_element))
+            .collect())
     }
     fn get_root_element(&self) -> UIElement {
         self.wrap_element(self.system_wide.clone())
     }
 
     fn get_focused_element(&self) -> Result<UIElement, AutomationError> {
-        // not implemented
-        Err(AutomationError::UnsupportedOperation(
-            "get_focused_element not yet implemented for macOS".to_string(),
-        ))
+        // The system-wide element always knows the focused element, regardless
+        // of which application owns it
+        let focused_attr = AXAttribute::new(&CFString::new("AXFocusedUIElement"));
+        let value = match self.system_wide.0.attribute(&focused_attr) {
+            Ok(value) => value,
+            // Usually nothing has focus, unless access was revoked or the app hung
+            Err(e) => match ax_error(&e) {
+                Some(error @ (AxError::ApiDisabled | AxError::CannotComplete)) => {
+                    return Err(error.into_automation_error("Failed to get focused element"))
+                }
+                _ => {
+                    return Err(AutomationError::ElementNotFound(format!(
+                        "Failed to get focused element: {}",
+                        e
+                    )))
+                }
+            },
+        };
+
+        match value.downcast::<AXUIElement>() {
+            Some(element) => Ok(self.wrap_element(ThreadSafeAXUIElement::new(element))),
+            None => Err(AutomationError::ElementNotFound(
+                "Focused element is not an AXUIElement".to_string(),
+            )),
+        }
     }
 
     fn get_application_by_name(&self, name: &str) -> Result<UIElement, AutomationError> {
@@ -509,8 +756,8 @@ impl AccessibilityEngine for MacOSEngine {
 
         // Look for an application with a matching name
         for app in apps {
-            let app_name = app.attributes().label.unwrap_or_default();
-            debug!("Checking application: '{}'", app_name);
+            let app_name = cached_application_name(&app);
+            trace!("Checking application: '{}'", app_name);
 
             // Case-insensitive comparison since macOS app names might have different casing
             if app_name.to_lowercase() == name.to_lowercase() {
@@ -587,6 +834,14 @@ impl AccessibilityEngine for MacOSEngine {
                 Ok(self.wrap_element(ThreadSafeAXUIElement::new(ax_ui_element)))
             }
             Selector::Id(id) => {
+                // Anything but a stable ID can never match, reject it before
+                // walking the whole tree
+                if !is_stable_id(id) {
+                    return Err(AutomationError::InvalidArgument(format!(
+                        "'{}' is not an element ID, IDs with a _N suffix are only found through the snapshot they came from",
+                        id
+                    )));
+                }
                 let id_owned = id.clone(); // Create an owned copy
                 let collector = ElementFinderWithWindows::new(
                     &self.system_wide.0,
@@ -692,11 +947,11 @@ impl AccessibilityEngine for MacOSEngine {
                 if let (Selector::Role { role, name: _ }, Selector::Id(id)) =
                     (&selectors[0], &selectors[1])
                 {
-                    debug!("Processing chain: role '{}' -> id '{}'", role, id);
+                    trace!("Processing chain: role '{}' -> id '{}'", role, id);
 
                     // First find elements matching the role
                     let role_elements = self.find_elements(&selectors[0], root)?;
-                    debug!(
+                    trace!(
                         "Found {} elements matching role '{}'",
                         role_elements.len(),
                         role
@@ -706,7 +961,7 @@ impl AccessibilityEngine for MacOSEngine {
                     for element in role_elements {
                         if let Some(element_id) = element.id() {
                             if element_id == *id {
-                                debug!("Found matching element with id '{}'", id);
+                                trace!("Found matching element with id '{}'", id);
                                 return Ok(element);
                             }
                         }
@@ -909,6 +1164,47 @@ impl AccessibilityEngine for MacOSEngine {
             Ok(self.get_root_element())
         }
     }
+
+    fn clear_cache(&self) {
+        APPLICATION_CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        debug!("Cleared application element cache");
+    }
+
+    fn get_displays(&self) -> Result<Vec<DisplayInfo>, AutomationError> {
+        let ids = CGDisplay::active_displays().map_err(|e| {
+            AutomationError::PlatformError(format!("Failed to list displays: CGError {}", e))
+        })?;
+        let main = CGDisplay::main().id;
+
+        // CGDisplayBounds uses the same space as AXPosition, points from the
+        // top-left corner of the main display
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let display = CGDisplay::new(id);
+                let rect = display.bounds();
+                // The mode knows both the size in points and in pixels
+                let scale_factor = display
+                    .display_mode()
+                    .filter(|mode| mode.width() > 0)
+                    .map_or(1.0, |mode| mode.pixel_width() as f64 / mode.width() as f64);
+                DisplayInfo {
+                    id,
+                    bounds: Bounds::from((
+                        rect.origin.x,
+                        rect.origin.y,
+                        rect.size.width,
+                        rect.size.height,
+                    )),
+                    primary: id == main,
+                    scale_factor,
+                }
+            })
+            .collect())
+    }
 }
 
 // Enum to represent which click method was used - move to module level
@@ -1059,10 +1355,7 @@ impl MacOSUIElement {
                     details: "Used accessibility AXPress action".to_string(),
                 })
             }
-            Err(e) => Err(AutomationError::PlatformError(format!(
-                "AXPress click failed: {:?}",
-                e
-            ))),
+            Err(e) => Err(ax_failure("AXPress click failed", &e)),
         }
     }
 
@@ -1077,10 +1370,7 @@ impl MacOSUIElement {
                     details: "Used accessibility AXClick action".to_string(),
                 })
             }
-            Err(e) => Err(AutomationError::PlatformError(format!(
-                "AXClick click failed: {:?}",
-                e
-            ))),
+            Err(e) => Err(ax_failure("AXClick click failed", &e)),
         }
     }
 
@@ -1096,6 +1386,8 @@ impl MacOSUIElement {
                 use core_graphics::event_source::CGEventSource;
                 use core_graphics::geometry::CGPoint;
 
+                // Mouse events take global points just like AXPosition, on
+                // every display whatever its scale factor, so no conversion
                 let point = CGPoint::new(center_x, center_y);
 
                 // Create event source
@@ -1240,6 +1532,24 @@ impl MacOSUIElement {
         Ok((key_code, flags))
     }
 
+    fn text_value(&self) -> Result<String, AutomationError> {
+        if is_secure_element(&self.element.0) {
+            return Err(AutomationError::PermissionDenied(
+                "Element holds secret text".to_string(),
+            ));
+        }
+        let attr = AXAttribute::new(&CFString::new("AXValue"));
+        self.element
+            .0
+            .attribute(&attr)
+            .ok()
+            .and_then(|value| value.downcast_into::<CFString>())
+            .map(|value| value.to_string())
+            .ok_or_else(|| {
+                AutomationError::UnsupportedOperation("Element has no text value".to_string())
+            })
+    }
+
     fn generate_stable_id(&self) -> String {
         let mut hasher = DefaultHasher::new();
 
@@ -1306,8 +1616,8 @@ impl UIElementImpl for MacOSUIElement {
         let mut hasher = DefaultHasher::new();
         stable_id.hash(&mut hasher);
         let id = hasher.finish() as usize;
-        debug!("Stable ID: {:?}", stable_id);
-        debug!("Hash: {:?}", id);
+        trace!("Stable ID: {:?}", stable_id);
+        trace!("Hash: {:?}", id);
         id
     }
 
@@ -1315,6 +1625,12 @@ impl UIElementImpl for MacOSUIElement {
         self
     }
 
+    fn native_identity(&self) -> Option<u64> {
+        // Unlike object_id, which hashes attributes and collides for
+        // look-alike wrapper groups, this follows CFEqual on the AX handle
+        Some(unsafe { CFHash(self.element.0.as_CFTypeRef()) } as u64)
+    }
+
     fn id(&self) -> Option<String> {
         Some(self.object_id().to_string())
     }
@@ -1328,7 +1644,7 @@ impl UIElementImpl for MacOSUIElement {
             .map(|r| r.to_string())
             .unwrap_or_default();
 
-        debug!("Original role from AXUIElement: {}", role);
+        trace!("Original role from AXUIElement: {}", role);
 
         // Map macOS-specific roles to generic roles
         // TODO: why first? any issue?
@@ -1350,7 +1666,7 @@ impl UIElementImpl for MacOSUIElement {
 
         // Special case for windows
         if is_window {
-            debug!("Getting attributes for window element");
+            trace!("Getting attributes for window element");
 
             let mut attrs = UIElementAttributes {
                 role: "window".to_string(),
@@ -1358,6 +1674,7 @@ impl UIElementImpl for MacOSUIElement {
                 value: None,
                 description: None,
                 properties,
+                redacted: false,
             };
 
             // Special handling for window title - try multiple attributes
@@ -1374,7 +1691,7 @@ impl UIElementImpl for MacOSUIElement {
                 if let Ok(value) = self.element.0.attribute(&title_attr) {
                     if let Some(cf_string) = value.downcast_into::<CFString>() {
                         attrs.label = Some(cf_string.to_string());
-                        debug!(
+                        trace!(
                             "Found window title via {}: {:?}",
                             title_attr_name, attrs.label
                         );
@@ -1383,12 +1700,6 @@ impl UIElementImpl for MacOSUIElement {
                 }
             }
 
-            // Try to get window position and size for debugging
-            let pos_attr = AXAttribute::new(&CFString::new("AXPosition"));
-            if let Ok(_) = self.element.0.attribute(&pos_attr) {
-                debug!("Window has position attribute");
-            }
-
             // Try to get standard macOS window attributes
             let std_attrs = ["AXMinimized", "AXMain", "AXFocused"];
 
@@ -1415,74 +1726,62 @@ impl UIElementImpl for MacOSUIElement {
             value: None,
             description: None,
             properties,
+            redacted: false,
         };
 
         // Debug attribute collection
-        debug!("Collecting attributes for element");
+        trace!("Collecting attributes for element");
 
-        // Directly try common macOS attributes one by one
-        let label_attr = AXAttribute::new(&CFString::new("AXTitle"));
-        match self.element.0.attribute(&label_attr) {
-            Ok(value) => {
-                if let Some(cf_string) = value.downcast_into::<CFString>() {
-                    attrs.label = Some(cf_string.to_string());
-                    debug!("Found AXTitle: {:?}", attrs.label);
-                }
-            }
+        let names: Vec<CFString> = match self.element.0.attribute_names() {
+            Ok(names) => names.iter().map(|name| (*name).clone()).collect(),
             Err(e) => {
-                debug!("Error getting AXTitle: {:?}", e);
-
-                // Fallback to AXLabel if AXTitle fails
-                let alt_label_attr = AXAttribute::new(&CFString::new("AXLabel"));
-                if let Ok(value) = self.element.0.attribute(&alt_label_attr) {
-                    if let Some(cf_string) = value.downcast_into::<CFString>() {
-                        attrs.label = Some(cf_string.to_string());
-                        debug!("Found AXLabel: {:?}", attrs.label);
-                    }
-                }
-            }
-        }
-
-        // Try to get description
-        let desc_attr = AXAttribute::new(&CFString::new("AXDescription"));
-        match self.element.0.attribute(&desc_attr) {
-            Ok(value) => {
-                if let Some(cf_string) = value.downcast_into::<CFString>() {
-                    attrs.description = Some(cf_string.to_string());
-                    debug!("Found AXDescription: {:?}", attrs.description);
-                }
-            }
-            Err(e) => {
-                debug!("Error getting AXDescription: {:?}", e);
+                trace!("Failed to get attribute names: {:?}", e);
+                Vec::new()
             }
+        };
+        trace!("Found {} attributes", names.len());
+
+        // One round trip for every attribute instead of one per attribute.
+        // Text attributes wait until the others show the element is no secret
+        let (text_names, names): (Vec<CFString>, Vec<CFString>) = names
+            .into_iter()
+            .partition(|name| TEXT_ATTRIBUTES.contains(&name.to_string().as_str()));
+        let mut values = copy_attribute_values(&self.element, &names);
+
+        let role = string_attribute(&values, "AXRole");
+        let subrole = string_attribute(&values, "AXSubrole");
+        let roles: Vec<&str> = role
+            .iter()
+            .chain(subrole.iter())
+            .map(String::as_str)
+            .collect();
+        attrs.redacted = roles.contains(&"AXSecureTextField")
+            || redact::is_redacted(&roles, string_attribute(&values, "AXIdentifier").as_deref());
+        if attrs.redacted {
+            trace!("Skipping text attributes of a secure element");
+        } else if !text_names.is_empty() {
+            values.extend(copy_attribute_values(&self.element, &text_names));
         }
 
-        // Collect all other attributes
-        if let Ok(attr_names) = self.element.0.attribute_names() {
-            debug!("Found {} attributes", attr_names.len());
-
-            for name in attr_names.iter() {
-                let attr = AXAttribute::new(&name);
-                match self.element.0.attribute(&attr) {
-                    Ok(value) => {
-                        let parsed_value = parse_ax_attribute_value(&name.to_string(), value);
-                        attrs.properties.insert(name.to_string(), parsed_value);
-                    }
-                    Err(e) => {
-                        // Avoid logging for common expected errors to reduce noise
-                        if !matches!(
-                            e,
-                            accessibility::Error::Ax(-25212)
-                                | accessibility::Error::Ax(-25205)
-                                | accessibility::Error::Ax(-25204)
-                        ) {
-                            debug!("Error getting attribute {:?}: {:?}", name, e);
-                        }
-                    }
+        attrs.label =
+            string_attribute(&values, "AXTitle").or_else(|| string_attribute(&values, "AXLabel"));
+        attrs.description = string_attribute(&values, "AXDescription");
+        let text = string_attribute(&values, "AXValue");
+
+        for (name, value) in values {
+            // Text ranges and lengths count UTF-16 code units, report them in
+            // chars of the value like selected_text_range does
+            let parsed_value = match name.as_str() {
+                "AXSelectedTextRange" | "AXVisibleCharacterRange" => {
+                    char_range_value(&value, text.as_deref())
                 }
-            }
-        } else {
-            debug!("Failed to get attribute names");
+                "AXNumberOfCharacters" => match &text {
+                    Some(text) => Some(Value::from(text.chars().count())),
+                    None => parse_ax_attribute_value(&name, value),
+                },
+                _ => parse_ax_attribute_value(&name, value),
+            };
+            attrs.properties.insert(name, parsed_value);
         }
 
         attrs
@@ -1537,12 +1836,14 @@ impl UIElementImpl for MacOSUIElement {
                         all_children.len()
                     );
                     Ok(all_children)
+                } else if ax_error(&e).is_some_and(AxError::is_missing_value) {
+                    // A leaf, not a failure
+                    Ok(all_children)
                 } else {
-                    // Otherwise return the error
-                    Err(AutomationError::PlatformError(format!(
-                        "Failed to get children: {}",
-                        e
-                    )))
+                    if ax_error(&e) == Some(AxError::InvalidUIElement) {
+                        forget_invalid_application(&self.element);
+                    }
+                    Err(ax_failure("Failed to get children", &e))
                 }
             }
         }
@@ -1658,9 +1959,32 @@ impl UIElementImpl for MacOSUIElement {
     }
 
     fn hover(&self) -> Result<(), AutomationError> {
-        Err(AutomationError::UnsupportedOperation(
-            "Hover not yet implemented for macOS".to_string(),
-        ))
+        // Some controls (e.g. row buttons in Slack or Notion) only exist in the
+        // AX tree while the pointer is over their parent, so we move the real
+        // pointer instead of relying on an accessibility action
+        use core_graphics::event::{CGEventType, CGMouseButton};
+
+        let (x, y, width, height) = self.bounds()?;
+        let center_x = x + width / 2.0;
+        let center_y = y + height / 2.0;
+        let point = CGPoint::new(center_x, center_y);
+
+        let source =
+            CGEventSource::new(core_graphics::event_source::CGEventSourceStateID::HIDSystemState)
+                .map_err(|_| {
+                AutomationError::PlatformError("Failed to create event source".to_string())
+            })?;
+
+        let mouse_move =
+            CGEvent::new_mouse_event(source, CGEventType::MouseMoved, point, CGMouseButton::Left)
+                .map_err(|_| {
+                    AutomationError::PlatformError("Failed to create mouse move event".to_string())
+                })?;
+        mouse_move.post(core_graphics::event::CGEventTapLocation::HID);
+
+        debug!("Hovered element at ({}, {})", center_x, center_y);
+
+        Ok(())
     }
 
     fn focus(&self) -> Result<(), AutomationError> {
@@ -1765,16 +2089,10 @@ impl UIElementImpl for MacOSUIElement {
 
             let result = AXUIElementSetAttributeValue(element_ref, attr_str_ref, value_ref);
 
-            if result != 0 {
-                debug!(
-                    "Failed to set text value via AXValue: error code {}",
-                    result
-                );
+            if let Some(error) = AxError::from_code(result) {
+                debug!("Failed to set text value via AXValue: {}", error);
 
-                return Err(AutomationError::PlatformError(format!(
-                    "Failed to set text: error code {}",
-                    result
-                )));
+                return Err(error.into_automation_error("Failed to set text"));
             }
         }
 
@@ -1859,6 +2177,9 @@ impl UIElementImpl for MacOSUIElement {
         // Extract text from all collected elements
         let mut all_text: Vec<String> = Vec::new();
         for element in elements {
+            if is_secure_element(&element) {
+                continue;
+            }
             // Extract text attributes from each element
             for attr_name in &[
                 "AXValue",
@@ -1883,6 +2204,65 @@ impl UIElementImpl for MacOSUIElement {
         Ok(all_text.join("\n"))
     }
 
+    fn selected_text_range(&self) -> Result<Option<Range<usize>>, AutomationError> {
+        let text = self.text_value()?;
+        let attr = AXAttribute::new(&CFString::new("AXSelectedTextRange"));
+        let units = match self.element.0.attribute(&attr) {
+            Ok(value) => match cf_range_value(&value) {
+                Some(units) => units,
+                None => return Ok(None),
+            },
+            Err(_) => return Ok(None),
+        };
+
+        utf16_range_to_char(&text, units.clone())
+            .map(Some)
+            .ok_or_else(|| {
+                AutomationError::PlatformError(format!(
+                    "Selected range {:?} does not fit the element's text",
+                    units
+                ))
+            })
+    }
+
+    fn select_text_range(&self, range: Range<usize>) -> Result<(), AutomationError> {
+        let text = self.text_value()?;
+        let units = char_range_to_utf16(&text, range.clone()).ok_or_else(|| {
+            AutomationError::InvalidArgument(format!(
+                "Range {}..{} is past the end of the element's {} characters",
+                range.start,
+                range.end,
+                text.chars().count()
+            ))
+        })?;
+
+        let cf_range = CFRange::init(units.start as isize, units.len() as isize);
+        unsafe {
+            let value = AXValueCreate(
+                K_AXVALUE_CFRANGE_TYPE,
+                &cf_range as *const CFRange as *const ::std::os::raw::c_void,
+            );
+            if value.is_null() {
+                return Err(AutomationError::PlatformError(
+                    "Failed to create range value".to_string(),
+                ));
+            }
+            // Follows the create rule, released when dropped
+            let value = CFType::wrap_under_create_rule(value);
+
+            let element_ref = self.element.0.as_concrete_TypeRef() as *mut ::std::os::raw::c_void;
+            let attr_str = CFString::new("AXSelectedTextRange");
+            let attr_str_ref = attr_str.as_concrete_TypeRef() as *const ::std::os::raw::c_void;
+            let result =
+                AXUIElementSetAttributeValue(element_ref, attr_str_ref, value.as_CFTypeRef());
+            if let Some(error) = AxError::from_code(result) {
+                return Err(error.into_automation_error("Failed to select text"));
+            }
+        }
+
+        Ok(())
+    }
+
     fn set_value(&self, value: &str) -> Result<(), AutomationError> {
         // This is essentially the same implementation as type_text for macOS,
         // as both rely on setting the AXValue attribute
@@ -1899,13 +2279,10 @@ impl UIElementImpl for MacOSUIElement {
 
             let result = AXUIElementSetAttributeValue(element_ref, attr_str_ref, value_ref);
 
-            if result != 0 {
-                debug!("Failed to set value via AXValue: error code {}", result);
+            if let Some(error) = AxError::from_code(result) {
+                debug!("Failed to set value via AXValue: {}", error);
 
-                return Err(AutomationError::PlatformError(format!(
-                    "Failed to set value: error code {}",
-                    result
-                )));
+                return Err(error.into_automation_error("Failed to set value"));
             }
         }
 
@@ -2051,6 +2428,101 @@ impl UIElementImpl for MacOSUIElement {
     }
 }
 
+// The AXError behind a failed call, None for errors the accessibility crate
+// raises itself
+fn ax_error(error: &accessibility::Error) -> Option<AxError> {
+    match error {
+        accessibility::Error::Ax(code) => AxError::from_code(*code),
+        _ => None,
+    }
+}
+
+// Keeps what the AXError means, e.g. a revoked permission, instead of folding
+// every failure into PlatformError
+fn ax_failure(context: &str, error: &accessibility::Error) -> AutomationError {
+    match ax_error(error) {
+        Some(ax_error) => ax_error.into_automation_error(context),
+        None => AutomationError::PlatformError(format!("{}: {}", context, error)),
+    }
+}
+
+// Identifies a referenced element by its CFHash, the address of a local wrapper
+// is only a stack slot and says nothing about the element
+fn element_reference(element: &AXUIElement) -> String {
+    let hash = unsafe { CFHash(element.as_CFTypeRef()) };
+    format!("{}", hash)
+}
+
+// IDs from `object_id`, a decimal u64. The `_N` suffix snapshots add to tell
+// identical siblings apart never matches, the server resolves it through the
+// cached snapshot instead
+fn is_stable_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 20 && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Attributes holding an element's text or revealing its length, never read for
+// secure elements
+const TEXT_ATTRIBUTES: &[&str] = &[
+    "AXValue",
+    "AXSelectedText",
+    "AXSelectedTextRange",
+    "AXSelectedTextRanges",
+    "AXVisibleCharacterRange",
+    "AXNumberOfCharacters",
+];
+
+// Same check as attributes() makes, for code reading text of elements directly
+fn is_secure_element(element: &AXUIElement) -> bool {
+    let role = element.role().ok().map(|role| role.to_string());
+    let subrole = element.subrole().ok().map(|subrole| subrole.to_string());
+    let identifier = element
+        .attribute(&AXAttribute::new(&CFString::new("AXIdentifier")))
+        .ok()
+        .and_then(|value| value.downcast_into::<CFString>())
+        .map(|value| value.to_string());
+
+    let roles: Vec<&str> = role
+        .iter()
+        .chain(subrole.iter())
+        .map(String::as_str)
+        .collect();
+    roles.contains(&"AXSecureTextField") || redact::is_redacted(&roles, identifier.as_deref())
+}
+
+fn string_attribute(values: &HashMap<String, CFType>, name: &str) -> Option<String> {
+    values
+        .get(name)
+        .and_then(|value| value.downcast::<CFString>())
+        .map(|value| value.to_string())
+}
+
+// Text range attributes hold a CFRange counted in UTF-16 code units
+fn cf_range_value(value: &CFType) -> Option<Range<usize>> {
+    let mut range = CFRange::init(0, 0);
+    let found = unsafe {
+        AXValueGetValue(
+            value.as_CFTypeRef(),
+            K_AXVALUE_CFRANGE_TYPE,
+            &mut range as *mut CFRange as *mut ::std::os::raw::c_void,
+        )
+    } != 0;
+    if !found || range.location < 0 || range.length < 0 {
+        return None;
+    }
+    let start = range.location as usize;
+    Some(start..start + range.length as usize)
+}
+
+// A text range attribute converted to chars of the element's text, ranges that
+// cannot be converted are left out rather than reported in the wrong unit
+fn char_range_value(value: &CFType, text: Option<&str>) -> Option<Value> {
+    let range = utf16_range_to_char(text?, cf_range_value(value)?)?;
+    Some(serde_json::json!({
+        "start": range.start,
+        "length": range.len()
+    }))
+}
+
 // Helper function to parse AXUIElement attribute values into appropriate types
 fn parse_ax_attribute_value(
     name: &str,
@@ -2135,8 +2607,7 @@ fn parse_ax_attribute_value(
         "AXParent" | "AXWindow" | "AXTopLevelUIElement" => {
             // get object id
             if let Some(ax_element) = value.downcast_into::<AXUIElement>() {
-                let address = &ax_element as *const _ as usize;
-                return Some(Value::String(format!("{}", address)));
+                return Some(Value::String(element_reference(&ax_element)));
             }
         }
 
@@ -2159,10 +2630,9 @@ fn parse_ax_attribute_value(
                     for i in 0..count {
                         let item = CFArrayGetValueAtIndex(array_ref, i as isize);
                         if !item.is_null() {
-                            // Correctly wrap the raw pointer into AXUIElement
+                            // The array owns the item, get rule retains it for our copy
                             let ax_element = AXUIElement::wrap_under_get_rule(item as *mut _);
-                            let address = &ax_element as *const _ as usize;
-                            items.push(json!(format!("{}", address)));
+                            items.push(json!(element_reference(&ax_element)));
                         }
                     }
                     return Some(Value::Array(items));
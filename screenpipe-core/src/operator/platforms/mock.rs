@@ -0,0 +1,864 @@
+//! Accessibility engine serving fixed trees, for tests and for working without
+//! accessibility access
+//!
+//! Applications come from snapshots, such as saved fetch-children responses.
+//! Actions change nothing on screen. They are recorded in a log, and typing or
+//! setting a value updates the element so later snapshots see the change.
+//! Values can also be scheduled to change on their own, as an app updating
+//! its UI would.
+
+use crate::operator::element::UIElementImpl;
+use crate::operator::platforms::AccessibilityEngine;
+use crate::operator::{
+    AutomationError, ClickResult, ElementSnapshot, Locator, Role, Selector, UIElement,
+    UIElementAttributes,
+};
+#[cfg(feature = "backend-mock")]
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+const DESKTOP_ID: &str = "mock_desktop";
+
+// Read by `MockEngine::from_dir` as value changes rather than an application
+const VALUE_CHANGES_FILE: &str = "value-changes.json";
+
+#[cfg(feature = "backend-mock")]
+static MOCK_ENGINE: OnceCell<MockEngine> = OnceCell::new();
+
+/// Have every `Desktop` created from now on use `engine` instead of the
+/// platform engine. Only the first call has an effect
+#[cfg(feature = "backend-mock")]
+pub fn use_mock_engine(engine: MockEngine) {
+    let _ = MOCK_ENGINE.set(engine);
+}
+
+/// The engine passed to `use_mock_engine`, if any
+#[cfg(feature = "backend-mock")]
+pub fn mock_engine() -> Option<MockEngine> {
+    MOCK_ENGINE.get().cloned()
+}
+
+/// An action performed on a mock element
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MockAction {
+    /// ID of the element acted on. Fixtures may repeat an ID, as identical
+    /// siblings share one on some platforms, and the Nth element with it in
+    /// tree order is logged with a `_N` suffix
+    pub element: String,
+    /// e.g. "click", "type_text" or the name passed to `perform_action`
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub argument: Option<String>,
+}
+
+/// A value an element takes on its own once `after_ms` milliseconds have
+/// passed since the engine was created
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockValueChange {
+    pub after_ms: u64,
+    /// ID of the element whose value changes
+    pub element: String,
+    pub value: Option<String>,
+}
+
+struct MockState {
+    // The desktop, applications are its children
+    root: Mutex<ElementSnapshot>,
+    actions: Mutex<Vec<MockAction>>,
+    failures: Mutex<HashMap<String, fn(String) -> AutomationError>>,
+    created: Instant,
+    // Not applied yet, soonest first
+    changes: Mutex<Vec<MockValueChange>>,
+}
+
+impl MockState {
+    // The tree, with the value changes that came due applied
+    fn root(&self) -> MutexGuard<'_, ElementSnapshot> {
+        let mut root = lock(&self.root);
+        let elapsed = self.created.elapsed();
+        let mut changes = lock(&self.changes);
+        let due = changes
+            .iter()
+            .take_while(|change| Duration::from_millis(change.after_ms) <= elapsed)
+            .count();
+        for change in changes.drain(..due) {
+            if let Some(element) = root.find_mut(&change.element) {
+                element.value = change.value;
+            }
+        }
+        drop(changes);
+        root
+    }
+}
+
+/// Engine serving applications from snapshots, cheap to clone and sharing its
+/// state between clones
+#[derive(Clone)]
+pub struct MockEngine {
+    state: Arc<MockState>,
+}
+
+impl MockEngine {
+    /// Serve `applications`, looked up by their label
+    pub fn new(applications: Vec<ElementSnapshot>) -> Self {
+        let mut root = ElementSnapshot::new(DESKTOP_ID, Role::Unknown("AXSystemWide".to_string()));
+        root.native_role = "AXSystemWide".to_string();
+        root.children = applications;
+        Self {
+            state: Arc::new(MockState {
+                root: Mutex::new(root),
+                actions: Mutex::new(Vec::new()),
+                failures: Mutex::new(HashMap::new()),
+                created: Instant::now(),
+                changes: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Serve every `.json` file in `dir` as an application snapshot, except
+    /// `value-changes.json`, which holds a list of `MockValueChange`s
+    pub fn from_dir(dir: &Path) -> Result<Self, AutomationError> {
+        let read_error = |e: std::io::Error| {
+            AutomationError::InvalidArgument(format!(
+                "Failed to read fixtures from {}: {}",
+                dir.display(),
+                e
+            ))
+        };
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                paths.push(path);
+            }
+        }
+        let changes_path = dir.join(VALUE_CHANGES_FILE);
+        paths.retain(|path| *path != changes_path);
+        // Keep the application order stable across runs
+        paths.sort();
+
+        let mut applications = Vec::new();
+        for path in paths {
+            let json = std::fs::read(&path).map_err(read_error)?;
+            let application = ElementSnapshot::from_json(&json).map_err(|e| {
+                AutomationError::InvalidArgument(format!(
+                    "{} is not an element snapshot: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            applications.push(application);
+        }
+        let engine = Self::new(applications);
+
+        if changes_path.is_file() {
+            let json = std::fs::read(&changes_path).map_err(read_error)?;
+            let changes = serde_json::from_slice(&json).map_err(|e| {
+                AutomationError::InvalidArgument(format!(
+                    "{} is not a list of value changes: {}",
+                    changes_path.display(),
+                    e
+                ))
+            })?;
+            engine.schedule_value_changes(changes);
+        }
+        Ok(engine)
+    }
+
+    /// Apply `changes` as their time comes, counted from when the engine was
+    /// created. Each is applied before the next read of the tree
+    pub fn schedule_value_changes(&self, changes: Vec<MockValueChange>) {
+        let mut scheduled = lock(&self.state.changes);
+        scheduled.extend(changes);
+        scheduled.sort_by_key(|change| change.after_ms);
+    }
+
+    /// Make listing the children of element `id` fail with `error`, e.g.
+    /// `AutomationError::Timeout` for an application that stopped answering
+    pub fn fail_children(&self, id: &str, error: fn(String) -> AutomationError) {
+        lock(&self.state.failures).insert(id.to_string(), error);
+    }
+
+    /// Change the value of element `id`, as if the application had changed it
+    pub fn set_value(&self, id: &str, value: Option<String>) -> Result<(), AutomationError> {
+        let mut root = self.state.root();
+        let element = root.find_mut(id).ok_or_else(|| not_found(id))?;
+        element.value = value;
+        Ok(())
+    }
+
+    /// Actions performed so far, oldest first
+    pub fn action_log(&self) -> Vec<MockAction> {
+        lock(&self.state.actions).clone()
+    }
+
+    // `element` must be part of `tree`, the whole tree
+    fn element(&self, tree: &ElementSnapshot, element: &ElementSnapshot) -> UIElement {
+        UIElement::new(Box::new(MockUIElement {
+            state: self.state.clone(),
+            id: element.id.clone(),
+            occurrence: occurrence(tree, element),
+        }))
+    }
+
+    fn search(
+        &self,
+        selector: &Selector,
+        root: Option<&UIElement>,
+    ) -> Result<Vec<UIElement>, AutomationError> {
+        let matches = |element: &ElementSnapshot| match selector {
+            Selector::Id(id) => element.id == *id,
+            Selector::Role { role, name } => {
+                (element.role == Role::from_native(role)
+                    || native_role(element).eq_ignore_ascii_case(role))
+                    && name
+                        .as_ref()
+                        .is_none_or(|name| element.label.as_ref() == Some(name))
+            }
+            Selector::Name(name) => element.label.as_ref() == Some(name),
+            Selector::Text(text) => [&element.label, &element.value]
+                .iter()
+                .any(|field| field.as_ref().is_some_and(|field| field.contains(text))),
+            _ => false,
+        };
+        if !matches!(
+            selector,
+            Selector::Id(_) | Selector::Role { .. } | Selector::Name(_) | Selector::Text(_)
+        ) {
+            return Err(AutomationError::UnsupportedOperation(format!(
+                "the mock engine does not support {:?} selectors",
+                selector
+            )));
+        }
+
+        let (root_id, root_occurrence) = root
+            .and_then(|root| root.as_any().downcast_ref::<MockUIElement>())
+            .map_or((DESKTOP_ID, 0), |root| (root.id.as_str(), root.occurrence));
+        let tree = self.state.root();
+        let start = nth(&tree, root_id, root_occurrence).ok_or_else(|| not_found(root_id))?;
+        Ok(start
+            .iter()
+            .skip(1)
+            .filter(|element| matches(element))
+            .map(|element| self.element(&tree, element))
+            .collect())
+    }
+
+    fn record(&self, element: &str, action: &str, argument: Option<String>) {
+        lock(&self.state.actions).push(MockAction {
+            element: element.to_string(),
+            action: action.to_string(),
+            argument,
+        });
+    }
+}
+
+impl AccessibilityEngine for MockEngine {
+    fn get_root_element(&self) -> UIElement {
+        let root = self.state.root();
+        self.element(&root, &root)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn get_element_by_id(&self, id: i32) -> Result<UIElement, AutomationError> {
+        self.find_element(&Selector::Id(id.to_string()), None)
+    }
+
+    fn get_focused_element(&self) -> Result<UIElement, AutomationError> {
+        Err(AutomationError::ElementNotFound(
+            "Nothing has focus in the mock engine".to_string(),
+        ))
+    }
+
+    fn get_applications(&self) -> Result<Vec<UIElement>, AutomationError> {
+        let root = self.state.root();
+        Ok(root
+            .children
+            .iter()
+            .map(|application| self.element(&root, application))
+            .collect())
+    }
+
+    fn get_application_by_name(&self, name: &str) -> Result<UIElement, AutomationError> {
+        let root = self.state.root();
+        root.children
+            .iter()
+            .find(|application| {
+                application
+                    .label
+                    .as_ref()
+                    .is_some_and(|label| label.eq_ignore_ascii_case(name))
+            })
+            .map(|application| self.element(&root, application))
+            .ok_or_else(|| {
+                AutomationError::ElementNotFound(format!("Application '{}' not found", name))
+            })
+    }
+
+    fn find_element(
+        &self,
+        selector: &Selector,
+        root: Option<&UIElement>,
+    ) -> Result<UIElement, AutomationError> {
+        self.search(selector, root)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                AutomationError::ElementNotFound(format!("No element matches {:?}", selector))
+            })
+    }
+
+    fn find_elements(
+        &self,
+        selector: &Selector,
+        root: Option<&UIElement>,
+    ) -> Result<Vec<UIElement>, AutomationError> {
+        self.search(selector, root)
+    }
+
+    fn open_application(&self, app_name: &str) -> Result<UIElement, AutomationError> {
+        let application = self.get_application_by_name(app_name)?;
+        self.record(DESKTOP_ID, "open_application", Some(app_name.to_string()));
+        Ok(application)
+    }
+
+    fn open_url(&self, url: &str, _browser: Option<&str>) -> Result<UIElement, AutomationError> {
+        Err(AutomationError::UnsupportedOperation(format!(
+            "the mock engine has no browser to open {}",
+            url
+        )))
+    }
+}
+
+struct MockUIElement {
+    state: Arc<MockState>,
+    id: String,
+    // Elements with the same ID before this one, in tree order
+    occurrence: usize,
+}
+
+impl Debug for MockUIElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockUIElement")
+            .field("id", &self.id)
+            .field("occurrence", &self.occurrence)
+            .finish()
+    }
+}
+
+impl MockUIElement {
+    fn engine(&self) -> MockEngine {
+        MockEngine {
+            state: self.state.clone(),
+        }
+    }
+
+    // Elements are looked up on every call, like platform handles they fail
+    // once the element is gone
+    fn with_snapshot<T>(
+        &self,
+        f: impl FnOnce(&mut ElementSnapshot) -> T,
+    ) -> Result<T, AutomationError> {
+        let mut root = self.state.root();
+        let element =
+            nth_mut(&mut root, &self.id, self.occurrence).ok_or_else(|| not_found(&self.id))?;
+        Ok(f(element))
+    }
+
+    fn act(&self, action: &str, argument: Option<String>) -> Result<(), AutomationError> {
+        self.with_snapshot(|_| ())?;
+        self.engine().record(&self.logged_id(), action, argument);
+        Ok(())
+    }
+
+    // The ID a snapshot would give the element, see `MockAction::element`
+    fn logged_id(&self) -> String {
+        match self.occurrence {
+            0 => self.id.clone(),
+            n => format!("{}_{}", self.id, n + 1),
+        }
+    }
+
+    fn click_result(&self) -> ClickResult {
+        ClickResult {
+            method: "Mock".to_string(),
+            coordinates: None,
+            details: "Recorded in the mock action log".to_string(),
+        }
+    }
+}
+
+impl UIElementImpl for MockUIElement {
+    fn object_id(&self) -> usize {
+        let mut hasher = DefaultHasher::new();
+        self.id.hash(&mut hasher);
+        self.occurrence.hash(&mut hasher);
+        hasher.finish() as usize
+    }
+
+    fn id(&self) -> Option<String> {
+        Some(self.id.clone())
+    }
+
+    fn role(&self) -> String {
+        self.with_snapshot(|element| native_role(element))
+            .unwrap_or_default()
+    }
+
+    fn attributes(&self) -> UIElementAttributes {
+        let attributes = self.with_snapshot(|element| UIElementAttributes {
+            role: native_role(element),
+            label: element.label.clone(),
+            value: element.value.clone(),
+            description: element.description.clone(),
+            properties: HashMap::new(),
+            redacted: element.redacted,
+        });
+        attributes.unwrap_or_else(|_| UIElementAttributes {
+            role: String::new(),
+            label: None,
+            value: None,
+            description: None,
+            properties: HashMap::new(),
+            redacted: false,
+        })
+    }
+
+    fn children(&self) -> Result<Vec<UIElement>, AutomationError> {
+        if let Some(error) = lock(&self.state.failures).get(&self.id) {
+            return Err(error(format!("Failed to get children of {}", self.id)));
+        }
+        let root = self.state.root();
+        let element = nth(&root, &self.id, self.occurrence).ok_or_else(|| not_found(&self.id))?;
+        let engine = self.engine();
+        Ok(element
+            .children
+            .iter()
+            .map(|child| engine.element(&root, child))
+            .collect())
+    }
+
+    fn parent(&self) -> Result<Option<UIElement>, AutomationError> {
+        let root = self.state.root();
+        let element = nth(&root, &self.id, self.occurrence).ok_or_else(|| not_found(&self.id))?;
+        let parent = root.iter().find(|parent| {
+            parent
+                .children
+                .iter()
+                .any(|child| std::ptr::eq(child, element))
+        });
+        Ok(parent.map(|parent| self.engine().element(&root, parent)))
+    }
+
+    fn bounds(&self) -> Result<(f64, f64, f64, f64), AutomationError> {
+        self.with_snapshot(|element| element.bounds)?
+            .map(|bounds| (bounds.x, bounds.y, bounds.width, bounds.height))
+            .ok_or_else(|| {
+                AutomationError::PlatformError(format!("Element {} has no bounds", self.id))
+            })
+    }
+
+    fn click(&self) -> Result<ClickResult, AutomationError> {
+        self.act("click", None)?;
+        Ok(self.click_result())
+    }
+
+    fn double_click(&self) -> Result<ClickResult, AutomationError> {
+        self.act("double_click", None)?;
+        Ok(self.click_result())
+    }
+
+    fn right_click(&self) -> Result<(), AutomationError> {
+        self.act("right_click", None)
+    }
+
+    fn hover(&self) -> Result<(), AutomationError> {
+        self.act("hover", None)
+    }
+
+    fn focus(&self) -> Result<(), AutomationError> {
+        self.act("focus", None)
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), AutomationError> {
+        self.with_snapshot(|element| element.value.get_or_insert_with(String::new).push_str(text))?;
+        self.engine()
+            .record(&self.logged_id(), "type_text", Some(text.to_string()));
+        Ok(())
+    }
+
+    fn press_key(&self, key: &str) -> Result<(), AutomationError> {
+        self.act("press_key", Some(key.to_string()))
+    }
+
+    fn get_text(&self, max_depth: usize) -> Result<String, AutomationError> {
+        self.with_snapshot(|element| {
+            let mut texts = Vec::new();
+            let mut stack = vec![(&*element, 0)];
+            while let Some((element, depth)) = stack.pop() {
+                texts.extend(element.label.iter().chain(element.value.iter()).cloned());
+                if depth < max_depth {
+                    stack.extend(
+                        element
+                            .children
+                            .iter()
+                            .rev()
+                            .map(|child| (child, depth + 1)),
+                    );
+                }
+            }
+            texts.join("\n")
+        })
+    }
+
+    fn set_value(&self, value: &str) -> Result<(), AutomationError> {
+        self.with_snapshot(|element| element.value = Some(value.to_string()))?;
+        self.engine()
+            .record(&self.logged_id(), "set_value", Some(value.to_string()));
+        Ok(())
+    }
+
+    fn is_enabled(&self) -> Result<bool, AutomationError> {
+        self.with_snapshot(|_| true)
+    }
+
+    fn is_visible(&self) -> Result<bool, AutomationError> {
+        self.with_snapshot(|element| element.bounds.is_some())
+    }
+
+    fn is_focused(&self) -> Result<bool, AutomationError> {
+        self.with_snapshot(|_| false)
+    }
+
+    fn perform_action(&self, action: &str) -> Result<(), AutomationError> {
+        self.act(action, None)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn create_locator(&self, selector: Selector) -> Result<Locator, AutomationError> {
+        Ok(
+            Locator::new(Arc::new(self.engine()), selector)
+                .within(UIElement::new(self.clone_box())),
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn UIElementImpl> {
+        Box::new(MockUIElement {
+            state: self.state.clone(),
+            id: self.id.clone(),
+            occurrence: self.occurrence,
+        })
+    }
+
+    fn scroll(&self, direction: &str, amount: f64) -> Result<(), AutomationError> {
+        self.act("scroll", Some(format!("{} {}", direction, amount)))
+    }
+
+    fn native_identity(&self) -> Option<u64> {
+        Some(self.object_id() as u64)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Fixtures may only carry the platform neutral role
+fn native_role(element: &ElementSnapshot) -> String {
+    if element.native_role.is_empty() {
+        element.role.to_string()
+    } else {
+        element.native_role.clone()
+    }
+}
+
+// How many elements of `tree` with the same ID come before `element`
+fn occurrence(tree: &ElementSnapshot, element: &ElementSnapshot) -> usize {
+    tree.iter()
+        .filter(|other| other.id == element.id)
+        .position(|other| std::ptr::eq(other, element))
+        .unwrap_or(0)
+}
+
+// The element with ID `id` preceded by `occurrence` others with it
+fn nth<'a>(tree: &'a ElementSnapshot, id: &str, occurrence: usize) -> Option<&'a ElementSnapshot> {
+    tree.iter()
+        .filter(|element| element.id == id)
+        .nth(occurrence)
+}
+
+// Same as `nth`, visiting elements in the order `ElementSnapshot::iter` does
+fn nth_mut<'a>(
+    tree: &'a mut ElementSnapshot,
+    id: &str,
+    mut occurrence: usize,
+) -> Option<&'a mut ElementSnapshot> {
+    let mut stack = vec![tree];
+    while let Some(element) = stack.pop() {
+        if element.id == id {
+            if occurrence == 0 {
+                return Some(element);
+            }
+            occurrence -= 1;
+        }
+        stack.extend(element.children.iter_mut().rev());
+    }
+    None
+}
+
+fn not_found(id: &str) -> AutomationError {
+    AutomationError::ElementNotFound(format!("Element {} no longer exists", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::{Bounds, Desktop, SnapshotOptions};
+
+    fn node(
+        id: &str,
+        role: &str,
+        label: Option<&str>,
+        children: Vec<ElementSnapshot>,
+    ) -> ElementSnapshot {
+        let mut node = ElementSnapshot::new(id, Role::from_native(role));
+        node.native_role = role.to_string();
+        node.label = label.map(str::to_string);
+        node.bounds = Some(Bounds {
+            x: 0.0,
+            y: 0.0,
+            width: 100.0,
+            height: 100.0,
+        });
+        node.children = children;
+        node
+    }
+
+    fn notes() -> ElementSnapshot {
+        node(
+            "app",
+            "AXApplication",
+            Some("Notes"),
+            vec![node(
+                "window",
+                "AXWindow",
+                Some("All Notes"),
+                vec![
+                    node("title", "AXTextField", Some("Title"), vec![]),
+                    node(
+                        "sidebar",
+                        "AXGroup",
+                        None,
+                        vec![node("folder", "AXRow", Some("Folders"), vec![])],
+                    ),
+                ],
+            )],
+        )
+    }
+
+    #[test]
+    fn test_capture_serves_the_fixture() {
+        let desktop = Desktop::with_engine(Arc::new(MockEngine::new(vec![notes()])));
+        let app = desktop.application("notes").unwrap();
+
+        let (snapshot, stats) =
+            ElementSnapshot::capture_with_stats(&app, &SnapshotOptions::default());
+        assert_eq!(snapshot, notes());
+        assert_eq!(stats.captured, 5);
+        assert!(stats.errors.is_empty());
+    }
+
+    #[test]
+    fn test_failed_children_are_reported() {
+        let engine = MockEngine::new(vec![notes()]);
+        engine.fail_children("sidebar", AutomationError::Timeout);
+        engine.fail_children("title", AutomationError::PermissionDenied);
+        let desktop = Desktop::with_engine(Arc::new(engine));
+        let app = desktop.application("Notes").unwrap();
+
+        let (snapshot, stats) =
+            ElementSnapshot::capture_with_stats(&app, &SnapshotOptions::default());
+        assert!(snapshot.find("sidebar").unwrap().unresponsive);
+        assert!(snapshot.find("folder").is_none());
+
+        let mut kinds: Vec<(&str, &str)> = stats
+            .errors
+            .iter()
+            .map(|error| (error.element.as_str(), error.kind.as_str()))
+            .collect();
+        kinds.sort();
+        assert_eq!(
+            kinds,
+            vec![("sidebar", "timeout"), ("title", "permission_denied")]
+        );
+    }
+
+    #[test]
+    fn test_find_live_follows_capture_ids() {
+        let engine = MockEngine::new(vec![notes()]);
+        let desktop = Desktop::with_engine(Arc::new(engine.clone()));
+        let app = desktop.application("Notes").unwrap();
+        let options = SnapshotOptions::default();
+
+        let folder = ElementSnapshot::find_live(&app, &options, "folder").unwrap();
+        folder.click().unwrap();
+        assert_eq!(engine.action_log()[0].element, "folder");
+        assert!(ElementSnapshot::find_live(&app, &options, "app").is_some());
+        assert!(ElementSnapshot::find_live(&app, &options, "missing").is_none());
+
+        // Beyond the depth a capture with these options reaches
+        let shallow = SnapshotOptions {
+            max_depth: 1,
+            ..SnapshotOptions::default()
+        };
+        assert!(ElementSnapshot::find_live(&app, &shallow, "folder").is_none());
+    }
+
+    #[test]
+    fn test_repeated_ids_are_separate_elements() {
+        let mut fixture = notes();
+        let sidebar = &mut fixture.children[0].children[1];
+        sidebar
+            .children
+            .push(node("folder", "AXRow", Some("Drafts"), vec![]));
+        let engine = MockEngine::new(vec![fixture]);
+        let desktop = Desktop::with_engine(Arc::new(engine.clone()));
+        let app = desktop.application("Notes").unwrap();
+        let options = SnapshotOptions::default();
+
+        let snapshot = ElementSnapshot::capture(&app, &options);
+        assert_eq!(
+            snapshot.find("folder_2").unwrap().label.as_deref(),
+            Some("Drafts")
+        );
+
+        let drafts = ElementSnapshot::find_live(&app, &options, "folder_2").unwrap();
+        drafts.type_text("x").unwrap();
+        assert_eq!(engine.action_log()[0].element, "folder_2");
+        let snapshot = ElementSnapshot::capture(&app, &options);
+        assert_eq!(snapshot.find("folder").unwrap().value, None);
+        assert_eq!(
+            snapshot.find("folder_2").unwrap().value.as_deref(),
+            Some("x")
+        );
+    }
+
+    #[test]
+    fn test_visible_only_below_a_window() {
+        let mut fixture = notes();
+        let sidebar = &mut fixture.children[0].children[1];
+        sidebar
+            .children
+            .push(node("trash", "AXRow", Some("Trash"), vec![]));
+        sidebar.children[1].bounds = Some(Bounds {
+            x: 5000.0,
+            y: 0.0,
+            width: 100.0,
+            height: 20.0,
+        });
+        let desktop = Desktop::with_engine(Arc::new(MockEngine::new(vec![fixture])));
+        let app = desktop.application("Notes").unwrap();
+        let sidebar = app.locator("#sidebar").unwrap().first().unwrap().unwrap();
+        let options = SnapshotOptions {
+            visible_only: true,
+            ..SnapshotOptions::default()
+        };
+
+        // Checked against the window the sidebar is in
+        let (snapshot, stats) = ElementSnapshot::capture_with_stats(&sidebar, &options);
+        assert!(snapshot.find("folder").is_some());
+        assert!(snapshot.find("trash").is_none());
+        assert_eq!(stats.skipped_offscreen, 1);
+
+        let (snapshot, _) = ElementSnapshot::capture_with_stats(&app, &options);
+        assert!(snapshot.find("trash").is_none());
+    }
+
+    #[test]
+    fn test_scheduled_values_change_when_due() {
+        let engine = MockEngine::new(vec![notes()]);
+        engine.schedule_value_changes(vec![
+            MockValueChange {
+                after_ms: 60_000,
+                element: "title".to_string(),
+                value: Some("Later".to_string()),
+            },
+            MockValueChange {
+                after_ms: 0,
+                element: "title".to_string(),
+                value: Some("Groceries".to_string()),
+            },
+        ]);
+        let desktop = Desktop::with_engine(Arc::new(engine));
+        let app = desktop.application("Notes").unwrap();
+
+        let (snapshot, _) = ElementSnapshot::capture_with_stats(&app, &SnapshotOptions::default());
+        assert_eq!(
+            snapshot.find("title").unwrap().value.as_deref(),
+            Some("Groceries")
+        );
+    }
+
+    #[test]
+    fn test_actions_are_logged_and_change_values() {
+        let engine = MockEngine::new(vec![notes()]);
+        let desktop = Desktop::with_engine(Arc::new(engine.clone()));
+        let app = desktop.application("Notes").unwrap();
+
+        let title = app.locator("#title").unwrap().first().unwrap().unwrap();
+        title.type_text("Groceries").unwrap();
+        title.press_key("Return").unwrap();
+        app.locator("Folders")
+            .unwrap()
+            .first()
+            .unwrap()
+            .unwrap()
+            .click()
+            .unwrap();
+
+        assert_eq!(
+            engine.action_log(),
+            vec![
+                MockAction {
+                    element: "title".to_string(),
+                    action: "type_text".to_string(),
+                    argument: Some("Groceries".to_string()),
+                },
+                MockAction {
+                    element: "title".to_string(),
+                    action: "press_key".to_string(),
+                    argument: Some("Return".to_string()),
+                },
+                MockAction {
+                    element: "folder".to_string(),
+                    action: "click".to_string(),
+                    argument: None,
+                },
+            ]
+        );
+
+        engine
+            .set_value("title", Some("Groceries!".to_string()))
+            .unwrap();
+        let snapshot = ElementSnapshot::capture(&app, &SnapshotOptions::default());
+        assert_eq!(
+            snapshot.find("title").unwrap().value.as_deref(),
+            Some("Groceries!")
+        );
+        assert!(engine.set_value("missing", None).is_err());
+    }
+}
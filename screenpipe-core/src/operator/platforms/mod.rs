@@ -1,4 +1,4 @@
-use crate::operator::{AutomationError, Selector, UIElement};
+use crate::operator::{AutomationError, DisplayInfo, Selector, UIElement};
 
 /// The common trait that all platform-specific engines must implement
 pub trait AccessibilityEngine: Send + Sync {
@@ -38,15 +38,36 @@ pub trait AccessibilityEngine: Send + Sync {
 
     /// Open a URL in a specified browser (or default if None)
     fn open_url(&self, url: &str, browser: Option<&str>) -> Result<UIElement, AutomationError>;
+
+    /// List the connected displays, in the same coordinates as element bounds
+    /// Default implementation returns an UnsupportedPlatform error
+    fn get_displays(&self) -> Result<Vec<DisplayInfo>, AutomationError> {
+        Err(AutomationError::UnsupportedPlatform(
+            "display layout is not available on this platform".to_string(),
+        ))
+    }
+
+    /// Drop any platform handles cached across calls
+    /// Default implementation does nothing, for engines that do not cache
+    fn clear_cache(&self) {}
 }
 
-#[cfg(target_os = "linux")]
+// Only the macOS backend reads AXErrors, the mapping itself is tested everywhere
+#[cfg(any(test, all(target_os = "macos", feature = "backend-macos")))]
+#[cfg_attr(
+    not(all(target_os = "macos", feature = "backend-macos")),
+    allow(dead_code)
+)]
+mod ax_error;
+#[cfg(all(target_os = "linux", feature = "backend-linux"))]
 mod linux;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", feature = "backend-macos"))]
 pub mod macos;
-#[cfg(target_os = "macos")]
+#[cfg(any(test, feature = "backend-mock"))]
+pub mod mock;
+#[cfg(all(target_os = "macos", feature = "backend-macos"))]
 pub mod tree_search;
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "backend-windows"))]
 mod windows;
 
 /// Create the appropriate engine for the current platform
@@ -54,31 +75,40 @@ pub fn create_engine(
     use_background_apps: bool,
     activate_app: bool,
 ) -> Result<Box<dyn AccessibilityEngine>, AutomationError> {
-    #[cfg(target_os = "macos")]
+    #[cfg(feature = "backend-mock")]
+    if let Some(engine) = mock::mock_engine() {
+        return Ok(Box::new(engine));
+    }
+    #[cfg(all(target_os = "macos", feature = "backend-macos"))]
     {
         return Ok(Box::new(macos::MacOSEngine::new(
             use_background_apps,
             activate_app,
         )?));
     }
-    #[cfg(target_os = "windows")]
+    #[cfg(all(target_os = "windows", feature = "backend-windows"))]
     {
         return Ok(Box::new(windows::WindowsEngine::new(
             use_background_apps,
             activate_app,
         )?));
     }
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", feature = "backend-linux"))]
     {
         return Ok(Box::new(linux::LinuxEngine::new(
             use_background_apps,
             activate_app,
         )?));
     }
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    #[cfg(not(any(
+        all(target_os = "macos", feature = "backend-macos"),
+        all(target_os = "windows", feature = "backend-windows"),
+        all(target_os = "linux", feature = "backend-linux")
+    )))]
     {
+        let _ = (use_background_apps, activate_app);
         return Err(AutomationError::UnsupportedPlatform(
-            "Current platform is not supported".to_string(),
+            "No accessibility backend was built for this platform".to_string(),
         ));
     }
 }
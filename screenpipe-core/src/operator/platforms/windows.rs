@@ -1,5 +1,6 @@
 use crate::operator::element::UIElementImpl;
 use crate::operator::platforms::AccessibilityEngine;
+use crate::operator::redact;
 use crate::operator::ClickResult;
 use crate::operator::{AutomationError, Locator, Selector, UIElement, UIElementAttributes};
 use std::error::Error;
@@ -13,7 +14,7 @@ use uiautomation::inputs::Mouse;
 use uiautomation::inputs::Keyboard;
 use uiautomation::variants::Variant;
 use uiautomation::patterns;
-use tracing::debug;
+use tracing::{debug, trace};
 use std::collections::{
     HashMap,
     hash_map::DefaultHasher
@@ -368,14 +369,17 @@ impl Debug for WindowsUIElement {
 
 impl UIElementImpl for WindowsUIElement {
     fn object_id(&self) -> usize {
-        // use hashed `AutomationId` as object_id
-        let stable_id = format!("{:?}",
-            self.element.0.get_automation_id().unwrap_or_default()); 
+        // use hashed `AutomationId` as object_id. Most elements have none, and
+        // would all compare equal, so those fall back to their runtime id
+        let automation_id = self.element.0.get_automation_id().unwrap_or_default();
         let mut hasher = DefaultHasher::new();
-        stable_id.hash(&mut hasher);
+        if automation_id.is_empty() {
+            self.element.0.get_runtime_id().unwrap_or_default().hash(&mut hasher);
+        } else {
+            automation_id.hash(&mut hasher);
+        }
         let id = hasher.finish() as usize;
-        debug!("Stable ID: {:?}", stable_id);
-        debug!("Hash: {:?}", id);
+        trace!("Stable ID: {:?}, hash: {:?}", automation_id, id);
         id
     }
 
@@ -391,15 +395,22 @@ impl UIElementImpl for WindowsUIElement {
         let mut properties = HashMap::new();
         // there are alot of properties, including neccessary ones
         // ref: https://docs.rs/uiautomation/0.16.1/uiautomation/types/enum.UIProperty.html
-        let property_list = vec![
+        let role = self.role();
+        let automation_id = self.element.0.get_automation_id().ok();
+        let redacted = self.element.0.is_password().unwrap_or(false)
+            || redact::is_redacted(&[&role], automation_id.as_deref());
+        let mut property_list = vec![
             UIProperty::Name,
             UIProperty::HelpText,
             UIProperty::LabeledBy,
-            UIProperty::ValueValue,
             UIProperty::ControlType,
             UIProperty::AutomationId,
             UIProperty::FullDescription,
         ];
+        // Never read the value of password fields
+        if !redacted {
+            property_list.push(UIProperty::ValueValue);
+        }
         for property in property_list {
             if let Ok(value) = self.element.0.get_property_value(property) {
                 properties.insert(
@@ -410,14 +421,20 @@ impl UIElementImpl for WindowsUIElement {
                 properties.insert(format!("{:?}", property), None);
             }
         }
+        let value = if redacted {
+            None
+        } else {
+            self.element.0.get_property_value(UIProperty::ValueValue)
+                .ok().and_then(|v| v.get_string().ok())
+        };
         UIElementAttributes {
-            role: self.role(),
+            role,
             label: self.element.0.get_labeled_by().ok()
                 .map(|e| e.get_name().unwrap_or_default()),
-            value: self.element.0.get_property_value(UIProperty::ValueValue)
-                .ok().and_then(|v| v.get_string().ok()),
+            value,
             description: self.element.0.get_help_text().ok(),
             properties,
+            redacted,
         }
     }
 
@@ -488,7 +505,7 @@ impl UIElementImpl for WindowsUIElement {
         if let Err(_) = click_result {
             debug!("clickable point unavailable, falling back to bounding rectangle");
             if let Ok(rect) = self.element.0.get_bounding_rectangle() {
-                println!("bounding rectangle: {:?}", rect);
+                trace!("bounding rectangle: {:?}", rect);
                 // Calculate center point of the element
                 let center_x = rect.get_left() + rect.get_width() / 2;
                 let center_y = rect.get_top() + rect.get_height() / 2;
@@ -537,7 +554,17 @@ impl UIElementImpl for WindowsUIElement {
     }
 
     fn hover(&self) -> Result<(), AutomationError> {
-        return Err(AutomationError::UnsupportedOperation("`hover` doesn't not support".to_string()));
+        let rect = self.element.0.get_bounding_rectangle()
+            .map_err(|e| AutomationError::ElementNotFound(e.to_string()))?;
+        let center_x = rect.get_left() + rect.get_width() / 2;
+        let center_y = rect.get_top() + rect.get_height() / 2;
+
+        let point = Point::new(center_x, center_y);
+        let mouse = Mouse::default();
+
+        debug!("hovering at center point: ({}, {})", center_x, center_y);
+        mouse.move_to(point)
+            .map_err(|e| AutomationError::PlatformError(e.to_string()))
     }
 
     fn focus(&self) -> Result<(), AutomationError> {
@@ -549,7 +576,7 @@ impl UIElementImpl for WindowsUIElement {
         let control_type = self.element.0.get_control_type()
             .map_err(|e| AutomationError::PlatformError(e.to_string()))?;
         // check if element accepts input
-        println!("control_type: {:#?}", control_type);
+        trace!("control_type: {:?}", control_type);
         // if control_type == ControlType::Edit {
             let keyboard = Keyboard::default();
             keyboard.send_text(text)
@@ -683,6 +710,14 @@ impl UIElementImpl for WindowsUIElement {
         self
     }
 
+    fn native_identity(&self) -> Option<u64> {
+        // runtime ids are unique among the elements currently on the desktop
+        let runtime_id = self.element.0.get_runtime_id().ok()?;
+        let mut hasher = DefaultHasher::new();
+        runtime_id.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
     fn create_locator(&self, selector: Selector) -> Result<Locator, AutomationError> {
         let automation = WindowsEngine::new(false, false)
             .map_err(|e| AutomationError::PlatformError(e.to_string()))?;
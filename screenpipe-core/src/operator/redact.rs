@@ -0,0 +1,64 @@
+//! Elements whose text must never be read
+//!
+//! Platform engines recognize password fields on their own. Custom controls
+//! that hold secrets without saying so can be added by role or identifier.
+
+use std::sync::{PoisonError, RwLock};
+
+struct RedactionRules {
+    roles: Vec<String>,
+    identifiers: Vec<String>,
+}
+
+static RULES: RwLock<RedactionRules> = RwLock::new(RedactionRules {
+    roles: Vec::new(),
+    identifiers: Vec::new(),
+});
+
+/// Treat elements with one of these roles or identifiers like password fields
+///
+/// Roles match the platform role, e.g. "AXTextField", case insensitively.
+/// Identifiers match AXIdentifier or the UI Automation AutomationId exactly.
+/// Replaces any previously set lists.
+pub fn set_redacted_elements(roles: Vec<String>, identifiers: Vec<String>) {
+    let mut rules = RULES.write().unwrap_or_else(PoisonError::into_inner);
+    rules.roles = roles;
+    rules.identifiers = identifiers;
+}
+
+/// Whether an element matches the configured roles or identifiers
+pub(crate) fn is_redacted(roles: &[&str], identifier: Option<&str>) -> bool {
+    let rules = RULES.read().unwrap_or_else(PoisonError::into_inner);
+    roles.iter().any(|role| {
+        rules
+            .roles
+            .iter()
+            .any(|redacted| redacted.eq_ignore_ascii_case(role))
+    }) || identifier.is_some_and(|identifier| {
+        rules
+            .identifiers
+            .iter()
+            .any(|redacted| redacted == identifier)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_roles_and_identifiers() {
+        set_redacted_elements(
+            vec!["AXPinField".to_string()],
+            vec!["vault-secret".to_string()],
+        );
+
+        assert!(is_redacted(&["AXTextField", "axpinfield"], None));
+        assert!(is_redacted(&["AXTextField"], Some("vault-secret")));
+        assert!(!is_redacted(&["AXTextField"], Some("Vault-Secret")));
+        assert!(!is_redacted(&["AXTextField"], None));
+
+        set_redacted_elements(Vec::new(), Vec::new());
+        assert!(!is_redacted(&["AXPinField"], Some("vault-secret")));
+    }
+}
@@ -0,0 +1,279 @@
+//! Roles shared by every platform
+//!
+//! Each backend names roles in its own vocabulary: "AXButton" on macOS,
+//! "Button" in UI Automation and "push button" in AT-SPI. `Role` maps them
+//! onto one set, the platform name stays available next to it.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// What kind of control an element is, independent of the platform
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Role {
+    Button,
+    TextField,
+    TextArea,
+    CheckBox,
+    Slider,
+    Menu,
+    MenuItem,
+    Window,
+    WebArea,
+    StaticText,
+    Group,
+    Table,
+    Row,
+    Cell,
+    /// A platform role without a counterpart here, holding the platform name
+    Unknown(String),
+}
+
+/// AXRole values
+const MACOS_ROLES: &[(&str, Role)] = &[
+    ("AXButton", Role::Button),
+    ("AXPopUpButton", Role::Button),
+    ("AXMenuButton", Role::Button),
+    ("AXTextField", Role::TextField),
+    ("AXSearchField", Role::TextField),
+    ("AXComboBox", Role::TextField),
+    ("AXTextArea", Role::TextArea),
+    ("AXCheckBox", Role::CheckBox),
+    ("AXSlider", Role::Slider),
+    ("AXMenu", Role::Menu),
+    ("AXMenuBar", Role::Menu),
+    ("AXMenuItem", Role::MenuItem),
+    ("AXMenuBarItem", Role::MenuItem),
+    ("AXWindow", Role::Window),
+    ("AXSheet", Role::Window),
+    ("AXWebArea", Role::WebArea),
+    ("AXStaticText", Role::StaticText),
+    ("AXGroup", Role::Group),
+    ("AXSplitGroup", Role::Group),
+    ("AXTable", Role::Table),
+    ("AXOutline", Role::Table),
+    ("AXRow", Role::Row),
+    ("AXCell", Role::Cell),
+];
+
+/// UI Automation control type names. Browsers expose pages as a plain
+/// Document, like word processors do, so there is no web area here
+const WINDOWS_ROLES: &[(&str, Role)] = &[
+    ("Button", Role::Button),
+    ("SplitButton", Role::Button),
+    ("Edit", Role::TextField),
+    ("ComboBox", Role::TextField),
+    ("CheckBox", Role::CheckBox),
+    ("Slider", Role::Slider),
+    ("Menu", Role::Menu),
+    ("MenuBar", Role::Menu),
+    ("MenuItem", Role::MenuItem),
+    ("Window", Role::Window),
+    ("Text", Role::StaticText),
+    ("Group", Role::Group),
+    ("Pane", Role::Group),
+    ("Table", Role::Table),
+    ("DataGrid", Role::Table),
+    ("DataItem", Role::Row),
+];
+
+/// AT-SPI role names, as returned by `atspi_role_get_name`
+const ATSPI_ROLES: &[(&str, Role)] = &[
+    ("push button", Role::Button),
+    ("toggle button", Role::Button),
+    ("entry", Role::TextField),
+    ("password text", Role::TextField),
+    ("combo box", Role::TextField),
+    ("text", Role::TextArea),
+    ("check box", Role::CheckBox),
+    ("slider", Role::Slider),
+    ("menu", Role::Menu),
+    ("menu bar", Role::Menu),
+    ("menu item", Role::MenuItem),
+    ("check menu item", Role::MenuItem),
+    ("frame", Role::Window),
+    ("window", Role::Window),
+    ("dialog", Role::Window),
+    ("document web", Role::WebArea),
+    ("label", Role::StaticText),
+    ("static", Role::StaticText),
+    ("panel", Role::Group),
+    ("filler", Role::Group),
+    ("grouping", Role::Group),
+    ("table", Role::Table),
+    ("tree table", Role::Table),
+    ("table row", Role::Row),
+    ("table cell", Role::Cell),
+];
+
+const KNOWN_ROLES: [Role; 14] = [
+    Role::Button,
+    Role::TextField,
+    Role::TextArea,
+    Role::CheckBox,
+    Role::Slider,
+    Role::Menu,
+    Role::MenuItem,
+    Role::Window,
+    Role::WebArea,
+    Role::StaticText,
+    Role::Group,
+    Role::Table,
+    Role::Row,
+    Role::Cell,
+];
+
+impl Role {
+    /// Role for a macOS AXRole
+    pub fn from_macos(role: &str) -> Self {
+        lookup(MACOS_ROLES, role).unwrap_or_else(|| Role::Unknown(role.to_string()))
+    }
+
+    /// Role for a UI Automation control type name
+    pub fn from_windows(role: &str) -> Self {
+        lookup(WINDOWS_ROLES, role).unwrap_or_else(|| Role::Unknown(role.to_string()))
+    }
+
+    /// Role for an AT-SPI role name
+    pub fn from_atspi(role: &str) -> Self {
+        lookup(ATSPI_ROLES, role).unwrap_or_else(|| Role::Unknown(role.to_string()))
+    }
+
+    /// Role for a name from any platform, or one of our own names like "text_field"
+    ///
+    /// Platform names are matched exactly since they only differ by case
+    /// between platforms, e.g. "Text" and "text".
+    pub fn from_native(role: &str) -> Self {
+        lookup(MACOS_ROLES, role)
+            .or_else(|| lookup(WINDOWS_ROLES, role))
+            .or_else(|| lookup(ATSPI_ROLES, role))
+            .or_else(|| {
+                KNOWN_ROLES
+                    .iter()
+                    .find(|known| known.as_str().eq_ignore_ascii_case(role))
+                    .cloned()
+            })
+            .unwrap_or_else(|| Role::Unknown(role.to_string()))
+    }
+
+    /// Our name for the role, or the platform name for unknown roles
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::Button => "button",
+            Role::TextField => "text_field",
+            Role::TextArea => "text_area",
+            Role::CheckBox => "check_box",
+            Role::Slider => "slider",
+            Role::Menu => "menu",
+            Role::MenuItem => "menu_item",
+            Role::Window => "window",
+            Role::WebArea => "web_area",
+            Role::StaticText => "static_text",
+            Role::Group => "group",
+            Role::Table => "table",
+            Role::Row => "row",
+            Role::Cell => "cell",
+            Role::Unknown(role) => role,
+        }
+    }
+
+    /// Whether users enter or change a value through the element
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Role::TextField | Role::TextArea | Role::CheckBox | Role::Slider
+        )
+    }
+}
+
+fn lookup(table: &[(&str, Role)], role: &str) -> Option<Role> {
+    table
+        .iter()
+        .find(|(name, _)| *name == role)
+        .map(|(_, role)| role.clone())
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Role {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let role = String::deserialize(deserializer)?;
+        Ok(Role::from_native(&role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_table(table: &[(&str, Role)], from: fn(&str) -> Role) {
+        for (name, role) in table {
+            assert_eq!(&from(*name), role, "{}", name);
+            assert_eq!(&Role::from_native(name), role, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_macos_roles() {
+        assert_table(MACOS_ROLES, Role::from_macos);
+        assert_eq!(Role::from_macos("AXButton"), Role::Button);
+        assert_eq!(Role::from_macos("AXWebArea"), Role::WebArea);
+        assert_eq!(
+            Role::from_macos("AXSplitter"),
+            Role::Unknown("AXSplitter".to_string())
+        );
+    }
+
+    #[test]
+    fn test_windows_roles() {
+        assert_table(WINDOWS_ROLES, Role::from_windows);
+        assert_eq!(Role::from_windows("Edit"), Role::TextField);
+        assert_eq!(Role::from_windows("Text"), Role::StaticText);
+        assert_eq!(
+            Role::from_windows("Document"),
+            Role::Unknown("Document".to_string())
+        );
+    }
+
+    #[test]
+    fn test_atspi_roles() {
+        assert_table(ATSPI_ROLES, Role::from_atspi);
+        assert_eq!(Role::from_atspi("push button"), Role::Button);
+        assert_eq!(Role::from_atspi("text"), Role::TextArea);
+        assert_eq!(
+            Role::from_atspi("Button"),
+            Role::Unknown("Button".to_string())
+        );
+    }
+
+    #[test]
+    fn test_own_names_round_trip() {
+        for role in KNOWN_ROLES {
+            assert_eq!(Role::from_native(role.as_str()), role);
+            let json = serde_json::to_value(&role).unwrap();
+            assert_eq!(serde_json::from_value::<Role>(json).unwrap(), role);
+        }
+
+        let unknown = Role::Unknown("AXSplitter".to_string());
+        let json = serde_json::to_value(&unknown).unwrap();
+        assert_eq!(json, "AXSplitter");
+        assert_eq!(serde_json::from_value::<Role>(json).unwrap(), unknown);
+    }
+
+    #[test]
+    fn test_inputs() {
+        assert!(Role::TextField.is_input());
+        assert!(Role::Slider.is_input());
+        assert!(!Role::Button.is_input());
+        assert!(!Role::Unknown("AXTextField".to_string()).is_input());
+    }
+}
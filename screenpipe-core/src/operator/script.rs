@@ -0,0 +1,544 @@
+//! Recording performed actions as scripts and replaying them
+//!
+//! Element handles and IDs do not survive the app restarting, so a script
+//! names each element by its app, role and label, and by its position in the
+//! tree to tell apart elements sharing a label. Replaying looks every element
+//! up again from those.
+
+use crate::operator::{AutomationError, Desktop, Role, UIElement};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What identifies an element across runs of its app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementMatcher {
+    pub app: String,
+    pub role: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Index of the element and each of its ancestors among their siblings,
+    /// from the app down
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<usize>,
+}
+
+impl ElementMatcher {
+    /// Matcher for `element`, a descendant of `app` named `app_name`
+    pub fn from_element(
+        app_name: &str,
+        app: &UIElement,
+        element: &UIElement,
+    ) -> Result<Self, AutomationError> {
+        let mut path = Vec::new();
+        let mut current = element.clone();
+        while !same_element(&current, app) {
+            let Some(parent) = current.parent()? else {
+                // Not below the app after all, the label has to do
+                path.clear();
+                break;
+            };
+            let index = parent
+                .children()?
+                .iter()
+                .position(|child| same_element(child, &current))
+                .ok_or_else(|| {
+                    AutomationError::Internal("element is missing from its parent".to_string())
+                })?;
+            path.push(index);
+            current = parent;
+        }
+        path.reverse();
+
+        Ok(Self {
+            app: app_name.to_string(),
+            role: Role::from_native(&element.role()),
+            label: element.attributes().label,
+            path,
+        })
+    }
+
+    fn matches(&self, element: &UIElement) -> bool {
+        Role::from_native(&element.role()) == self.role && element.attributes().label == self.label
+    }
+
+    /// Find the element in the running app
+    ///
+    /// The recorded path is tried first. When the element there has another
+    /// role or label, the app is searched down to `max_depth` levels and the
+    /// match whose position is closest to the recorded one wins.
+    pub fn resolve(
+        &self,
+        desktop: &Desktop,
+        max_depth: usize,
+    ) -> Result<UIElement, AutomationError> {
+        let app = desktop.application(&self.app)?;
+
+        let mut element = Some(app.clone());
+        for &index in &self.path {
+            element = element.and_then(|element| element.children().ok()?.into_iter().nth(index));
+        }
+        if let Some(element) = element.filter(|element| self.matches(element)) {
+            return Ok(element);
+        }
+
+        let mut best: Option<(usize, UIElement)> = None;
+        let mut level = vec![(app, Vec::new())];
+        for _ in 0..max_depth.max(self.path.len()) {
+            let mut next = Vec::new();
+            for (element, path) in level {
+                for (index, child) in element
+                    .children()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .enumerate()
+                {
+                    let mut child_path: Vec<usize> = path.clone();
+                    child_path.push(index);
+                    if self.matches(&child) {
+                        let shared = child_path
+                            .iter()
+                            .zip(&self.path)
+                            .take_while(|(a, b)| a == b)
+                            .count();
+                        if best.as_ref().is_none_or(|(best, _)| shared > *best) {
+                            best = Some((shared, child.clone()));
+                        }
+                    }
+                    next.push((child, child_path));
+                }
+            }
+            level = next;
+        }
+
+        best.map(|(_, element)| element).ok_or_else(|| {
+            AutomationError::ElementNotFound(format!(
+                "No {} labeled {:?} in {}",
+                self.role, self.label, self.app
+            ))
+        })
+    }
+}
+
+// Whether two handles point at the same platform element. `UIElement`
+// equality compares attribute hashes on macOS, under which look-alike siblings
+// such as list rows all equal the first one, and it costs AX calls
+fn same_element(a: &UIElement, b: &UIElement) -> bool {
+    match (a.native_identity(), b.native_identity()) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// An action a script performs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptAction {
+    Click,
+    DoubleClick,
+    RightClick,
+    Focus,
+    Hover,
+    TypeText { text: String },
+    PressKey { key: String },
+    SetValue { value: String },
+    Scroll { direction: String, amount: f64 },
+}
+
+impl ScriptAction {
+    fn perform(&self, element: &UIElement) -> Result<(), AutomationError> {
+        match self {
+            ScriptAction::Click => element.click().map(drop),
+            ScriptAction::DoubleClick => element.double_click().map(drop),
+            ScriptAction::RightClick => element.right_click(),
+            ScriptAction::Focus => element.focus(),
+            ScriptAction::Hover => element.hover(),
+            ScriptAction::TypeText { text } => element.type_text(text),
+            ScriptAction::PressKey { key } => element.press_key(key),
+            ScriptAction::SetValue { value } => element.set_value(value),
+            ScriptAction::Scroll { direction, amount } => element.scroll(direction, *amount),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptStep {
+    pub matcher: ElementMatcher,
+    pub action: ScriptAction,
+    /// Milliseconds since the previous step when it was recorded
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Actions in the order they were performed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AutomationScript {
+    pub steps: Vec<ScriptStep>,
+}
+
+/// Collects actions into a script between `start_recording` and
+/// `stop_recording`
+#[derive(Default)]
+pub struct ScriptRecorder {
+    // The steps so far and when the last one was performed
+    recording: Mutex<Option<(Vec<ScriptStep>, Instant)>>,
+}
+
+impl ScriptRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new script, dropping the one being recorded if any
+    pub fn start_recording(&self) {
+        *self.lock() = Some((Vec::new(), Instant::now()));
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// The script recorded since `start_recording`, empty when not recording
+    pub fn stop_recording(&self) -> AutomationScript {
+        let steps = self
+            .lock()
+            .take()
+            .map(|(steps, _)| steps)
+            .unwrap_or_default();
+        AutomationScript { steps }
+    }
+
+    /// Add `action` on `element` of `app` to the script, does nothing when not
+    /// recording
+    ///
+    /// Call after the action succeeded, a failed one would fail again on replay.
+    pub fn record(
+        &self,
+        app_name: &str,
+        app: &UIElement,
+        element: &UIElement,
+        action: ScriptAction,
+    ) -> Result<(), AutomationError> {
+        if !self.is_recording() {
+            return Ok(());
+        }
+        // Walking up the tree may take a while, outside of the lock
+        let matcher = ElementMatcher::from_element(app_name, app, element)?;
+        if let Some((steps, last)) = self.lock().as_mut() {
+            steps.push(ScriptStep {
+                matcher,
+                action,
+                delay_ms: last.elapsed().as_millis() as u64,
+            });
+            *last = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<(Vec<ScriptStep>, Instant)>> {
+        self.recording
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayOptions {
+    /// Milliseconds to wait for each element to appear
+    pub timeout_ms: u64,
+    /// How often to try again when an action fails
+    pub retries: u32,
+    /// Milliseconds between two attempts at finding an element or acting on it
+    pub retry_delay_ms: u64,
+    /// Carry on with the next step when one fails, instead of stopping
+    pub continue_on_failure: bool,
+    /// Wait between steps as long as when they were recorded
+    pub keep_delays: bool,
+    /// How deep to search the app for elements that moved
+    pub max_depth: usize,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 5000,
+            retries: 2,
+            retry_delay_ms: 250,
+            continue_on_failure: false,
+            keep_delays: false,
+            max_depth: 10,
+        }
+    }
+}
+
+/// How one step of a replay went
+#[derive(Debug, Serialize)]
+pub struct StepOutcome {
+    /// Index of the step in the script
+    pub step: usize,
+    /// How often the action was performed, 0 when the element was not found
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AutomationError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    /// One per step run, steps after a failure are missing unless
+    /// `continue_on_failure` is set
+    pub steps: Vec<StepOutcome>,
+    /// Whether every step of the script succeeded
+    pub completed: bool,
+}
+
+/// Perform the steps of `script` in order, blocking until done
+pub fn replay_script(
+    desktop: &Desktop,
+    script: &AutomationScript,
+    options: &ReplayOptions,
+) -> ReplayReport {
+    let mut outcomes = Vec::new();
+    for (index, step) in script.steps.iter().enumerate() {
+        if options.keep_delays && index > 0 {
+            thread::sleep(Duration::from_millis(step.delay_ms));
+        }
+        let outcome = replay_step(desktop, index, step, options);
+        let failed = outcome.error.is_some();
+        outcomes.push(outcome);
+        if failed && !options.continue_on_failure {
+            break;
+        }
+    }
+
+    ReplayReport {
+        completed: outcomes.len() == script.steps.len()
+            && outcomes.iter().all(|outcome| outcome.error.is_none()),
+        steps: outcomes,
+    }
+}
+
+fn replay_step(
+    desktop: &Desktop,
+    index: usize,
+    step: &ScriptStep,
+    options: &ReplayOptions,
+) -> StepOutcome {
+    let outcome = |attempts, error| StepOutcome {
+        step: index,
+        attempts,
+        error,
+    };
+
+    let timeout = Duration::from_millis(options.timeout_ms);
+    let retry_delay = Duration::from_millis(options.retry_delay_ms);
+    let started = Instant::now();
+    let element = loop {
+        match step.matcher.resolve(desktop, options.max_depth) {
+            Ok(element) => break element,
+            // The app may still be opening the window or dialog of this step
+            Err(AutomationError::ElementNotFound(_)) if started.elapsed() < timeout => {
+                thread::sleep(retry_delay)
+            }
+            Err(e) => return outcome(0, Some(e)),
+        }
+    };
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match step.action.perform(&element) {
+            Ok(()) => return outcome(attempts, None),
+            Err(e) if attempts > options.retries => return outcome(attempts, Some(e)),
+            Err(_) => thread::sleep(retry_delay),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::platforms::mock::{MockAction, MockEngine};
+    use crate::operator::ElementSnapshot;
+    use std::sync::Arc;
+
+    fn node(
+        id: &str,
+        role: &str,
+        label: Option<&str>,
+        children: Vec<ElementSnapshot>,
+    ) -> ElementSnapshot {
+        let mut node = ElementSnapshot::new(id, Role::from_native(role));
+        node.native_role = role.to_string();
+        node.label = label.map(str::to_string);
+        node.children = children;
+        node
+    }
+
+    fn mail(buttons: Vec<ElementSnapshot>) -> ElementSnapshot {
+        node(
+            "app",
+            "AXApplication",
+            Some("Mail"),
+            vec![node(
+                "window",
+                "AXWindow",
+                Some("New Message"),
+                vec![
+                    node("to", "AXTextField", Some("To"), vec![]),
+                    node("toolbar", "AXGroup", None, buttons),
+                ],
+            )],
+        )
+    }
+
+    fn buttons() -> Vec<ElementSnapshot> {
+        vec![
+            node("attach", "AXButton", Some("Attach"), vec![]),
+            node("send", "AXButton", Some("Send"), vec![]),
+        ]
+    }
+
+    fn action(element: &str, action: &str, argument: Option<&str>) -> MockAction {
+        MockAction {
+            element: element.to_string(),
+            action: action.to_string(),
+            argument: argument.map(str::to_string),
+        }
+    }
+
+    fn record(desktop: &Desktop) -> AutomationScript {
+        let recorder = ScriptRecorder::new();
+        let app = desktop.application("Mail").unwrap();
+        let to = app.locator("#to").unwrap().first().unwrap().unwrap();
+        let send = app.locator("#send").unwrap().first().unwrap().unwrap();
+
+        // Not recording yet
+        recorder
+            .record("Mail", &app, &send, ScriptAction::Click)
+            .unwrap();
+        recorder.start_recording();
+        recorder
+            .record(
+                "Mail",
+                &app,
+                &to,
+                ScriptAction::TypeText {
+                    text: "jane@example.com".to_string(),
+                },
+            )
+            .unwrap();
+        recorder
+            .record("Mail", &app, &send, ScriptAction::Click)
+            .unwrap();
+        let script = recorder.stop_recording();
+        assert!(!recorder.is_recording());
+        script
+    }
+
+    #[test]
+    fn test_records_matchers() {
+        let desktop = Desktop::with_engine(Arc::new(MockEngine::new(vec![mail(buttons())])));
+        let script = record(&desktop);
+
+        assert_eq!(script.steps.len(), 2);
+        assert_eq!(
+            script.steps[1].matcher,
+            ElementMatcher {
+                app: "Mail".to_string(),
+                role: Role::Button,
+                label: Some("Send".to_string()),
+                path: vec![0, 1, 1],
+            }
+        );
+        assert_eq!(script.steps[0].matcher.path, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_replays_after_elements_moved() {
+        let script = record(&Desktop::with_engine(Arc::new(MockEngine::new(vec![
+            mail(buttons()),
+        ]))));
+
+        // The app came back with a new button before Send and new IDs
+        let engine = MockEngine::new(vec![mail(vec![
+            node("format", "AXButton", Some("Format"), vec![]),
+            node("attach2", "AXButton", Some("Attach"), vec![]),
+            node("send2", "AXButton", Some("Send"), vec![]),
+        ])]);
+        let desktop = Desktop::with_engine(Arc::new(engine.clone()));
+
+        let report = replay_script(&desktop, &script, &ReplayOptions::default());
+        assert!(report.completed);
+        assert_eq!(
+            engine.action_log(),
+            vec![
+                action("to", "type_text", Some("jane@example.com")),
+                action("send2", "click", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tells_look_alike_siblings_apart() {
+        let rows = |prefix: &str| {
+            (0..3)
+                .map(|i| node(&format!("{}{}", prefix, i), "AXRow", Some("Draft"), vec![]))
+                .collect()
+        };
+        let desktop = Desktop::with_engine(Arc::new(MockEngine::new(vec![mail(rows("row"))])));
+        let app = desktop.application("Mail").unwrap();
+        let third = app.locator("#row2").unwrap().first().unwrap().unwrap();
+
+        let recorder = ScriptRecorder::new();
+        recorder.start_recording();
+        recorder
+            .record("Mail", &app, &third, ScriptAction::Click)
+            .unwrap();
+        let script = recorder.stop_recording();
+        assert_eq!(script.steps[0].matcher.path, vec![0, 1, 2]);
+
+        let engine = MockEngine::new(vec![mail(rows("new"))]);
+        let desktop = Desktop::with_engine(Arc::new(engine.clone()));
+        assert!(replay_script(&desktop, &script, &ReplayOptions::default()).completed);
+        assert_eq!(engine.action_log(), vec![action("new2", "click", None)]);
+    }
+
+    #[test]
+    fn test_missing_elements_fail_the_step() {
+        let script = record(&Desktop::with_engine(Arc::new(MockEngine::new(vec![
+            mail(buttons()),
+        ]))));
+        let engine = MockEngine::new(vec![mail(vec![])]);
+        let desktop = Desktop::with_engine(Arc::new(engine.clone()));
+        let options = ReplayOptions {
+            timeout_ms: 0,
+            ..ReplayOptions::default()
+        };
+
+        let mut reversed = script.clone();
+        reversed.steps.reverse();
+        let report = replay_script(&desktop, &reversed, &options);
+        assert!(!report.completed);
+        assert_eq!(report.steps.len(), 1);
+        assert!(matches!(
+            report.steps[0].error,
+            Some(AutomationError::ElementNotFound(_))
+        ));
+        assert!(engine.action_log().is_empty());
+
+        let report = replay_script(
+            &desktop,
+            &reversed,
+            &ReplayOptions {
+                continue_on_failure: true,
+                ..options
+            },
+        );
+        assert!(!report.completed);
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.steps[1].attempts, 1);
+        assert!(report.steps[1].error.is_none());
+    }
+}
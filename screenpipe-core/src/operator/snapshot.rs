@@ -0,0 +1,975 @@
+//! Serializable snapshots of accessibility trees
+//!
+//! A snapshot is a plain copy of an element subtree that no longer holds on to
+//! platform handles, so it can be cached, diffed and sent over the wire.
+
+use crate::operator::{AutomationError, DisplayLayout, Role, UIElement};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use tracing::{debug, instrument};
+
+/// Position and size of an element in screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Bounds {
+    /// Whether the two rectangles overlap
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    /// Grow the rectangle by `margin` on every side
+    pub fn expand(&self, margin: f64) -> Bounds {
+        Bounds {
+            x: self.x - margin,
+            y: self.y - margin,
+            width: self.width + margin * 2.0,
+            height: self.height + margin * 2.0,
+        }
+    }
+
+    /// Smallest rectangle containing both
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Bounds {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+impl From<(f64, f64, f64, f64)> for Bounds {
+    fn from((x, y, width, height): (f64, f64, f64, f64)) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// A detached copy of a UI element and its descendants
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementSnapshot {
+    /// Stable ID of the element, unique within the snapshot
+    pub id: String,
+    pub role: Role,
+    /// Role as named by the platform, e.g. "AXButton"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub native_role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Set when `value` was cut to `max_value_length` characters
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub value_truncated: bool,
+    /// Length in characters of the value before truncation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_value_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The element holds secret text, so `value` is always missing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<Bounds>,
+    /// ID of the display showing most of the element, see `assign_displays`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ElementSnapshot>,
+    /// The owning application timed out while listing children, so they are missing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unresponsive: bool,
+    /// Number of wrapper groups spliced out directly above this element
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub collapsed: usize,
+}
+
+/// Options controlling how much of the tree is captured
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// Maximum depth below the root element, the root itself is depth 0
+    pub max_depth: usize,
+    /// Skip elements that lie outside of their window
+    pub visible_only: bool,
+    /// Distance in points around a window that still counts as visible
+    pub visible_margin: f64,
+    /// Cut element values longer than this many characters
+    pub max_value_length: Option<usize>,
+    /// Stop adding elements once the serialized snapshot would exceed this size
+    pub max_payload_bytes: Option<usize>,
+    /// Splice out unlabeled groups that only wrap a single child
+    pub collapse_groups: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 30,
+            visible_only: false,
+            visible_margin: 50.0,
+            max_value_length: Some(2048),
+            max_payload_bytes: None,
+            collapse_groups: false,
+        }
+    }
+}
+
+/// Counters collected while capturing a snapshot
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureStats {
+    /// Elements included in the snapshot
+    pub captured: usize,
+    /// Elements left out, with their subtrees, for lying outside their window
+    pub skipped_offscreen: usize,
+    /// Elements left out, with their subtrees, once `max_payload_bytes` was reached
+    pub dropped_over_budget: usize,
+    /// Approximate size of the serialized snapshot
+    pub payload_bytes: usize,
+    /// Elements left out for showing up again among their own descendants
+    pub cycles_skipped: usize,
+    /// Wrapper groups spliced out by `collapse_groups`
+    pub collapsed_groups: usize,
+    /// Elements whose children could not be listed, up to the first 16
+    pub errors: Vec<CaptureError>,
+}
+
+/// A part of the tree missing from a snapshot because the platform failed to
+/// list it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureError {
+    /// ID of the element whose children are missing
+    pub element: String,
+    /// Machine readable kind, as returned by `AutomationError::kind`
+    pub kind: String,
+    pub message: String,
+}
+
+// Apps that fail at all usually fail for every element, the first few say
+// enough about why
+const MAX_REPORTED_ERRORS: usize = 16;
+
+/// Hard limit on the capture depth, whatever `max_depth` asks for
+const MAX_CAPTURE_DEPTH: usize = 512;
+
+impl ElementSnapshot {
+    /// An element with only an ID and a role, to fill in by hand, as for
+    /// fixtures
+    pub fn new(id: impl Into<String>, role: Role) -> Self {
+        Self {
+            id: id.into(),
+            role,
+            native_role: String::new(),
+            label: None,
+            value: None,
+            value_truncated: false,
+            original_value_length: None,
+            description: None,
+            redacted: false,
+            bounds: None,
+            display: None,
+            children: Vec::new(),
+            unresponsive: false,
+            collapsed: 0,
+        }
+    }
+
+    /// Capture the subtree rooted at `element`
+    pub fn capture(element: &UIElement, options: &SnapshotOptions) -> Self {
+        Self::capture_with_stats(element, options).0
+    }
+
+    /// Capture the subtree rooted at `element`, along with what was left out
+    #[instrument(level = "debug", skip_all)]
+    pub fn capture_with_stats(
+        element: &UIElement,
+        options: &SnapshotOptions,
+    ) -> (Self, CaptureStats) {
+        let mut capture = Capture {
+            options,
+            seen_ids: HashSet::new(),
+            stats: CaptureStats::default(),
+            wanted: None,
+            found: None,
+        };
+        let mut root = capture.run(element);
+        if options.collapse_groups {
+            capture.stats.collapsed_groups = root.collapse_groups();
+        }
+        debug!("Captured snapshot: {:?}", capture.stats);
+        (root, capture.stats)
+    }
+
+    /// Find the live element a capture of `root` with `options` lists under `id`
+    ///
+    /// Walks the tree the way `capture` does, so IDs made unique with a
+    /// suffix, like "ax_1f_2", and position IDs resolve to the element they
+    /// were given to, as long as the tree did not change in between. Stops at
+    /// that element.
+    pub fn find_live(root: &UIElement, options: &SnapshotOptions, id: &str) -> Option<UIElement> {
+        let mut capture = Capture {
+            options,
+            seen_ids: HashSet::new(),
+            stats: CaptureStats::default(),
+            wanted: Some(id),
+            found: None,
+        };
+        capture.run(root);
+        capture.found
+    }
+
+    /// Find an element of this subtree by ID
+    pub fn find(&self, id: &str) -> Option<&ElementSnapshot> {
+        self.iter().find(|element| element.id == id)
+    }
+
+    /// Find an element of this subtree by ID, mutably
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut ElementSnapshot> {
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            if element.id == id {
+                return Some(element);
+            }
+            stack.extend(element.children.iter_mut().rev());
+        }
+        None
+    }
+
+    /// Detach a descendant (not the element itself) and return it
+    pub fn remove_descendant(&mut self, id: &str) -> Option<ElementSnapshot> {
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            if let Some(index) = element.children.iter().position(|child| child.id == id) {
+                return Some(element.children.remove(index));
+            }
+            stack.extend(element.children.iter_mut());
+        }
+        None
+    }
+
+    /// Drop descendants that neither match `keep` nor contain an element that
+    /// does and return how many elements were removed
+    ///
+    /// Ancestors of matching elements stay so the result is still a tree. The
+    /// element itself is never removed.
+    pub fn prune(&mut self, keep: impl Fn(&ElementSnapshot) -> bool) -> usize {
+        let mut kept = HashSet::new();
+        let mut removed = 0;
+        {
+            // In reverse pre-order every child comes before its parent
+            let elements: Vec<&ElementSnapshot> = self.iter().collect();
+            for element in elements.into_iter().rev() {
+                if keep(element)
+                    || element
+                        .children
+                        .iter()
+                        .any(|child| kept.contains(child.id.as_str()))
+                {
+                    kept.insert(element.id.clone());
+                }
+            }
+        }
+
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            element.children.retain(|child| {
+                let keep = kept.contains(child.id.as_str());
+                if !keep {
+                    removed += child.element_count();
+                }
+                keep
+            });
+            stack.extend(element.children.iter_mut());
+        }
+        removed
+    }
+
+    /// Splice out unlabeled groups with exactly one child and return how many
+    /// were removed
+    ///
+    /// The surviving child takes the union of the frames and counts the
+    /// wrappers above it in `collapsed`. IDs are left alone, so elements keep
+    /// the IDs they had in the uncollapsed tree. The element itself is never
+    /// removed.
+    pub fn collapse_groups(&mut self) -> usize {
+        let mut collapsed = 0;
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            for child in element.children.iter_mut() {
+                while child.is_wrapper_group() {
+                    let mut inner = child.children.pop().expect("wrappers have one child");
+                    inner.bounds = match (child.bounds, inner.bounds) {
+                        (Some(outer), Some(bounds)) => Some(outer.union(&bounds)),
+                        (outer, bounds) => bounds.or(outer),
+                    };
+                    inner.collapsed += child.collapsed + 1;
+                    *child = inner;
+                    collapsed += 1;
+                }
+            }
+            stack.extend(element.children.iter_mut());
+        }
+        collapsed
+    }
+
+    fn is_wrapper_group(&self) -> bool {
+        let is_blank = |text: &Option<String>| text.as_deref().is_none_or(str::is_empty);
+        self.children.len() == 1
+            && !self.unresponsive
+            && is_blank(&self.label)
+            && is_blank(&self.value)
+            && is_blank(&self.description)
+            && self.role == Role::Group
+    }
+
+    /// Record which display shows each element of this subtree
+    ///
+    /// Elements without bounds, or outside every display, get no display.
+    pub fn assign_displays(&mut self, layout: &DisplayLayout) {
+        let mut stack = vec![self];
+        while let Some(element) = stack.pop() {
+            element.display = element
+                .bounds
+                .and_then(|bounds| layout.display_for(&bounds))
+                .map(|display| display.id);
+            stack.extend(element.children.iter_mut());
+        }
+    }
+
+    /// Hash of this element and everything below it
+    ///
+    /// Hashes are only comparable within the same process.
+    pub fn content_hash(&self) -> u64 {
+        self.subtree_hashes()
+            .get(self.id.as_str())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Hash of every subtree, keyed by the ID of its root element
+    pub fn subtree_hashes(&self) -> HashMap<&str, u64> {
+        let mut hashes = HashMap::new();
+        // In reverse pre-order every child comes before its parent
+        let elements: Vec<&ElementSnapshot> = self.iter().collect();
+        for element in elements.into_iter().rev() {
+            let mut hasher = DefaultHasher::new();
+            element.hash_fields(&mut hasher);
+            for child in &element.children {
+                hashes.get(child.id.as_str()).hash(&mut hasher);
+            }
+            hashes.insert(element.id.as_str(), hasher.finish());
+        }
+        hashes
+    }
+
+    fn hash_fields(&self, hasher: &mut impl Hasher) {
+        self.id.hash(hasher);
+        self.role.hash(hasher);
+        self.native_role.hash(hasher);
+        self.label.hash(hasher);
+        self.value.hash(hasher);
+        self.value_truncated.hash(hasher);
+        self.original_value_length.hash(hasher);
+        self.description.hash(hasher);
+        self.bounds
+            .map(|b| [b.x, b.y, b.width, b.height].map(f64::to_bits))
+            .hash(hasher);
+        self.display.hash(hasher);
+        self.redacted.hash(hasher);
+        self.unresponsive.hash(hasher);
+        self.collapsed.hash(hasher);
+    }
+
+    /// Number of elements in this subtree, including the element itself
+    pub fn element_count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Elements of this subtree that take input, see `Role::is_input`
+    pub fn input_elements(&self) -> Vec<&ElementSnapshot> {
+        self.iter()
+            .filter(|element| element.role.is_input())
+            .collect()
+    }
+
+    /// Iterate over this subtree in pre-order
+    pub fn iter(&self) -> SnapshotIter<'_> {
+        SnapshotIter { stack: vec![self] }
+    }
+
+    /// Read a snapshot back from its JSON
+    ///
+    /// Each element nests two levels deep in JSON, so `serde_json::from_slice`
+    /// gives up on trees deeper than 64 elements, well short of what a capture
+    /// may reach. This lifts the limit and grows the stack as needed instead.
+    pub fn from_json(json: &[u8]) -> serde_json::Result<Self> {
+        let mut deserializer = serde_json::Deserializer::from_slice(json);
+        deserializer.disable_recursion_limit();
+        let snapshot = Self::deserialize(serde_stacker::Deserializer::new(&mut deserializer))?;
+        deserializer.end()?;
+        Ok(snapshot)
+    }
+}
+
+// The generated drop glue recurses once per level, flatten the subtree first so
+// very deep snapshots cannot overflow the stack when they go away
+impl Drop for ElementSnapshot {
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(mut element) = stack.pop() {
+            stack.append(&mut element.children);
+        }
+    }
+}
+
+/// Pre-order iterator over the elements of a snapshot
+pub struct SnapshotIter<'a> {
+    stack: Vec<&'a ElementSnapshot>,
+}
+
+impl<'a> Iterator for SnapshotIter<'a> {
+    type Item = &'a ElementSnapshot;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.stack.pop()?;
+        self.stack.extend(element.children.iter().rev());
+        Some(element)
+    }
+}
+
+struct Capture<'a> {
+    options: &'a SnapshotOptions,
+    seen_ids: HashSet<String>,
+    stats: CaptureStats,
+    // Stop at the element getting this ID, keeping it in `found`
+    wanted: Option<&'a str>,
+    found: Option<UIElement>,
+}
+
+// An element waiting on the capture stack
+struct PendingElement {
+    element: UIElement,
+    depth: usize,
+    path: String,
+    viewport: Option<Bounds>,
+    parent: Option<usize>,
+}
+
+// A captured element, linked to its parent by index until the tree is assembled
+struct CapturedElement {
+    snapshot: ElementSnapshot,
+    identity: Option<u64>,
+    parent: Option<usize>,
+}
+
+impl Capture<'_> {
+    // Walks the tree with an explicit stack, broken apps can report trees
+    // hundreds of levels deep or list an element among its own descendants
+    fn run(&mut self, root: &UIElement) -> ElementSnapshot {
+        let max_depth = self.options.max_depth.min(MAX_CAPTURE_DEPTH);
+        let mut captured: Vec<CapturedElement> = Vec::new();
+        // A root inside a window is checked against that window, a window
+        // root sets its own viewport below
+        let viewport = if self.options.visible_only {
+            enclosing_window_bounds(root).map(|bounds| bounds.expand(self.options.visible_margin))
+        } else {
+            None
+        };
+        let mut stack = vec![PendingElement {
+            element: root.clone(),
+            depth: 0,
+            path: "0".to_string(),
+            viewport,
+            parent: None,
+        }];
+
+        while let Some(pending) = stack.pop() {
+            let identity = pending.element.native_identity();
+            let bounds = match pending.parent {
+                // The root is always kept, even when it is off screen itself
+                None => pending.element.bounds().ok().map(Bounds::from),
+                Some(parent) => {
+                    if self.over_budget() {
+                        self.stats.dropped_over_budget += 1;
+                        continue;
+                    }
+                    let bounds = pending.element.bounds().ok().map(Bounds::from);
+                    if self.is_offscreen(&pending.element, bounds, pending.viewport) {
+                        self.stats.skipped_offscreen += 1;
+                        continue;
+                    }
+                    if is_own_ancestor(identity, parent, &captured) {
+                        debug!("Skipping element that contains itself at {}", pending.path);
+                        self.stats.cycles_skipped += 1;
+                        continue;
+                    }
+                    bounds
+                }
+            };
+
+            let mut snapshot = self.element(&pending.element, &pending.path, bounds);
+            if self.wanted == Some(snapshot.id.as_str()) {
+                self.found = Some(pending.element);
+                captured.push(CapturedElement {
+                    snapshot,
+                    identity,
+                    parent: pending.parent,
+                });
+                break;
+            }
+
+            // Descendants are checked against the closest enclosing window
+            let viewport = match bounds {
+                Some(bounds) if self.options.visible_only && snapshot.role == Role::Window => {
+                    Some(bounds.expand(self.options.visible_margin))
+                }
+                _ => pending.viewport,
+            };
+
+            let children = if pending.depth < max_depth {
+                match pending.element.children() {
+                    Ok(children) => children,
+                    Err(e) => {
+                        if let AutomationError::Timeout(_) = e {
+                            debug!(
+                                "Skipping children of unresponsive element {}: {}",
+                                snapshot.id, e
+                            );
+                            snapshot.unresponsive = true;
+                        }
+                        if self.stats.errors.len() < MAX_REPORTED_ERRORS {
+                            self.stats.errors.push(CaptureError {
+                                element: snapshot.id.clone(),
+                                kind: e.kind().to_string(),
+                                message: e.to_string(),
+                            });
+                        }
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            let index = captured.len();
+            // Pushed in reverse so they come off the stack in document order
+            for (child_index, child) in children.into_iter().enumerate().rev() {
+                stack.push(PendingElement {
+                    element: child,
+                    depth: pending.depth + 1,
+                    path: format!("{}.{}", pending.path, child_index),
+                    viewport,
+                    parent: Some(index),
+                });
+            }
+            captured.push(CapturedElement {
+                snapshot,
+                identity,
+                parent: pending.parent,
+            });
+        }
+
+        // Every element comes after its parent, so attach them back to front;
+        // siblings arrive last first and get flipped once their parent is done
+        let mut root = None;
+        while let Some(CapturedElement {
+            mut snapshot,
+            parent,
+            ..
+        }) = captured.pop()
+        {
+            snapshot.children.reverse();
+            match parent {
+                Some(parent) => captured[parent].snapshot.children.push(snapshot),
+                None => root = Some(snapshot),
+            }
+        }
+        root.expect("the root element is always captured")
+    }
+
+    fn element(
+        &mut self,
+        element: &UIElement,
+        path: &str,
+        bounds: Option<Bounds>,
+    ) -> ElementSnapshot {
+        let attributes = element.attributes();
+
+        // Not every platform exposes an ID for every element, fall back to the
+        // position in the tree so diffs can still key on something
+        let id = element
+            .id()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("path_{}", path));
+        let id = unique_id(id, &mut self.seen_ids);
+
+        let (value, original_value_length) = match attributes.value {
+            // Platforms leave out secret values already, never pass one on
+            Some(_) if attributes.redacted => (None, None),
+            Some(value) => {
+                let (value, original_length) = truncate_value(value, self.options.max_value_length);
+                (Some(value), original_length)
+            }
+            None => (None, None),
+        };
+
+        let snapshot = ElementSnapshot {
+            id,
+            role: Role::from_native(&attributes.role),
+            native_role: attributes.role,
+            label: attributes.label,
+            value,
+            value_truncated: original_value_length.is_some(),
+            original_value_length,
+            description: attributes.description,
+            redacted: attributes.redacted,
+            bounds,
+            display: None,
+            children: Vec::new(),
+            unresponsive: false,
+            collapsed: 0,
+        };
+        self.stats.captured += 1;
+        if self.options.max_payload_bytes.is_some() {
+            self.stats.payload_bytes += serde_json::to_vec(&snapshot).map_or(0, |b| b.len());
+        }
+        snapshot
+    }
+
+    fn over_budget(&self) -> bool {
+        self.options
+            .max_payload_bytes
+            .is_some_and(|budget| self.stats.payload_bytes >= budget)
+    }
+
+    fn is_offscreen(
+        &self,
+        element: &UIElement,
+        bounds: Option<Bounds>,
+        viewport: Option<Bounds>,
+    ) -> bool {
+        let (bounds, viewport) = match (bounds, viewport) {
+            (Some(bounds), Some(viewport)) if self.options.visible_only => (bounds, viewport),
+            _ => return false,
+        };
+
+        // Empty frames are common on containers and say nothing about where
+        // their children are
+        if bounds.width <= 0.0 || bounds.height <= 0.0 || bounds.intersects(&viewport) {
+            return false;
+        }
+
+        // Scroll areas report frames that do not always match what they
+        // display, so always look inside them
+        !is_scroll_area_role(&element.role())
+    }
+}
+
+// Bounds of the closest window above `element`, None when it is a window
+// itself or is not in one
+fn enclosing_window_bounds(element: &UIElement) -> Option<Bounds> {
+    let mut ancestor = element.parent().ok()?;
+    for _ in 0..MAX_CAPTURE_DEPTH {
+        let current = ancestor?;
+        if Role::from_native(&current.role()) == Role::Window {
+            return current.bounds().ok().map(Bounds::from);
+        }
+        ancestor = current.parent().ok()?;
+    }
+    None
+}
+
+// Whether the element at `identity` is already on the path from the root to `parent`
+fn is_own_ancestor(identity: Option<u64>, parent: usize, captured: &[CapturedElement]) -> bool {
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return false,
+    };
+    let mut ancestor = Some(parent);
+    while let Some(index) = ancestor {
+        if captured[index].identity == Some(identity) {
+            return true;
+        }
+        ancestor = captured[index].parent;
+    }
+    false
+}
+
+// Returns the possibly shortened value and, when it was cut, its original length
+fn truncate_value(value: String, max_length: Option<usize>) -> (String, Option<usize>) {
+    let max_length = match max_length {
+        Some(max_length) => max_length,
+        None => return (value, None),
+    };
+
+    match value.char_indices().nth(max_length) {
+        Some((end, _)) => {
+            let original_length = value.chars().count();
+            (value[..end].to_string(), Some(original_length))
+        }
+        None => (value, None),
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn is_scroll_area_role(role: &str) -> bool {
+    matches!(role.to_lowercase().as_str(), "axscrollarea" | "scrollarea")
+}
+
+// Stable IDs are hashes of a few attributes, so identical siblings collide
+fn unique_id(id: String, seen_ids: &mut HashSet<String>) -> String {
+    if seen_ids.insert(id.clone()) {
+        return id;
+    }
+
+    let mut suffix = 2;
+    loop {
+        let candidate = format!("{}_{}", id, suffix);
+        if seen_ids.insert(candidate.clone()) {
+            return candidate;
+        }
+        suffix += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::DisplayInfo;
+
+    fn node(id: &str, children: Vec<ElementSnapshot>) -> ElementSnapshot {
+        let mut node = ElementSnapshot::new(id, Role::Group);
+        node.children = children;
+        node
+    }
+
+    // Built bottom up, one level per iteration
+    fn chain(depth: usize) -> ElementSnapshot {
+        let mut element = node(&format!("n{}", depth - 1), vec![]);
+        for level in (0..depth - 1).rev() {
+            element = node(&format!("n{}", level), vec![element]);
+        }
+        element
+    }
+
+    #[test]
+    fn test_iter_is_pre_order() {
+        let tree = node(
+            "root",
+            vec![node("a", vec![node("a1", vec![])]), node("b", vec![])],
+        );
+        let ids: Vec<&str> = tree.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["root", "a", "a1", "b"]);
+        assert_eq!(tree.element_count(), 4);
+    }
+
+    #[test]
+    fn test_remove_descendant() {
+        let mut tree = node(
+            "root",
+            vec![node("a", vec![node("a1", vec![])]), node("b", vec![])],
+        );
+        let removed = tree.remove_descendant("a1").unwrap();
+        assert_eq!(removed.id, "a1");
+        assert!(tree.find("a1").is_none());
+        assert!(tree.remove_descendant("root").is_none());
+    }
+
+    #[test]
+    fn test_deep_chain_does_not_overflow() {
+        let mut tree = chain(10_000);
+        assert_eq!(tree.element_count(), 10_000);
+        assert_eq!(tree.subtree_hashes().len(), 10_000);
+        assert!(tree.find_mut("n9999").is_some());
+
+        let removed = tree.remove_descendant("n5000").unwrap();
+        assert_eq!(removed.element_count(), 5_000);
+        assert_eq!(tree.element_count(), 5_000);
+    }
+
+    #[test]
+    fn test_deep_tree_round_trips_through_json() {
+        let tree = chain(200);
+        let json = serde_json::to_vec(&tree).unwrap();
+        assert!(serde_json::from_slice::<ElementSnapshot>(&json).is_err());
+        assert_eq!(ElementSnapshot::from_json(&json).unwrap(), tree);
+        assert!(ElementSnapshot::from_json(b"{} trailing").is_err());
+    }
+
+    #[test]
+    fn test_assign_displays() {
+        let layout = DisplayLayout::new(vec![
+            DisplayInfo {
+                id: 1,
+                bounds: Bounds::from((0.0, 0.0, 1512.0, 982.0)),
+                primary: true,
+                scale_factor: 1.0,
+            },
+            DisplayInfo {
+                id: 2,
+                bounds: Bounds::from((-1920.0, 0.0, 1920.0, 1080.0)),
+                primary: false,
+                scale_factor: 1.0,
+            },
+        ]);
+        let mut left = node("left", vec![node("unplaced", vec![])]);
+        left.bounds = Some(Bounds::from((-800.0, 100.0, 600.0, 400.0)));
+        let mut right = node("right", vec![]);
+        right.bounds = Some(Bounds::from((100.0, 100.0, 600.0, 400.0)));
+        let mut tree = node("root", vec![left, right]);
+
+        tree.assign_displays(&layout);
+
+        assert_eq!(tree.find("left").unwrap().display, Some(2));
+        assert_eq!(tree.find("right").unwrap().display, Some(1));
+        assert_eq!(tree.find("unplaced").unwrap().display, None);
+        assert_eq!(tree.display, None);
+    }
+
+    #[test]
+    fn test_collapse_groups_splices_wrapper_chains() {
+        let mut leaf = node("button", vec![]);
+        leaf.role = Role::Button;
+        leaf.bounds = Some(Bounds::from((10.0, 10.0, 20.0, 20.0)));
+        let mut outer = node("g1", vec![node("g2", vec![node("g3", vec![leaf])])]);
+        outer.native_role = "AXGroup".to_string();
+        outer.bounds = Some(Bounds::from((0.0, 0.0, 100.0, 15.0)));
+        let mut labelled = node("labelled", vec![node("g4", vec![node("leaf", vec![])])]);
+        labelled.label = Some("Toolbar".to_string());
+        let mut tree = node("root", vec![outer, labelled]);
+
+        assert_eq!(tree.collapse_groups(), 4);
+
+        let ids: Vec<&str> = tree.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["root", "button", "labelled", "leaf"]);
+        let button = tree.find("button").unwrap();
+        assert_eq!(button.collapsed, 3);
+        assert_eq!(button.bounds, Some(Bounds::from((0.0, 0.0, 100.0, 30.0))));
+        assert_eq!(tree.find("leaf").unwrap().collapsed, 1);
+
+        // Groups with more than one child carry structure and stay
+        let mut tree = node(
+            "root",
+            vec![node("g", vec![node("a", vec![]), node("b", vec![])])],
+        );
+        assert_eq!(tree.collapse_groups(), 0);
+    }
+
+    #[test]
+    fn test_bounds_intersection() {
+        let window = Bounds::from((0.0, 0.0, 100.0, 100.0));
+        assert!(Bounds::from((50.0, 50.0, 100.0, 100.0)).intersects(&window));
+        assert!(!Bounds::from((100.0, 0.0, 10.0, 10.0)).intersects(&window));
+        assert!(!Bounds::from((0.0, 500.0, 10.0, 10.0)).intersects(&window));
+        assert!(Bounds::from((0.0, 120.0, 10.0, 10.0)).intersects(&window.expand(50.0)));
+    }
+
+    #[test]
+    fn test_content_hash_tracks_subtree_changes() {
+        let tree = node(
+            "root",
+            vec![node("a", vec![node("a1", vec![])]), node("b", vec![])],
+        );
+        let mut changed = tree.clone();
+        changed.children[0].children[0].value = Some("new".to_string());
+
+        assert_eq!(tree.content_hash(), tree.clone().content_hash());
+        assert_ne!(tree.content_hash(), changed.content_hash());
+
+        let before = tree.subtree_hashes();
+        let after = changed.subtree_hashes();
+        assert_ne!(before["a"], after["a"]);
+        assert_eq!(before["b"], after["b"]);
+    }
+
+    #[test]
+    fn test_prune_keeps_ancestors_of_matches() {
+        let mut tree = node(
+            "root",
+            vec![
+                node("a", vec![node("a1", vec![node("match", vec![])])]),
+                node("b", vec![node("b1", vec![]), node("b2", vec![])]),
+            ],
+        );
+
+        assert_eq!(tree.prune(|e| e.id == "match"), 3);
+
+        let ids: Vec<&str> = tree.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["root", "a", "a1", "match"]);
+        assert_eq!(tree.prune(|_| false), 3);
+        assert_eq!(tree.element_count(), 1);
+    }
+
+    #[test]
+    fn test_input_elements_use_platform_neutral_roles() {
+        let with_role = |id: &str, native_role: &str| {
+            let mut element = node(id, vec![]);
+            element.role = Role::from_native(native_role);
+            element.native_role = native_role.to_string();
+            element
+        };
+        let tree = node(
+            "window",
+            vec![
+                with_role("search", "AXSearchField"),
+                with_role("notes", "Edit"),
+                with_role("send", "AXButton"),
+                with_role("split", "AXSplitter"),
+            ],
+        );
+
+        let inputs: Vec<&str> = tree
+            .input_elements()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(inputs, vec!["search", "notes"]);
+        assert_eq!(
+            tree.find("split").unwrap().role,
+            Role::Unknown("AXSplitter".to_string())
+        );
+    }
+
+    #[test]
+    fn test_truncate_value_counts_characters() {
+        assert_eq!(
+            truncate_value("héllo wörld".to_string(), Some(4)),
+            ("héll".to_string(), Some(11))
+        );
+        assert_eq!(
+            truncate_value("short".to_string(), Some(5)),
+            ("short".to_string(), None)
+        );
+        assert_eq!(
+            truncate_value("no limit".to_string(), None),
+            ("no limit".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_unique_id_suffixes_duplicates() {
+        let mut seen = HashSet::new();
+        assert_eq!(unique_id("ax_1".to_string(), &mut seen), "ax_1");
+        assert_eq!(unique_id("ax_1".to_string(), &mut seen), "ax_1_2");
+        assert_eq!(unique_id("ax_1".to_string(), &mut seen), "ax_1_3");
+    }
+}
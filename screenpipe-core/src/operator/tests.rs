@@ -5,7 +5,7 @@ mod tests {
     use super::*;
     use tracing_subscriber::{filter::LevelFilter, fmt, EnvFilter};
 
-    #[cfg(target_os = "macos")]
+    #[cfg(all(target_os = "macos", feature = "backend-macos"))]
     mod macos_tests {
         use serde_json::Value;
 
@@ -305,4 +305,18 @@ mod tests {
             // println!("children: {:?}", children.len());
         }
     }
+
+    #[cfg(target_os = "linux")]
+    mod linux_tests {
+        use crate::Desktop;
+
+        // Callers tell "not available here" from a failure by the kind alone
+        #[test]
+        fn test_desktop_reports_unsupported_platform() {
+            match Desktop::new(false, false) {
+                Ok(_) => panic!("Linux has no accessibility backend yet"),
+                Err(e) => assert_eq!(e.kind(), "unsupported_platform"),
+            }
+        }
+    }
 }
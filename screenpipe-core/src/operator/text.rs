@@ -0,0 +1,117 @@
+//! Conversion between char and UTF-16 text offsets
+//!
+//! Platform text APIs count UTF-16 code units, where anything outside the basic
+//! multilingual plane, like most emoji, takes two. Ranges exposed by this crate
+//! count chars instead and are only converted where they reach a platform API.
+//! A char is a Unicode scalar value, so a combining accent counts on its own.
+
+use std::ops::Range;
+
+/// Convert a range of chars of `text` to UTF-16 code units
+///
+/// Returns None when the range ends past the end of `text`.
+pub fn char_range_to_utf16(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    if range.start > range.end {
+        return None;
+    }
+    let start = char_to_utf16(text, range.start)?;
+    let end = char_to_utf16(text, range.end)?;
+    Some(start..end)
+}
+
+/// Convert a range of UTF-16 code units of `text` to chars
+///
+/// Returns None when the range ends past the end of `text` or either end falls
+/// between the two halves of a surrogate pair.
+pub fn utf16_range_to_char(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    if range.start > range.end {
+        return None;
+    }
+    let start = utf16_to_char(text, range.start)?;
+    let end = utf16_to_char(text, range.end)?;
+    Some(start..end)
+}
+
+fn char_to_utf16(text: &str, offset: usize) -> Option<usize> {
+    let mut units = 0;
+    let mut chars = text.chars();
+    for _ in 0..offset {
+        units += chars.next()?.len_utf16();
+    }
+    Some(units)
+}
+
+fn utf16_to_char(text: &str, offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (index, c) in text.chars().enumerate() {
+        if units == offset {
+            return Some(index);
+        }
+        if units > offset {
+            return None;
+        }
+        units += c.len_utf16();
+    }
+    (units == offset).then(|| text.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_offsets_match() {
+        assert_eq!(char_range_to_utf16("hello", 1..4), Some(1..4));
+        assert_eq!(utf16_range_to_char("hello", 1..4), Some(1..4));
+        assert_eq!(char_range_to_utf16("hello", 5..5), Some(5..5));
+        assert_eq!(char_range_to_utf16("hello", 3..6), None);
+    }
+
+    #[test]
+    fn test_emoji_take_two_units() {
+        let text = "a😀b👍🏽c";
+        // 👍🏽 is a thumbs up followed by a skin tone modifier, two chars
+        assert_eq!(text.chars().count(), 6);
+        assert_eq!(char_range_to_utf16(text, 2..3), Some(3..4));
+        assert_eq!(char_range_to_utf16(text, 3..5), Some(4..8));
+        assert_eq!(char_range_to_utf16(text, 0..6), Some(0..9));
+        assert_eq!(utf16_range_to_char(text, 3..4), Some(2..3));
+        assert_eq!(utf16_range_to_char(text, 0..9), Some(0..6));
+
+        // Half of the first emoji is not a valid position
+        assert_eq!(utf16_range_to_char(text, 2..3), None);
+        assert_eq!(utf16_range_to_char(text, 0..10), None);
+    }
+
+    #[test]
+    fn test_combining_characters_count_separately() {
+        // "é" as e followed by a combining acute accent
+        let text = "cafe\u{301}!";
+        assert_eq!(char_range_to_utf16(text, 3..5), Some(3..5));
+        assert_eq!(utf16_range_to_char(text, 5..6), Some(5..6));
+    }
+
+    #[test]
+    fn test_cjk_stays_in_one_unit() {
+        let text = "日本語のテキスト";
+        assert_eq!(char_range_to_utf16(text, 2..5), Some(2..5));
+        assert_eq!(utf16_range_to_char(text, 0..8), Some(0..8));
+
+        // Extension B ideographs are outside the basic multilingual plane
+        let text = "𠀋x";
+        assert_eq!(char_range_to_utf16(text, 1..2), Some(2..3));
+        assert_eq!(utf16_range_to_char(text, 2..3), Some(1..2));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "x😀é日𠀋\u{301}👍🏽";
+        let count = text.chars().count();
+        for start in 0..=count {
+            for end in start..=count {
+                let units = char_range_to_utf16(text, start..end).unwrap();
+                assert_eq!(utf16_range_to_char(text, units), Some(start..end));
+            }
+        }
+    }
+}
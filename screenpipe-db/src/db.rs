@@ -23,7 +23,7 @@ use crate::{
     AudioChunksResponse, AudioDevice, AudioEntry, AudioResult, AudioResultRaw, ContentType,
     DeviceType, FrameData, FrameRow, OCREntry, OCRResult, OCRResultRaw, OcrEngine, OcrTextBlock,
     Order, SearchMatch, SearchResult, Speaker, TagContentType, TextBounds, TextPosition,
-    TimeSeriesChunk, UiContent, VideoMetadata,
+    TimeSeriesChunk, UiContent, UiSnapshotMeta, VideoMetadata,
 };
 
 pub struct DatabaseManager {
@@ -965,6 +965,146 @@ impl DatabaseManager {
         .await
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_ui_snapshot(
+        &self,
+        timestamp: DateTime<Utc>,
+        app_name: &str,
+        window_name: Option<&str>,
+        focused_label: Option<&str>,
+        element_count: i64,
+        data: &[u8],
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO ui_snapshots
+                (timestamp, app_name, window_name, focused_label, element_count, data, data_size)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(timestamp)
+        .bind(app_name)
+        .bind(window_name)
+        .bind(focused_label)
+        .bind(element_count)
+        .bind(data)
+        .bind(data.len() as i64)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Stored snapshots taken between `from` and `to`, oldest first
+    pub async fn query_ui_snapshots(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        app_name: Option<&str>,
+    ) -> Result<Vec<UiSnapshotMeta>, sqlx::Error> {
+        sqlx::query_as::<_, UiSnapshotMeta>(
+            r#"
+            SELECT id, timestamp, app_name, window_name, focused_label, element_count, data_size
+            FROM ui_snapshots
+            WHERE timestamp >= ?1
+                AND timestamp <= ?2
+                AND (?3 IS NULL OR app_name = ?3)
+            ORDER BY timestamp, id
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .bind(app_name)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// The last stored snapshot taken at or before `timestamp` and the first
+    /// one taken after it, whichever exist
+    pub async fn nearest_ui_snapshots(
+        &self,
+        timestamp: DateTime<Utc>,
+        app_name: Option<&str>,
+    ) -> Result<Vec<UiSnapshotMeta>, sqlx::Error> {
+        sqlx::query_as::<_, UiSnapshotMeta>(
+            r#"
+            SELECT * FROM (
+                SELECT id, timestamp, app_name, window_name, focused_label, element_count, data_size
+                FROM ui_snapshots
+                WHERE timestamp <= ?1 AND (?2 IS NULL OR app_name = ?2)
+                ORDER BY timestamp DESC, id DESC
+                LIMIT 1
+            )
+            UNION ALL
+            SELECT * FROM (
+                SELECT id, timestamp, app_name, window_name, focused_label, element_count, data_size
+                FROM ui_snapshots
+                WHERE timestamp > ?1 AND (?2 IS NULL OR app_name = ?2)
+                ORDER BY timestamp, id
+                LIMIT 1
+            )
+            "#,
+        )
+        .bind(timestamp)
+        .bind(app_name)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Compressed tree of a stored snapshot
+    pub async fn get_ui_snapshot_data(&self, id: i64) -> Result<Option<Vec<u8>>, sqlx::Error> {
+        sqlx::query_scalar::<_, Vec<u8>>("SELECT data FROM ui_snapshots WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Delete snapshots taken before `older_than`, then the oldest ones until
+    /// the rest take at most `max_total_bytes`. Returns how many were deleted
+    pub async fn prune_ui_snapshots(
+        &self,
+        older_than: DateTime<Utc>,
+        max_total_bytes: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let expired = sqlx::query("DELETE FROM ui_snapshots WHERE timestamp < ?1")
+            .bind(older_than)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        let over_budget = sqlx::query(
+            r#"
+            DELETE FROM ui_snapshots
+            WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, SUM(data_size) OVER (ORDER BY timestamp DESC, id DESC) AS total
+                    FROM ui_snapshots
+                )
+                WHERE total > ?1
+            )
+            "#,
+        )
+        .bind(max_total_bytes)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(expired + over_budget)
+    }
+
+    /// When a frame was captured and the app it shows, None if there is no such frame
+    pub async fn get_frame_time_and_app(
+        &self,
+        frame_id: i64,
+    ) -> Result<Option<(DateTime<Utc>, Option<String>)>, sqlx::Error> {
+        sqlx::query_as::<_, (DateTime<Utc>, Option<String>)>(
+            "SELECT timestamp, app_name FROM frames WHERE id = ?1",
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn count_search_results(
         &self,
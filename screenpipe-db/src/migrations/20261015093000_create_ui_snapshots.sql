@@ -0,0 +1,14 @@
+-- Accessibility snapshots, the tree is stored compressed in data
+CREATE TABLE IF NOT EXISTS ui_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp DATETIME NOT NULL,
+    app_name TEXT NOT NULL,
+    window_name TEXT DEFAULT NULL,
+    focused_label TEXT DEFAULT NULL,
+    element_count INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    data_size INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ui_snapshots_timestamp ON ui_snapshots(timestamp);
+CREATE INDEX IF NOT EXISTS idx_ui_snapshots_app_timestamp ON ui_snapshots(app_name, timestamp);
@@ -136,6 +136,20 @@ pub enum TagContentType {
     Audio,
 }
 
+/// A stored accessibility snapshot, without the compressed tree itself
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UiSnapshotMeta {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: Option<String>,
+    /// Label of the element holding keyboard focus when the snapshot was taken
+    pub focused_label: Option<String>,
+    pub element_count: i64,
+    /// Size of the compressed tree in bytes
+    pub data_size: i64,
+}
+
 #[derive(OaSchema, Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UiContent {
     pub id: i64,
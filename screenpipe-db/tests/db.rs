@@ -1445,4 +1445,65 @@ mod tests {
             .unwrap();
         assert_eq!(count, 0, "Should count zero results for non-matching query");
     }
+
+    #[tokio::test]
+    async fn test_ui_snapshots() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let minutes_ago = |minutes| now - chrono::Duration::minutes(minutes);
+
+        let old = db
+            .insert_ui_snapshot(minutes_ago(30), "Finder", None, None, 3, &[0; 10])
+            .await
+            .unwrap();
+        let finder = db
+            .insert_ui_snapshot(
+                minutes_ago(10),
+                "Finder",
+                Some("Downloads"),
+                Some("Search"),
+                12,
+                &[1; 40],
+            )
+            .await
+            .unwrap();
+        let slack = db
+            .insert_ui_snapshot(minutes_ago(5), "Slack", None, None, 7, &[2; 40])
+            .await
+            .unwrap();
+
+        let all = db
+            .query_ui_snapshots(minutes_ago(60), now, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            all.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![old, finder, slack]
+        );
+
+        let recent_finder = db
+            .query_ui_snapshots(minutes_ago(20), now, Some("Finder"))
+            .await
+            .unwrap();
+        assert_eq!(recent_finder.len(), 1);
+        assert_eq!(recent_finder[0].window_name.as_deref(), Some("Downloads"));
+        assert_eq!(recent_finder[0].focused_label.as_deref(), Some("Search"));
+        assert_eq!(recent_finder[0].element_count, 12);
+        assert_eq!(recent_finder[0].data_size, 40);
+
+        assert_eq!(
+            db.get_ui_snapshot_data(slack).await.unwrap(),
+            Some(vec![2; 40])
+        );
+        assert_eq!(db.get_ui_snapshot_data(-1).await.unwrap(), None);
+
+        // The old one expires, then the size limit drops the next oldest
+        let deleted = db.prune_ui_snapshots(minutes_ago(20), 50).await.unwrap();
+        assert_eq!(deleted, 2);
+        let left = db
+            .query_ui_snapshots(minutes_ago(60), now, None)
+            .await
+            .unwrap();
+        assert_eq!(left.iter().map(|s| s.id).collect::<Vec<_>>(), vec![slack]);
+    }
 }
@@ -7,6 +7,7 @@ import mcp.types as types
 import mcp.server.stdio
 import argparse
 import json
+import os
 import platform
 import sys
 
@@ -22,6 +23,7 @@ IS_LINUX = CURRENT_OS == "Linux"
 # Parse command line arguments
 parser = argparse.ArgumentParser(description='Screenpipe MCP Server')
 parser.add_argument('--port', type=int, default=3030, help='Port number for the screenpipe API (default: 3030)')
+parser.add_argument('--allow-actions', action='store_true', help='Let the model click, type, scroll and open apps or URLs without asking the user in a dialog first')
 args = parser.parse_args()
 
 # Initialize server
@@ -30,6 +32,143 @@ server = Server("screenpipe")
 # Constants
 SCREENPIPE_API = f"http://localhost:{args.port}"
 
+# Tools that change what is on screen
+ACTION_TOOLS = [
+    "pixel-control", "click-element", "fill-element", "scroll-element",
+    "open-application", "open-url",
+]
+
+# The server runs once per client session, so this lasts for the session. Only
+# the --allow-actions flag or the user answering a dialog set it, no tool does
+actions_allowed = args.allow_actions
+ask_lock = asyncio.Lock()
+
+# Seconds to wait for the user to answer before treating it as a refusal
+CONFIRM_TIMEOUT = 120
+
+def confirm_command(message: str) -> tuple[list[str], dict] | None:
+    """Command and extra environment showing a native dialog that exits with
+    status 0 when the user allows actions, None when there is no way to ask."""
+    if IS_MACOS:
+        script = (
+            'on run argv\n'
+            'display dialog (item 1 of argv) with title "screenpipe" with icon caution '
+            'buttons {"Deny", "Allow for this session"} '
+            'default button "Deny" cancel button "Deny"\n'
+            'end run'
+        )
+        return ["osascript", "-e", script, message], {}
+    if IS_WINDOWS:
+        script = (
+            "Add-Type -AssemblyName PresentationFramework; "
+            "$answer = [System.Windows.MessageBox]::Show($env:SCREENPIPE_CONFIRM, 'screenpipe', 'YesNo', 'Warning'); "
+            "if ($answer -eq 'Yes') { exit 0 } else { exit 1 }"
+        )
+        return (
+            ["powershell", "-NoProfile", "-Command", script],
+            {"SCREENPIPE_CONFIRM": message + "\n\nYes allows it for the rest of this session."},
+        )
+    if IS_LINUX:
+        return [
+            "zenity", "--question", "--title=screenpipe", f"--text={message}",
+            "--ok-label=Allow for this session", "--cancel-label=Deny",
+        ], {}
+    return None
+
+async def ask_user(tool: str, arguments: dict) -> bool:
+    """Ask the user, outside the model's reach, whether the model may control
+    the computer for the rest of the session."""
+    details = json.dumps(arguments)
+    if len(details) > 500:
+        details = details[:500] + "…"
+    command = confirm_command(
+        f"An AI assistant wants to use '{tool}' to control your computer through screenpipe.\n\n{details}"
+    )
+    if command is None:
+        return False
+    argv, env = command
+
+    try:
+        process = await asyncio.create_subprocess_exec(
+            *argv,
+            env={**os.environ, **env},
+            stdout=asyncio.subprocess.DEVNULL,
+            stderr=asyncio.subprocess.DEVNULL,
+        )
+    except OSError:
+        # No dialog program, e.g. zenity is not installed
+        return False
+    try:
+        return await asyncio.wait_for(process.wait(), CONFIRM_TIMEOUT) == 0
+    except asyncio.TimeoutError:
+        process.kill()
+        await process.wait()
+        return False
+
+def operator_error(response: httpx.Response) -> str:
+    """The error body of an operator endpoint, as JSON with its error kind."""
+    try:
+        body = response.json()
+    except json.JSONDecodeError:
+        body = {}
+    return json.dumps({
+        "error": body.get("error", response.text or f"status {response.status_code}"),
+        "kind": body.get("kind", "internal"),
+    })
+
+def walk(element: dict):
+    """Yield a snapshot element and all of its descendants."""
+    yield element
+    for child in element.get("children", []):
+        yield from walk(child)
+
+def element_matches(element: dict, role: str | None, label: str | None) -> bool:
+    if role and element.get("role", "").lower() != role.lower():
+        return False
+    if label and label.lower() not in (element.get("label") or "").lower():
+        return False
+    return True
+
+def describe_element(element: dict) -> str:
+    """One line per element, the same layout as `ax-snapshot dump`."""
+    line = f"{element.get('id')} {element.get('role')}"
+    if element.get("label"):
+        line += f" {json.dumps(element['label'])}"
+    if element.get("redacted"):
+        line += " = <redacted>"
+    elif element.get("value") is not None:
+        line += f" = {json.dumps(element['value'])}"
+    bounds = element.get("bounds")
+    if bounds:
+        line += f" @ {bounds['x']:g},{bounds['y']:g} {bounds['width']:g}x{bounds['height']:g}"
+    return line
+
+def render_snapshot(element: dict, roles: list[str] | None, depth: int = 0) -> list[str]:
+    """Indented lines for the tree, keeping only elements with one of `roles`
+    and flattening the others away."""
+    keep = not roles or element.get("role") in roles
+    lines = [("  " * depth) + describe_element(element)] if keep else []
+    for child in element.get("children", []):
+        lines.extend(render_snapshot(child, roles, depth + 1 if keep else depth))
+    return lines
+
+async def get_snapshot(client: httpx.AsyncClient, arguments: dict) -> dict:
+    """A fresh snapshot of the app in `arguments`, raises httpx.HTTPStatusError
+    on failure."""
+    params = {
+        "app": arguments.get("app"),
+        "window": arguments.get("window"),
+        "max_depth": arguments.get("max_depth"),
+        "visible_only": arguments.get("visible_only"),
+    }
+    response = await client.get(
+        f"{SCREENPIPE_API}/experimental/operator/snapshot",
+        params={k: v for k, v in params.items() if v is not None},
+        timeout=30.0
+    )
+    response.raise_for_status()
+    return response.json()
+
 @server.list_tools()
 async def handle_list_tools() -> list[types.Tool]:
     """List available search tools for screenpipe."""
@@ -133,6 +272,85 @@ async def handle_list_tools() -> list[types.Tool]:
                 "required": ["action_type", "data"]
             },
         ),
+        types.Tool(
+            name="get-ui-snapshot",
+            description=(
+                "Capture the accessibility tree of an application: one line per element with its id, role, label, "
+                "value and bounds, children indented below their parent. "
+                "Use the ids with click-element and fill-element. "
+                "Roles are normalized: 'button', 'text_field', 'text_area', 'check_box', 'slider', 'menu', "
+                "'menu_item', 'window', 'web_area', 'static_text', 'group', 'table', 'row', 'cell', "
+                "other roles keep their platform name."
+            ),
+            inputSchema={
+                "type": "object",
+                "properties": {
+                    "app": {
+                        "type": "string",
+                        "description": "The name of the application (e.g., 'Chrome', 'Finder', 'Slack')"
+                    },
+                    "window": {
+                        "type": "string",
+                        "description": "Title of one window of the app (optional)"
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "description": "How many levels below the app or window to include",
+                        "default": 5
+                    },
+                    "roles": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Only list elements with these roles, e.g. ['button', 'text_field'] (optional)"
+                    },
+                    "visible_only": {
+                        "type": "boolean",
+                        "description": "Leave out elements outside of their window",
+                        "default": False
+                    }
+                },
+                "required": ["app"]
+            },
+        ),
+        types.Tool(
+            name="wait-for-element",
+            description=(
+                "Wait until an element with a role and/or label appears in an application, "
+                "e.g. a dialog after clicking a button. Returns the matching elements, or a timeout error."
+            ),
+            inputSchema={
+                "type": "object",
+                "properties": {
+                    "app": {
+                        "type": "string",
+                        "description": "The name of the application"
+                    },
+                    "window": {
+                        "type": "string",
+                        "description": "Title of one window of the app (optional)"
+                    },
+                    "role": {
+                        "type": "string",
+                        "description": "Normalized role of the element, e.g. 'button' or 'text_field'"
+                    },
+                    "label": {
+                        "type": "string",
+                        "description": "Case insensitive part of the element label"
+                    },
+                    "timeout_ms": {
+                        "type": "integer",
+                        "description": "How long to wait in milliseconds",
+                        "default": 10000
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "description": "How many levels below the app or window to search",
+                        "default": 5
+                    }
+                },
+                "required": ["app"]
+            },
+        ),
     ]
     
     # Add MacOS-specific tools only if running on MacOS
@@ -354,6 +572,75 @@ async def handle_call_tool(
             text=f"the '{name}' tool is only available on MacOS. current platform: {CURRENT_OS}"
         )]
 
+    global actions_allowed
+    if name in ACTION_TOOLS and not actions_allowed:
+        # One dialog at a time, calls made meanwhile use its answer
+        async with ask_lock:
+            if not actions_allowed:
+                actions_allowed = await ask_user(name, arguments)
+        if not actions_allowed:
+            return [types.TextContent(
+                type="text",
+                text=json.dumps({
+                    "error": f"the user did not allow '{name}' to control their computer",
+                    "kind": "permission_denied",
+                })
+            )]
+
+    if name == "get-ui-snapshot":
+        async with httpx.AsyncClient() as client:
+            try:
+                data = await get_snapshot(client, arguments)
+            except httpx.HTTPStatusError as e:
+                return [types.TextContent(type="text", text=operator_error(e.response))]
+            except Exception as e:
+                return [types.TextContent(
+                    type="text",
+                    text=json.dumps({"error": f"failed to get snapshot: {str(e)}", "kind": "internal"})
+                )]
+
+            lines = render_snapshot(data["element"], arguments.get("roles"))
+            if data.get("errors"):
+                lines.append(f"incomplete, children missing: {json.dumps(data['errors'])}")
+            return [types.TextContent(type="text", text="\n".join(lines))]
+
+    if name == "wait-for-element":
+        role = arguments.get("role")
+        label = arguments.get("label")
+        if not role and not label:
+            return [types.TextContent(
+                type="text",
+                text=json.dumps({"error": "give a role, a label or both", "kind": "invalid_argument"})
+            )]
+
+        deadline = asyncio.get_running_loop().time() + arguments.get("timeout_ms", 10000) / 1000
+        async with httpx.AsyncClient() as client:
+            while True:
+                try:
+                    data = await get_snapshot(client, arguments)
+                    found = [e for e in walk(data["element"]) if element_matches(e, role, label)]
+                    if found:
+                        return [types.TextContent(
+                            type="text",
+                            text="\n".join(describe_element(e) for e in found)
+                        )]
+                except httpx.HTTPStatusError as e:
+                    # The app may be starting or busy, anything else will not get better
+                    if e.response.status_code not in (404, 504):
+                        return [types.TextContent(type="text", text=operator_error(e.response))]
+                except Exception as e:
+                    return [types.TextContent(
+                        type="text",
+                        text=json.dumps({"error": f"failed to get snapshot: {str(e)}", "kind": "internal"})
+                    )]
+
+                if asyncio.get_running_loop().time() >= deadline:
+                    return [types.TextContent(
+                        type="text",
+                        text=json.dumps({"error": "element did not appear in time", "kind": "timeout"})
+                    )]
+                await asyncio.sleep(0.5)
+
     if name == "search-content":
         async with httpx.AsyncClient() as client:
             # Define the order of content types to try
@@ -514,6 +801,8 @@ async def handle_call_tool(
                     text=f"successfully clicked element using {method}. {details}"
                 )]
                 
+            except httpx.HTTPStatusError as e:
+                return [types.TextContent(type="text", text=operator_error(e.response))]
             except Exception as e:
                 return [types.TextContent(
                     type="text",
@@ -550,6 +839,8 @@ async def handle_call_tool(
                     text=f"successfully filled element with text"
                 )]
                 
+            except httpx.HTTPStatusError as e:
+                return [types.TextContent(type="text", text=operator_error(e.response))]
             except Exception as e:
                 return [types.TextContent(
                     type="text",
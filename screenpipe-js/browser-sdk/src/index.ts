@@ -789,4 +789,4 @@ export const pipe = pipeImpl;
 pipeImpl.pipes = pipeManager;
 
 export * from "../../common/types";
-export { getDefaultSettings } from "../../common/utils";
+export { getDefaultSettings, decompressSnapshot } from "../../common/utils";
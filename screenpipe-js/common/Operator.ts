@@ -3,9 +3,15 @@ import type {
   ElementSelector,
   ElementPosition,
   ElementSize,
+  ElementSnapshot,
+  DisplayInfo,
   ElementStats,
 } from "./types";
-import { convertObjectToCamelCase, convertObjectToSnakeCase } from "./utils";
+import {
+  convertObjectToCamelCase,
+  convertObjectToSnakeCase,
+  decompressSnapshot,
+} from "./utils";
 
 export interface ClickResult {
   method: "AXPress" | "AXClick" | "MouseSimulation"; // TODO: get rid of that hardcoded macos thing ...
@@ -873,6 +879,465 @@ export class Operator {
     const data = await response.json();
     return data.success;
   }
+
+  /**
+   * Hover over an element and return its snapshot after a dwell, which
+   * includes controls that only appear while the pointer is over the element
+   *
+   * @param options.durationMs - How long to dwell before the snapshot (default: 500, at most 10000)
+   * @param options.maxDepth - Levels below the element to include (default: 1)
+   * @returns Promise resolving to the element snapshot, shaped like fetchChildren's
+   *
+   * @example
+   * // Reveal the row actions of a Slack message
+   * const row = await pipe.operator.hover({
+   *   app: "Slack",
+   *   id: "1234",
+   *   durationMs: 500,
+   *   restorePointer: true
+   * });
+   */
+  async hover(options: {
+    app: string;
+    id: string;
+    window?: string;
+    useBackgroundApps?: boolean;
+    activateApp?: boolean;
+    durationMs?: number;
+    restorePointer?: boolean;
+    maxDepth?: number;
+  }): Promise<ElementSnapshot> {
+    const selector: ElementSelector = {
+      app_name: options.app,
+      window_name: options.window,
+      locator: `#${options.id}`,
+      use_background_apps: options.useBackgroundApps,
+      activate_app: options.activateApp !== false,
+    };
+
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/hover`,
+      {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          selector,
+          duration_ms: options.durationMs,
+          restore_pointer: options.restorePointer,
+          max_depth: options.maxDepth,
+        }),
+      }
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to hover element: ${errorData.error || response.statusText}`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to hover element (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    return data.element;
+  }
+
+  /**
+   * Fetches an element and its descendants up to the given depth, so large
+   * trees can be expanded on demand
+   *
+   * @param options.depth - Levels below the element to include (default: 1)
+   * @param options.visibleOnly - Leave out elements outside of their window (default: false)
+   * @param options.visibleMargin - Points around the window that still count as inside it (default: 50)
+   * @param options.maxValueLength - Cut longer values, use fetchAttributes for the full text (default: 2048)
+   * @param options.maxPayloadBytes - Stop adding elements past this serialized size
+   * @param options.compress - Gzip the snapshot on the server, it is decoded before returning (default: false)
+   * @param options.collapseGroups - Splice out unlabeled groups that only wrap a single child (default: false)
+   * @returns Promise resolving to the element snapshot
+   *
+   * @example
+   * const node = await pipe.operator.fetchChildren({
+   *   app: "Chrome",
+   *   id: "1234",
+   *   depth: 2
+   * });
+   */
+  async fetchChildren(options: {
+    app: string;
+    id: string;
+    window?: string;
+    useBackgroundApps?: boolean;
+    activateApp?: boolean;
+    depth?: number;
+    visibleOnly?: boolean;
+    visibleMargin?: number;
+    maxValueLength?: number;
+    maxPayloadBytes?: number;
+    compress?: boolean;
+    collapseGroups?: boolean;
+  }): Promise<ElementSnapshot> {
+    const selector: ElementSelector = {
+      app_name: options.app,
+      window_name: options.window,
+      locator: `#${options.id}`,
+      use_background_apps: options.useBackgroundApps,
+      activate_app: options.activateApp,
+    };
+
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/children`,
+      {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          selector,
+          depth: options.depth,
+          visible_only: options.visibleOnly,
+          visible_margin: options.visibleMargin,
+          max_value_length: options.maxValueLength,
+          max_payload_bytes: options.maxPayloadBytes,
+          compress: options.compress,
+          collapse_groups: options.collapseGroups,
+        }),
+      }
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to fetch children: ${errorData.error || response.statusText}`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to fetch children (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    if (data.compression) {
+      return decompressSnapshot(data.element);
+    }
+    return data.element;
+  }
+
+  /**
+   * Fetches attributes of a single element without walking its children
+   *
+   * @param options.names - Attributes to return (default: all)
+   * @returns Promise resolving to a map of attribute names to values
+   *
+   * @example
+   * const { value } = await pipe.operator.fetchAttributes({
+   *   app: "TextEdit",
+   *   id: "1234",
+   *   names: ["value"]
+   * });
+   */
+  async fetchAttributes(options: {
+    app: string;
+    id: string;
+    window?: string;
+    useBackgroundApps?: boolean;
+    activateApp?: boolean;
+    names?: string[];
+  }): Promise<Record<string, any>> {
+    const selector: ElementSelector = {
+      app_name: options.app,
+      window_name: options.window,
+      locator: `#${options.id}`,
+      use_background_apps: options.useBackgroundApps,
+      activate_app: options.activateApp,
+    };
+
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/attributes`,
+      {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          selector,
+          names: options.names,
+        }),
+      }
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to fetch attributes: ${
+            errorData.error || response.statusText
+          }`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to fetch attributes (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    return data.attributes;
+  }
+
+  /**
+   * Returns the app or window snapshot most recently taken through
+   * /experimental/operator/snapshot or a snapshot stream, without querying the
+   * accessibility APIs again. Subtrees from fetchChildren are not kept
+   *
+   * @returns Promise resolving to the snapshot, its app and its age in milliseconds,
+   * or null when no snapshot has been taken yet
+   *
+   * @example
+   * const latest = await pipe.operator.latestSnapshot();
+   * if (latest && latest.ageMs < 1000) {
+   *   render(latest.element);
+   * }
+   */
+  async latestSnapshot(): Promise<{
+    appName: string;
+    ageMs: number;
+    element: ElementSnapshot;
+  } | null> {
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/latest-snapshot`
+    );
+
+    if (response.status === 404) {
+      return null;
+    }
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to get latest snapshot: ${
+            errorData.error || response.statusText
+          }`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to get latest snapshot (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    return {
+      appName: data.app_name,
+      ageMs: data.age_ms,
+      element: data.element,
+    };
+  }
+
+  /**
+   * Returns how many snapshot captures are waiting or running for each app
+   *
+   * fetchChildren calls for the same app are queued, and identical calls
+   * share a single capture. A growing depth means snapshots are requested
+   * faster than the app can answer.
+   *
+   * @returns Promise resolving to a map from app name to queue depth
+   *
+   * @example
+   * const depth = await pipe.operator.snapshotQueue();
+   * if ((depth["Safari"] ?? 0) > 2) {
+   *   pollInterval *= 2;
+   * }
+   */
+  async snapshotQueue(): Promise<Record<string, number>> {
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/snapshot-queue`
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to get snapshot queue: ${
+            errorData.error || response.statusText
+          }`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to get snapshot queue (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    return data.depth;
+  }
+
+  /**
+   * Lists the connected displays
+   *
+   * Element bounds use the same coordinates: points from the top-left corner
+   * of the primary display.
+   *
+   * @returns Promise resolving to the connected displays
+   *
+   * @example
+   * const displays = await pipe.operator.displays();
+   * const primary = displays.find((display) => display.primary);
+   */
+  async displays(): Promise<DisplayInfo[]> {
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/displays`
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to get displays: ${errorData.error || response.statusText}`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to get displays (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    return data.displays;
+  }
+
+  /**
+   * Converts a point between global coordinates and coordinates relative to
+   * a display
+   *
+   * @param options.x - Horizontal position in points
+   * @param options.y - Vertical position in points
+   * @param options.fromDisplay - Display the point is relative to, global when omitted
+   * @param options.toDisplay - Display to make the point relative to, global when omitted
+   * @param options.fromPhysical - The point is in pixels of fromDisplay instead of points
+   * @param options.toPhysical - Return pixels of toDisplay instead of points
+   * @returns Promise resolving to the converted point
+   *
+   * @example
+   * // Place a window sized in pixels over an element on a Retina display
+   * const { x, y } = await pipe.operator.convertPoint({
+   *   x: element.bounds.x,
+   *   y: element.bounds.y,
+   *   toDisplay: element.display,
+   *   toPhysical: true,
+   * });
+   */
+  async convertPoint(options: {
+    x: number;
+    y: number;
+    fromDisplay?: number;
+    toDisplay?: number;
+    fromPhysical?: boolean;
+    toPhysical?: boolean;
+  }): Promise<{ x: number; y: number }> {
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/convert-point`,
+      {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify(convertObjectToSnakeCase(options)),
+      }
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to convert point: ${errorData.error || response.statusText}`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to convert point (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    return await response.json();
+  }
+
+  /**
+   * Clears cached application handles, the interactable elements cache and
+   * the latest snapshot
+   *
+   * @returns Promise resolving to true once the caches are cleared
+   *
+   * @example
+   * await pipe.operator.clearCache();
+   */
+  async clearCache(): Promise<boolean> {
+    const response = await fetch(
+      `${this.baseUrl}/experimental/operator/clear-cache`,
+      {
+        method: "POST",
+      }
+    );
+
+    if (!response.ok) {
+      const responseText = await response.text();
+      console.log("error response:", responseText);
+
+      try {
+        const errorData = JSON.parse(responseText);
+        throw new Error(
+          `failed to clear cache: ${errorData.error || response.statusText}`
+        );
+      } catch (parseError) {
+        throw new Error(
+          `failed to clear cache (status ${response.status}): ${
+            responseText || response.statusText
+          }`
+        );
+      }
+    }
+
+    const data = await response.json();
+    return data.success;
+  }
 }
 
 class ElementLocator {
@@ -369,6 +369,46 @@ export interface ElementInfo {
   properties: Record<string, any>;
 }
 
+export interface ElementBounds {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/**
+ * A connected display. Bounds are in points from the top-left corner of the
+ * primary display, so displays left of or above it have negative coordinates.
+ */
+export interface DisplayInfo {
+  id: number;
+  bounds: ElementBounds;
+  primary: boolean;
+  /** Pixels per point, 2 on Retina displays */
+  scale_factor: number;
+}
+
+export interface ElementSnapshot {
+  id: string;
+  /** Role shared by every platform in snake_case, e.g. "button" or "text_field". Roles without one keep their platform name */
+  role: string;
+  /** Role as named by the platform, e.g. "AXButton" */
+  native_role: string;
+  label?: string;
+  value?: string;
+  value_truncated?: boolean;
+  original_value_length?: number;
+  description?: string;
+  /** The element holds secret text, such as a password, so value is never set */
+  redacted?: boolean;
+  bounds?: ElementBounds;
+  /** ID of the display showing most of the element */
+  display?: number;
+  children?: ElementSnapshot[];
+  unresponsive?: boolean;
+  collapsed?: number;
+}
+
 export interface ElementStats {
   total: number;
   definitely_interactable: number;
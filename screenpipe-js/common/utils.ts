@@ -1,4 +1,4 @@
-import type { Settings } from "./types";
+import type { ElementSnapshot, Settings } from "./types";
 
 // Helper functions to flatten/unflatten objects
 const flattenObject = (obj: any, prefix = ""): Record<string, any> => {
@@ -108,6 +108,15 @@ function getDefaultSettings(): Settings {
   };
 }
 
+// Decodes an element returned with `compress: true` (base64 encoded gzip of its JSON)
+async function decompressSnapshot(data: string): Promise<ElementSnapshot> {
+  const bytes = Uint8Array.from(atob(data), (c) => c.charCodeAt(0));
+  const stream = new Blob([bytes])
+    .stream()
+    .pipeThrough(new DecompressionStream("gzip"));
+  return JSON.parse(await new Response(stream).text());
+}
+
 export {
   flattenObject,
   unflattenObject,
@@ -116,4 +125,5 @@ export {
   convertObjectToSnakeCase,
   convertObjectToCamelCase,
   getDefaultSettings,
+  decompressSnapshot,
 };
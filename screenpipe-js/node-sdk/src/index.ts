@@ -239,6 +239,6 @@ class NodePipe {
 const pipe = new NodePipe();
 
 export * from "../../common/types";
-export { getDefaultSettings } from "../../common/utils";
+export { getDefaultSettings, decompressSnapshot } from "../../common/utils";
 
 export { pipe };
@@ -22,6 +22,7 @@ use screenpipe_server::{
     },
     handle_index_command,
     pipe_manager::PipeInfo,
+    snapshot_recorder::{SnapshotRecorder, SnapshotRecorderConfig},
     start_continuous_recording, watch_pid, PipeManager, ResourceMonitor, SCServer,
 };
 use screenpipe_vision::monitor::list_monitors;
@@ -743,6 +744,31 @@ async fn main() -> anyhow::Result<()> {
     #[cfg(feature = "llm")]
     debug!("LLM initialized");
 
+    screenpipe_core::set_redacted_elements(
+        cli.operator_redacted_role.clone(),
+        cli.operator_redacted_id.clone(),
+    );
+
+    #[cfg(feature = "operator-mock")]
+    if let Some(dir) = &cli.operator_mock_fixtures {
+        let engine = screenpipe_core::operator::MockEngine::from_dir(dir)?;
+        screenpipe_core::operator::use_mock_engine(engine);
+        info!("serving accessibility trees from fixtures in {}", dir.display());
+    }
+
+    let snapshot_recorder = cli.enable_operator_snapshot_recording.then(|| {
+        let recorder = Arc::new(SnapshotRecorder::new(
+            db_server.clone(),
+            SnapshotRecorderConfig {
+                retention: Duration::from_secs(cli.operator_snapshot_retention_days * 24 * 60 * 60),
+                max_total_bytes: cli.operator_snapshot_max_mb * 1024 * 1024,
+                ..SnapshotRecorderConfig::default()
+            },
+        ));
+        recorder.start_pruning();
+        recorder
+    });
+
     let server = SCServer::new(
         db_server,
         SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), cli.port),
@@ -751,6 +777,10 @@ async fn main() -> anyhow::Result<()> {
         cli.disable_vision,
         cli.disable_audio,
         cli.enable_ui_monitoring,
+        cli.operator_snapshot_concurrency,
+        Duration::from_secs(cli.operator_snapshot_timeout),
+        snapshot_recorder,
+        cli.enable_operator_applescript,
         audio_manager.clone(),
     );
 
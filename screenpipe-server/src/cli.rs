@@ -272,6 +272,46 @@ pub struct Cli {
     /// Enable UI monitoring (macOS only)
     #[arg(long, default_value_t = false)]
     pub enable_ui_monitoring: bool,
+
+    /// Maximum number of accessibility snapshots captured at once for the same app
+    #[arg(long, default_value_t = 1)]
+    pub operator_snapshot_concurrency: usize,
+
+    /// Seconds an accessibility snapshot may take, including time spent queued, before the request fails
+    #[arg(long, default_value_t = 30)]
+    pub operator_snapshot_timeout: u64,
+
+    /// Accessibility roles whose text is never read, on top of password fields (can be specified multiple times)
+    /// --operator-redacted-role "AXTextField" hides the value of every plain text field
+    #[arg(long)]
+    pub operator_redacted_role: Vec<String>,
+
+    /// Accessibility identifiers (AXIdentifier / AutomationId) whose text is never read (can be specified multiple times)
+    #[arg(long)]
+    pub operator_redacted_id: Vec<String>,
+
+    /// Serve /experimental/operator/applescript, which runs any AppleScript it is sent. Only
+    /// callers on this machine, with no browser origin other than the app's, may use it
+    #[arg(long, default_value_t = false)]
+    pub enable_operator_applescript: bool,
+
+    /// Store the app and window accessibility snapshots captured through the API in the database
+    #[arg(long, default_value_t = false)]
+    pub enable_operator_snapshot_recording: bool,
+
+    /// Days to keep recorded accessibility snapshots
+    #[arg(long, default_value_t = 7)]
+    pub operator_snapshot_retention_days: u64,
+
+    /// Megabytes recorded accessibility snapshots may take before the oldest are deleted
+    #[arg(long, default_value_t = 1024)]
+    pub operator_snapshot_max_mb: u64,
+
+    /// Serve accessibility trees from the snapshot fixtures in this directory instead of the
+    /// screen. Actions are only logged, see /experimental/operator/mock/actions
+    #[cfg(feature = "operator-mock")]
+    #[arg(long)]
+    pub operator_mock_fixtures: Option<PathBuf>,
     
     /// Enable experimental video frame cache (may increase CPU usage) - makes timeline UI available, frame streaming, etc.
     #[arg(long, default_value_t = true)]
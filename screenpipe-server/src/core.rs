@@ -1,3 +1,4 @@
+use crate::snapshot_recorder::wall_clock_time;
 use crate::VideoCapture;
 use anyhow::Result;
 use futures::future::join_all;
@@ -240,7 +241,7 @@ async fn record_video(
                 let result = db
                     .insert_frame(
                         &device_name,
-                        None,
+                        Some(wall_clock_time(frame.timestamp)),
                         window_result.browser_url.as_deref(),
                         Some(window_result.app_name.as_str()),
                         Some(window_result.window_name.as_str()),
@@ -7,6 +7,9 @@ pub mod filtering;
 pub mod pipe_manager;
 mod resource_monitor;
 mod server;
+pub mod snapshot_queue;
+pub mod snapshot_recorder;
+pub mod snapshot_stream;
 pub mod text_embeds;
 mod video;
 pub mod video_cache;
@@ -2,21 +2,29 @@ use axum::{
     body::Body,
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Json, Path, Query, State,
+        ConnectInfo, Json, Path, Query, State,
     },
-    http::StatusCode,
-    response::{IntoResponse, Json as JsonResponse, Response},
-    routing::get,
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Json as JsonResponse, Response,
+    },
+    routing::{get, post},
     serve, Router,
 };
 use oasgen::{oasgen, OaSchema, Server};
 
-use screenpipe_core::Desktop;
+use screenpipe_core::{
+    correlate_ocr, export_tree, replay_script, run_apple_script, snapshot_events, snapshot_update,
+    AppleScriptSource, AutomationError, AutomationScript, Bounds, CaptureStats, CoordinateSpace,
+    Desktop, DisplayLayout, ElementSnapshot, InternedSnapshot, OcrBlock, ReplayOptions, Role,
+    ScriptAction, ScriptRecorder, SnapshotOptions, SnapshotUpdate, TreeFormat,
+};
 
 use chrono::TimeZone;
 use screenpipe_db::{
     ContentType, DatabaseManager, FrameData, Order, SearchMatch, SearchResult, Speaker,
-    TagContentType,
+    TagContentType, UiSnapshotMeta,
 };
 
 use tokio_util::io::ReaderStream;
@@ -32,6 +40,9 @@ use screenpipe_events::{send_event, subscribe_to_all_events, Event as Screenpipe
 
 use crate::{
     embedding::embedding_endpoint::create_embeddings,
+    snapshot_queue::{SnapshotQueue, SnapshotTaskError},
+    snapshot_recorder::{wall_clock_time, SnapshotRecorder},
+    snapshot_stream::SnapshotStreams,
     video::{finish_ffmpeg_process, start_ffmpeg_process, write_frame_to_ffmpeg, MAX_FPS},
     video_cache::{AudioEntry, DeviceFrame, FrameCache, FrameMetadata, TimeSeriesFrame},
     video_utils::{
@@ -47,7 +58,7 @@ use screenpipe_audio::{
         default_input_device, default_output_device, list_audio_devices, AudioDevice, DeviceType,
     },
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use screenpipe_vision::monitor::{get_monitor_by_id, list_monitors};
 use screenpipe_vision::OcrEngine;
@@ -61,6 +72,10 @@ use std::{
     time::{Duration, Instant},
 };
 
+use base64::{engine::general_purpose, Engine as _};
+use flate2::{write::GzEncoder, Compression};
+use std::io::Write;
+
 use lru::LruCache;
 
 use tokio::{
@@ -83,6 +98,10 @@ use uuid::Uuid; // or sentry::protocol::Uuid depending on which you want to use
 
 pub type FrameImageCache = LruCache<i64, (String, Instant)>;
 
+/// App snapshots by the query that captured them, with when they were taken
+/// and the capture errors
+pub type AppSnapshotCache = LruCache<String, (Arc<ElementSnapshot>, Instant, Value)>;
+
 pub struct AppState {
     pub db: Arc<DatabaseManager>,
     pub audio_manager: Arc<AudioManager>,
@@ -95,7 +114,26 @@ pub struct AppState {
     pub frame_cache: Option<Arc<FrameCache>>,
     pub frame_image_cache: Option<Arc<Mutex<FrameImageCache>>>,
     pub element_cache: Arc<Mutex<Option<(Vec<UIElement>, Instant, String)>>>,
-}
+    /// Most recent app or window snapshot, with when it was taken and for
+    /// which app. Subtrees from `/children` are never kept here
+    pub snapshot_cache: Arc<Mutex<Option<(Arc<ElementSnapshot>, Instant, String)>>>,
+    /// Snapshots served to `max_age_ms` requests of `/experimental/operator/snapshot`
+    pub app_snapshot_cache: Arc<Mutex<AppSnapshotCache>>,
+    pub snapshot_queue: Arc<SnapshotQueue<CapturedSnapshot>>,
+    pub snapshot_streams: Arc<SnapshotStreams<StreamFrame>>,
+    /// Stores captured snapshots in the database, when enabled
+    pub snapshot_recorder: Option<Arc<SnapshotRecorder>>,
+    /// Clicks and typing done through the API, while a recording is running
+    pub script_recorder: Arc<ScriptRecorder>,
+}
+
+/// Result of a queued snapshot capture, with when the capture started. Errors
+/// carry the status and body to respond with
+pub type CapturedSnapshot =
+    Result<(Arc<ElementSnapshot>, CaptureStats, Instant), (StatusCode, Value)>;
+
+/// Latest capture of a snapshot stream session, errors carry the body to send
+pub type StreamFrame = Result<Arc<ElementSnapshot>, Value>;
 
 // Update the SearchQuery struct
 #[derive(OaSchema, Deserialize)]
@@ -292,6 +330,9 @@ pub struct HealthCheckResponse {
     pub message: String,
     pub verbose_instructions: Option<String>,
     pub device_status_details: Option<String>,
+    /// State of the ui_monitor helper process, macOS with UI monitoring only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ui_monitor: Option<Value>,
 }
 
 #[derive(OaSchema, Serialize, Deserialize)]
@@ -748,6 +789,13 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         )
     };
 
+    #[cfg(target_os = "macos")]
+    let ui_monitor = state
+        .ui_monitoring_enabled
+        .then(|| json!(screenpipe_vision::ui_monitor_status()));
+    #[cfg(not(target_os = "macos"))]
+    let ui_monitor = None;
+
     JsonResponse(HealthCheckResponse {
         status: overall_status.to_string(),
         status_code,
@@ -767,6 +815,7 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         message,
         verbose_instructions,
         device_status_details,
+        ui_monitor,
     })
 }
 
@@ -1042,6 +1091,10 @@ pub struct SCServer {
     vision_disabled: bool,
     audio_disabled: bool,
     ui_monitoring_enabled: bool,
+    snapshot_concurrency: usize,
+    snapshot_timeout: Duration,
+    snapshot_recorder: Option<Arc<SnapshotRecorder>>,
+    apple_script_enabled: bool,
 }
 
 impl SCServer {
@@ -1054,6 +1107,10 @@ impl SCServer {
         vision_disabled: bool,
         audio_disabled: bool,
         ui_monitoring_enabled: bool,
+        snapshot_concurrency: usize,
+        snapshot_timeout: Duration,
+        snapshot_recorder: Option<Arc<SnapshotRecorder>>,
+        apple_script_enabled: bool,
         audio_manager: Arc<AudioManager>,
     ) -> Self {
         SCServer {
@@ -1064,6 +1121,10 @@ impl SCServer {
             vision_disabled,
             audio_disabled,
             ui_monitoring_enabled,
+            snapshot_concurrency,
+            snapshot_timeout,
+            snapshot_recorder,
+            apple_script_enabled,
             audio_manager,
         }
     }
@@ -1114,6 +1175,14 @@ impl SCServer {
                 None
             },
             element_cache: Arc::new(Mutex::new(None)),
+            snapshot_cache: Arc::new(Mutex::new(None)),
+            app_snapshot_cache: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(32).unwrap()))),
+            snapshot_queue: Arc::new(
+                SnapshotQueue::new(self.snapshot_concurrency).with_timeout(self.snapshot_timeout),
+            ),
+            snapshot_streams: Arc::new(SnapshotStreams::new()),
+            snapshot_recorder: self.snapshot_recorder.clone(),
+            script_recorder: Arc::new(ScriptRecorder::new()),
         });
 
         let cors = CorsLayer::new()
@@ -1181,6 +1250,53 @@ impl SCServer {
             )
             .post("/experimental/operator/open-url", open_url_handler)
             .post("/experimental/operator/pixel", input_control_handler)
+            .post("/experimental/operator/hover", hover_element_handler)
+            .post("/experimental/operator/clear-cache", clear_cache_handler)
+            .post("/experimental/operator/children", fetch_children_handler)
+            .post(
+                "/experimental/operator/attributes",
+                fetch_attributes_handler,
+            )
+            .get(
+                "/experimental/operator/latest-snapshot",
+                latest_snapshot_handler,
+            )
+            .get("/experimental/operator/snapshot", app_snapshot_handler)
+            .get("/experimental/operator/export-tree", export_tree_handler)
+            .get(
+                "/experimental/operator/snapshots",
+                recorded_snapshots_handler,
+            )
+            .get(
+                "/experimental/operator/snapshots/:id",
+                recorded_snapshot_handler,
+            )
+            .get(
+                "/experimental/operator/frames/:frame_id/snapshot",
+                frame_snapshot_handler,
+            )
+            .get(
+                "/experimental/operator/snapshot-queue",
+                snapshot_queue_handler,
+            )
+            .get("/experimental/operator/displays", displays_handler)
+            .post(
+                "/experimental/operator/convert-point",
+                convert_point_handler,
+            )
+            .post(
+                "/experimental/operator/correlate-ocr",
+                correlate_ocr_handler,
+            )
+            .post(
+                "/experimental/operator/recording/start",
+                start_recording_handler,
+            )
+            .post(
+                "/experimental/operator/recording/stop",
+                stop_recording_handler,
+            )
+            .post("/experimental/operator/replay", replay_script_handler)
             .post("/audio/start", start_audio)
             .post("/audio/stop", stop_audio)
             .get("/semantic-search", semantic_search_handler)
@@ -1194,13 +1310,32 @@ impl SCServer {
             .freeze();
 
         // Build the main router with all routes
-        Router::new()
+        let mut router = Router::new()
             .merge(server.into_router())
             // NOTE: websockerts and sse is not supported by openapi so we move it down here
             .route("/stream/frames", get(stream_frames_handler))
             .route("/ws/events", get(ws_events_handler))
             .route("/ws/health", get(ws_health_handler))
-            .route("/frames/export", get(handle_video_export_ws))
+            .route("/ws/operator/snapshot", get(snapshot_stream_handler))
+            .route("/sse/operator/events", get(snapshot_events_handler))
+            .route("/frames/export", get(handle_video_export_ws));
+        // Runs arbitrary scripts, so only served when asked for and kept out of
+        // the OpenAPI spec
+        if self.apple_script_enabled {
+            router = router.route(
+                "/experimental/operator/applescript",
+                post(apple_script_handler),
+            );
+        }
+        // Only there to check what was done to the fixtures, see
+        // --operator-mock-fixtures
+        if screenpipe_core::operator::mock_engine().is_some() {
+            router = router.route(
+                "/experimental/operator/mock/actions",
+                get(mock_actions_handler),
+            );
+        }
+        router
             .with_state(app_state)
             .layer(cors)
             .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::default()))
@@ -3171,6 +3306,87 @@ pub struct ActionResponse {
     message: String,
 }
 
+// Platforms without an accessibility backend answer 501, so clients can tell
+// "not available here" apart from a failure
+fn automation_status(e: &AutomationError) -> StatusCode {
+    match e {
+        AutomationError::UnsupportedPlatform(_) => StatusCode::NOT_IMPLEMENTED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// A malformed ID is a bug in the caller, a missing element usually means the
+// UI changed. When the ID was in the last snapshot, its age tells the caller
+// whether taking a fresh one is worth a retry
+async fn element_lookup_error(
+    state: &AppState,
+    locator: &str,
+    e: AutomationError,
+) -> (StatusCode, JsonResponse<Value>) {
+    let status = match e {
+        AutomationError::InvalidArgument(_) => StatusCode::BAD_REQUEST,
+        AutomationError::ElementNotFound(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let last_seen_ms_ago = match locator.strip_prefix('#') {
+        Some(id) => state
+            .snapshot_cache
+            .lock()
+            .await
+            .as_ref()
+            .filter(|(snapshot, _, _)| snapshot.find(id).is_some())
+            .map(|(_, taken_at, _)| taken_at.elapsed().as_millis() as u64),
+        None => None,
+    };
+    (
+        status,
+        JsonResponse(json!({
+            "error": format!("Failed to find element: {}", e),
+            "kind": e.kind(),
+            "last_seen_ms_ago": last_seen_ms_ago
+        })),
+    )
+}
+
+// The capture behind the newest cached snapshot of `app_name` holding the
+// element a `#id` locator names. Suffixed IDs of identical siblings and
+// position IDs only mean something to the walk that gave them out
+async fn snapshot_source(
+    state: &AppState,
+    app_name: &str,
+    locator: &str,
+) -> Option<AppSnapshotQuery> {
+    let id = locator.strip_prefix('#')?;
+    let cache = state.app_snapshot_cache.lock().await;
+    cache.iter().find_map(|(key, (snapshot, _, _))| {
+        let query: AppSnapshotQuery = serde_json::from_str(key).ok()?;
+        (query.app.eq_ignore_ascii_case(app_name) && snapshot.find(id).is_some()).then_some(query)
+    })
+}
+
+// First element `locator` finds in `app`, walking the tree again the way
+// `source` did for an ID from its snapshot
+fn locate(
+    app: &UIElement,
+    locator: &str,
+    source: Option<&AppSnapshotQuery>,
+) -> Result<Option<UIElement>, AutomationError> {
+    match (locator.strip_prefix('#'), source) {
+        (Some(id), Some(query)) => {
+            let root = capture_root(app, query)?;
+            root.and_then(|root| ElementSnapshot::find_live(&root, &snapshot_options(query), id))
+                .map(Some)
+                .ok_or_else(|| {
+                    AutomationError::ElementNotFound(format!(
+                        "Element with ID '{}' is no longer where the snapshot found it",
+                        id
+                    ))
+                })
+        }
+        _ => app.locator(locator)?.first(),
+    }
+}
+
 // Handler functions for UI automation
 #[oasgen]
 async fn find_elements_handler(
@@ -3184,9 +3400,10 @@ async fn find_elements_handler(
         Ok(d) => d,
         Err(e) => {
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("Failed to initialize desktop automation: {}", e)
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3199,7 +3416,8 @@ async fn find_elements_handler(
             return Err((
                 StatusCode::NOT_FOUND,
                 JsonResponse(json!({
-                    "error": format!("Application not found: {}", e)
+                    "error": format!("Application not found: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3249,7 +3467,10 @@ async fn find_elements_handler(
             error!("Failed to create locator: {}", e);
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({ "error": format!("Failed to create locator: {}", e) })),
+                JsonResponse(json!({
+                    "error": format!("Failed to create locator: {}", e),
+                    "kind": e.kind()
+                })),
             ));
         }
     };
@@ -3293,7 +3514,7 @@ async fn find_elements_handler(
 
 #[oasgen]
 async fn click_element_handler(
-    State(_): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<ClickElementRequest>,
 ) -> Result<JsonResponse<ActionResponse>, (StatusCode, JsonResponse<Value>)> {
     let desktop = match Desktop::new(
@@ -3304,9 +3525,10 @@ async fn click_element_handler(
         Err(e) => {
             error!("Failed to initialize desktop automation: {}", e);
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("Failed to initialize desktop automation: {}", e)
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3319,7 +3541,8 @@ async fn click_element_handler(
             return Err((
                 StatusCode::NOT_FOUND,
                 JsonResponse(json!({
-                    "error": format!("Application not found: {}", e)
+                    "error": format!("Application not found: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3328,27 +3551,17 @@ async fn click_element_handler(
     debug!("app: {:?}", app.text(1).unwrap_or_default());
 
     // Find elements matching the selector
-    let element = match app.locator(request.selector.locator.as_str()) {
-        Ok(locator) => match locator.first() {
-            Ok(element) => element,
-            Err(e) => {
-                error!("Failed to find elements: {}", e);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    JsonResponse(json!({
-                        "error": format!("Failed to find elements: {}", e)
-                    })),
-                ));
-            }
-        },
+    let source = snapshot_source(
+        &state,
+        &request.selector.app_name,
+        &request.selector.locator,
+    )
+    .await;
+    let element = match locate(&app, &request.selector.locator, source.as_ref()) {
+        Ok(element) => element,
         Err(e) => {
-            error!("Failed to create locator: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({
-                    "error": format!("Failed to create locator: {}", e)
-                })),
-            ));
+            error!("Failed to find elements: {}", e);
+            return Err(element_lookup_error(&state, &request.selector.locator, e).await);
         }
     };
 
@@ -3356,16 +3569,26 @@ async fn click_element_handler(
 
     match element {
         Some(element) => match element.click() {
-            Ok(_) => Ok(JsonResponse(ActionResponse {
-                success: true,
-                message: format!("Clicked element with role: {}", element.role()),
-            })),
+            Ok(_) => {
+                record_action(
+                    &state,
+                    &request.selector.app_name,
+                    &app,
+                    &element,
+                    ScriptAction::Click,
+                );
+                Ok(JsonResponse(ActionResponse {
+                    success: true,
+                    message: format!("Clicked element with role: {}", element.role()),
+                }))
+            }
             Err(e) => {
                 error!("Failed to click element: {}", e);
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
                     JsonResponse(json!({
-                        "error": format!("Failed to click element: {}", e)
+                        "error": format!("Failed to click element: {}", e),
+                        "kind": e.kind()
                     })),
                 ))
             }
@@ -3381,7 +3604,7 @@ async fn click_element_handler(
 
 #[oasgen]
 async fn type_text_handler(
-    State(_): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<TypeTextRequest>,
 ) -> Result<JsonResponse<ActionResponse>, (StatusCode, JsonResponse<Value>)> {
     let desktop = match Desktop::new(
@@ -3391,9 +3614,10 @@ async fn type_text_handler(
         Ok(d) => d,
         Err(e) => {
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("Failed to initialize desktop automation: {}", e)
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3406,7 +3630,8 @@ async fn type_text_handler(
             return Err((
                 StatusCode::NOT_FOUND,
                 JsonResponse(json!({
-                    "error": format!("Application not found: {}", e)
+                    "error": format!("Application not found: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3414,27 +3639,17 @@ async fn type_text_handler(
 
     debug!("app: {:?}", app);
     // Find elements matching the selector
-    let element = match app.locator(request.selector.locator.as_str()) {
-        Ok(locator) => match locator.first() {
-            Ok(element) => element,
-            Err(e) => {
-                error!("Failed to find elements: {}", e);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    JsonResponse(json!({
-                        "error": format!("Failed to find elements: {}", e)
-                    })),
-                ));
-            }
-        },
+    let source = snapshot_source(
+        &state,
+        &request.selector.app_name,
+        &request.selector.locator,
+    )
+    .await;
+    let element = match locate(&app, &request.selector.locator, source.as_ref()) {
+        Ok(element) => element,
         Err(e) => {
-            error!("Failed to create locator: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({
-                    "error": format!("Failed to create locator: {}", e)
-                })),
-            ));
+            error!("Failed to find elements: {}", e);
+            return Err(element_lookup_error(&state, &request.selector.locator, e).await);
         }
     };
 
@@ -3442,16 +3657,28 @@ async fn type_text_handler(
 
     match element {
         Some(element) => match element.type_text(&request.text) {
-            Ok(_) => Ok(JsonResponse(ActionResponse {
-                success: true,
-                message: format!("Typed text into element with role: {}", element.role()),
-            })),
+            Ok(_) => {
+                record_action(
+                    &state,
+                    &request.selector.app_name,
+                    &app,
+                    &element,
+                    ScriptAction::TypeText {
+                        text: request.text.clone(),
+                    },
+                );
+                Ok(JsonResponse(ActionResponse {
+                    success: true,
+                    message: format!("Typed text into element with role: {}", element.role()),
+                }))
+            }
             Err(e) => {
                 error!("Failed to type text: {}", e);
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
                     JsonResponse(json!({
-                        "error": format!("Failed to type text: {}", e)
+                        "error": format!("Failed to type text: {}", e),
+                        "kind": e.kind()
                     })),
                 ))
             }
@@ -3503,9 +3730,10 @@ async fn get_text_handler(
         Ok(d) => d,
         Err(e) => {
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("Failed to initialize desktop automation: {}", e)
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3518,7 +3746,8 @@ async fn get_text_handler(
             return Err((
                 StatusCode::NOT_FOUND,
                 JsonResponse(json!({
-                    "error": format!("Application not found: {}", e)
+                    "error": format!("Application not found: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3532,7 +3761,8 @@ async fn get_text_handler(
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({
-                    "error": format!("Failed to extract text: {}", e)
+                    "error": format!("Failed to extract text: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3645,9 +3875,10 @@ async fn list_interactable_elements_handler(
         Ok(d) => d,
         Err(e) => {
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("Failed to initialize desktop automation: {}", e)
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3661,7 +3892,8 @@ async fn list_interactable_elements_handler(
             return Err((
                 StatusCode::NOT_FOUND,
                 JsonResponse(json!({
-                    "error": format!("Application not found: {}", e)
+                    "error": format!("Application not found: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3674,7 +3906,8 @@ async fn list_interactable_elements_handler(
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({
-                    "error": format!("Failed to get elements: {}", e)
+                    "error": format!("Failed to get elements: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3686,7 +3919,8 @@ async fn list_interactable_elements_handler(
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({
-                    "error": format!("Failed to get elements: {}", e)
+                    "error": format!("Failed to get elements: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -3812,25 +4046,29 @@ async fn click_by_index_handler(
 
     // Then proceed with the rest of the logic...
     match elements_opt {
-        Some((elements, timestamp, _app_name)) if timestamp.elapsed() < Duration::from_secs(30) => {
+        Some((elements, timestamp, app_name)) if timestamp.elapsed() < Duration::from_secs(30) => {
             // Use element_index directly
             if request.element_index < elements.len() {
                 let element = &elements[request.element_index];
 
                 match element.click() {
-                    Ok(_) => Ok(JsonResponse(ClickByIndexResponse {
-                        success: true,
-                        message: format!(
-                            "Successfully clicked element with role: {}",
-                            element.role()
-                        ),
-                    })),
+                    Ok(_) => {
+                        record_indexed_action(&state, &app_name, element, ScriptAction::Click);
+                        Ok(JsonResponse(ClickByIndexResponse {
+                            success: true,
+                            message: format!(
+                                "Successfully clicked element with role: {}",
+                                element.role()
+                            ),
+                        }))
+                    }
                     Err(e) => {
                         error!("failed to click element: {}", e);
                         Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             JsonResponse(json!({
-                                "error": format!("failed to click element: {}", e)
+                                "error": format!("failed to click element: {}", e),
+                                "kind": e.kind()
                             })),
                         ))
                     }
@@ -3910,25 +4148,36 @@ async fn type_by_index_handler(
 
     // Then proceed with the logic...
     match elements_opt {
-        Some((elements, timestamp, _app_name)) if timestamp.elapsed() < Duration::from_secs(30) => {
+        Some((elements, timestamp, app_name)) if timestamp.elapsed() < Duration::from_secs(30) => {
             // Use element_index directly
             if request.element_index < elements.len() {
                 let element = &elements[request.element_index];
 
                 match element.type_text(&request.text) {
-                    Ok(_) => Ok(JsonResponse(TypeByIndexResponse {
-                        success: true,
-                        message: format!(
-                            "successfully typed text into element with role: {}",
-                            element.role()
-                        ),
-                    })),
+                    Ok(_) => {
+                        record_indexed_action(
+                            &state,
+                            &app_name,
+                            element,
+                            ScriptAction::TypeText {
+                                text: request.text.clone(),
+                            },
+                        );
+                        Ok(JsonResponse(TypeByIndexResponse {
+                            success: true,
+                            message: format!(
+                                "successfully typed text into element with role: {}",
+                                element.role()
+                            ),
+                        }))
+                    }
                     Err(e) => {
                         error!("failed to type text into element: {}", e);
                         Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             JsonResponse(json!({
-                                "error": format!("failed to type text into element: {}", e)
+                                "error": format!("failed to type text into element: {}", e),
+                                "kind": e.kind()
                             })),
                         ))
                     }
@@ -3985,7 +4234,7 @@ pub struct PressKeyResponse {
 // Add this new handler function
 #[oasgen]
 async fn press_key_handler(
-    State(_): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<PressKeyRequest>,
 ) -> Result<JsonResponse<PressKeyResponse>, (StatusCode, JsonResponse<Value>)> {
     debug!(target: "operator", "pressing key combination: {}", request.key_combo);
@@ -3998,9 +4247,10 @@ async fn press_key_handler(
         Err(e) => {
             error!("failed to initialize desktop automation: {}", e);
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("failed to initialize desktop automation: {}", e)
+                    "error": format!("failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -4013,7 +4263,8 @@ async fn press_key_handler(
             return Err((
                 StatusCode::NOT_FOUND,
                 JsonResponse(json!({
-                    "error": format!("application not found: {}", e)
+                    "error": format!("application not found: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -4022,27 +4273,17 @@ async fn press_key_handler(
     debug!(target: "operator", "app: {:?}", app);
 
     // Find elements matching the selector
-    let element = match app.locator(request.selector.locator.as_str()) {
-        Ok(locator) => match locator.first() {
-            Ok(element) => element,
-            Err(e) => {
-                error!("failed to find elements: {}", e);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    JsonResponse(json!({
-                        "error": format!("failed to find elements: {}", e)
-                    })),
-                ));
-            }
-        },
+    let source = snapshot_source(
+        &state,
+        &request.selector.app_name,
+        &request.selector.locator,
+    )
+    .await;
+    let element = match locate(&app, &request.selector.locator, source.as_ref()) {
+        Ok(element) => element,
         Err(e) => {
-            error!("failed to create locator: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({
-                    "error": format!("failed to create locator: {}", e)
-                })),
-            ));
+            error!("failed to find elements: {}", e);
+            return Err(element_lookup_error(&state, &request.selector.locator, e).await);
         }
     };
 
@@ -4050,20 +4291,32 @@ async fn press_key_handler(
 
     match element {
         Some(element) => match element.press_key(&request.key_combo) {
-            Ok(_) => Ok(JsonResponse(PressKeyResponse {
-                success: true,
-                message: format!(
-                    "successfully pressed key combination '{}' on element with role: {}",
-                    request.key_combo,
-                    element.role()
-                ),
-            })),
+            Ok(_) => {
+                record_action(
+                    &state,
+                    &request.selector.app_name,
+                    &app,
+                    &element,
+                    ScriptAction::PressKey {
+                        key: request.key_combo.clone(),
+                    },
+                );
+                Ok(JsonResponse(PressKeyResponse {
+                    success: true,
+                    message: format!(
+                        "successfully pressed key combination '{}' on element with role: {}",
+                        request.key_combo,
+                        element.role()
+                    ),
+                }))
+            }
             Err(e) => {
                 error!("failed to press key: {}", e);
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
                     JsonResponse(json!({
-                        "error": format!("failed to press key: {}", e)
+                        "error": format!("failed to press key: {}", e),
+                        "kind": e.kind()
                     })),
                 ))
             }
@@ -4126,9 +4379,10 @@ async fn press_key_by_index_handler(
                 Err(e) => {
                     error!("failed to initialize desktop automation: {}", e);
                     return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
+                        automation_status(&e),
                         JsonResponse(json!({
-                            "error": format!("failed to initialize desktop automation: {}", e)
+                            "error": format!("failed to initialize desktop automation: {}", e),
+                            "kind": e.kind()
                         })),
                     ));
                 }
@@ -4142,7 +4396,8 @@ async fn press_key_by_index_handler(
                     return Err((
                         StatusCode::NOT_FOUND,
                         JsonResponse(json!({
-                            "error": format!("application not found: {}", e)
+                            "error": format!("application not found: {}", e),
+                            "kind": e.kind()
                         })),
                     ));
                 }
@@ -4153,20 +4408,31 @@ async fn press_key_by_index_handler(
                 let element = &elements[request.element_index];
 
                 match element.press_key(&request.key_combo) {
-                    Ok(_) => Ok(JsonResponse(PressKeyByIndexResponse {
-                        success: true,
-                        message: format!(
-                            "successfully pressed key combination '{}' on element with role: {}",
-                            request.key_combo,
-                            element.role()
-                        ),
-                    })),
+                    Ok(_) => {
+                        record_indexed_action(
+                            &state,
+                            &app_name,
+                            element,
+                            ScriptAction::PressKey {
+                                key: request.key_combo.clone(),
+                            },
+                        );
+                        Ok(JsonResponse(PressKeyByIndexResponse {
+                            success: true,
+                            message: format!(
+                                "successfully pressed key combination '{}' on element with role: {}",
+                                request.key_combo,
+                                element.role()
+                            ),
+                        }))
+                    }
                     Err(e) => {
                         error!("failed to press key on element: {}", e);
                         Err((
                             StatusCode::INTERNAL_SERVER_ERROR,
                             JsonResponse(json!({
-                                "error": format!("failed to press key on element: {}", e)
+                                "error": format!("failed to press key on element: {}", e),
+                                "kind": e.kind()
                             })),
                         ))
                     }
@@ -4243,8 +4509,11 @@ async fn open_application_handler(
         Ok(desktop) => desktop,
         Err(err) => {
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": format!("Failed to initialize automation: {}", err)})),
+                automation_status(&err),
+                JsonResponse(json!({
+                    "error": format!("Failed to initialize automation: {}", err),
+                    "kind": err.kind()
+                })),
             ));
         }
     };
@@ -4257,7 +4526,10 @@ async fn open_application_handler(
         })),
         Err(err) => Err((
             StatusCode::BAD_REQUEST,
-            JsonResponse(json!({"error": format!("Failed to open application: {}", err)})),
+            JsonResponse(json!({
+                "error": format!("Failed to open application: {}", err),
+                "kind": err.kind()
+            })),
         )),
     }
 }
@@ -4273,8 +4545,11 @@ async fn open_url_handler(
         Ok(desktop) => desktop,
         Err(err) => {
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": format!("Failed to initialize automation: {}", err)})),
+                automation_status(&err),
+                JsonResponse(json!({
+                    "error": format!("Failed to initialize automation: {}", err),
+                    "kind": err.kind()
+                })),
             ));
         }
     };
@@ -4288,7 +4563,10 @@ async fn open_url_handler(
         })),
         Err(err) => Err((
             StatusCode::BAD_REQUEST,
-            JsonResponse(json!({"error": format!("Failed to open URL: {}", err)})),
+            JsonResponse(json!({
+                "error": format!("Failed to open URL: {}", err),
+                "kind": err.kind()
+            })),
         )),
     }
 }
@@ -4309,7 +4587,7 @@ pub struct ScrollElementResponse {
 // Add the handler function
 #[oasgen]
 async fn scroll_element_handler(
-    State(_): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<ScrollElementRequest>,
 ) -> Result<JsonResponse<ScrollElementResponse>, (StatusCode, JsonResponse<Value>)> {
     let desktop = match Desktop::new(
@@ -4320,9 +4598,10 @@ async fn scroll_element_handler(
         Err(e) => {
             error!("Failed to initialize desktop automation: {}", e);
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
+                automation_status(&e),
                 JsonResponse(json!({
-                    "error": format!("Failed to initialize desktop automation: {}", e)
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
                 })),
             ));
         }
@@ -4335,26 +4614,28 @@ async fn scroll_element_handler(
             error!("Failed to find application: {}", e);
             return Err((
                 StatusCode::NOT_FOUND,
-                JsonResponse(json!({"error": format!("Failed to find application: {}", e)})),
+                JsonResponse(json!({
+                    "error": format!("Failed to find application: {}", e),
+                    "kind": e.kind()
+                })),
             ));
         }
     };
 
-    let element = match app.locator(request.selector.locator.as_str()) {
-        Ok(locator) => locator.first(),
-        Err(e) => {
-            error!("Failed to find element: {}", e);
-            return Err((
-                StatusCode::NOT_FOUND,
-                JsonResponse(json!({"error": format!("Failed to find element: {}", e)})),
-            ));
-        }
-    }
-    .map_err(|e| {
+    let source = snapshot_source(
+        &state,
+        &request.selector.app_name,
+        &request.selector.locator,
+    )
+    .await;
+    let element = locate(&app, &request.selector.locator, source.as_ref()).map_err(|e| {
         error!("Failed to find element: {}", e);
         (
             StatusCode::NOT_FOUND,
-            JsonResponse(json!({"error": format!("Failed to find element: {}", e)})),
+            JsonResponse(json!({
+                "error": format!("Failed to find element: {}", e),
+                "kind": e.kind()
+            })),
         )
     })?;
 
@@ -4362,24 +4643,1685 @@ async fn scroll_element_handler(
         Some(element) => {
             // Perform scroll
             match element.scroll(&request.direction, request.amount) {
-                Ok(_) => Ok(JsonResponse(ScrollElementResponse {
-                    success: true,
-                    message: format!(
-                        "Successfully scrolled {} by {}",
-                        request.direction, request.amount
-                    ),
-                })),
+                Ok(_) => {
+                    record_action(
+                        &state,
+                        &request.selector.app_name,
+                        &app,
+                        &element,
+                        ScriptAction::Scroll {
+                            direction: request.direction.clone(),
+                            amount: request.amount,
+                        },
+                    );
+                    Ok(JsonResponse(ScrollElementResponse {
+                        success: true,
+                        message: format!(
+                            "Successfully scrolled {} by {}",
+                            request.direction, request.amount
+                        ),
+                    }))
+                }
                 Err(e) => Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
                     JsonResponse(json!({
-                        "error": format!("Failed to scroll element: {}", e)
+                        "error": format!("Failed to scroll element: {}", e),
+                        "kind": e.kind()
                     })),
                 )),
             }
         }
         None => Err((
             StatusCode::NOT_FOUND,
-            JsonResponse(json!({"error": "No element found"})),
+            JsonResponse(json!({"error": "No element found", "kind": "element_not_found"})),
         )),
     }
 }
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct HoverElementRequest {
+    selector: ElementSelector,
+    /// How long to keep the pointer over the element before taking its snapshot,
+    /// defaults to 500 and is capped at 10000
+    duration_ms: Option<u64>,
+    /// If true, the pointer is moved back to where it was before hovering
+    restore_pointer: Option<bool>,
+    /// How many levels below the element to include, defaults to 1
+    max_depth: Option<usize>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct HoverElementResponse {
+    success: bool,
+    message: String,
+    /// Snapshot of the element after the dwell, including hover-revealed controls,
+    /// in the shape /children returns
+    element: Value,
+    /// Elements whose children could not be read, with the error kind
+    errors: Value,
+}
+
+// Long enough for any tooltip, short enough not to tie up a request
+const MAX_HOVER_DURATION: Duration = Duration::from_secs(10);
+
+#[oasgen]
+async fn hover_element_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<HoverElementRequest>,
+) -> Result<JsonResponse<HoverElementResponse>, (StatusCode, JsonResponse<Value>)> {
+    use enigo::Mouse;
+
+    let desktop = match Desktop::new(
+        request.selector.use_background_apps.unwrap_or(false),
+        request.selector.activate_app.unwrap_or(false),
+    ) {
+        Ok(d) => d,
+        Err(e) => {
+            error!("Failed to initialize desktop automation: {}", e);
+            return Err((
+                automation_status(&e),
+                JsonResponse(json!({
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
+                })),
+            ));
+        }
+    };
+
+    let app = match desktop.application(&request.selector.app_name) {
+        Ok(app) => app,
+        Err(e) => {
+            error!("Failed to find application: {}", e);
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({
+                    "error": format!("Failed to find application: {}", e),
+                    "kind": e.kind()
+                })),
+            ));
+        }
+    };
+
+    let source = snapshot_source(
+        &state,
+        &request.selector.app_name,
+        &request.selector.locator,
+    )
+    .await;
+    let element = locate(&app, &request.selector.locator, source.as_ref()).map_err(|e| {
+        error!("Failed to find element: {}", e);
+        (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": format!("Failed to find element: {}", e),
+                "kind": e.kind()
+            })),
+        )
+    })?;
+
+    let element = match element {
+        Some(element) => element,
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "No element found", "kind": "element_not_found"})),
+            ))
+        }
+    };
+
+    // enigo is not Send, so only keep it around long enough to read the position
+    let original_position = if request.restore_pointer.unwrap_or(false) {
+        Enigo::new(&Settings::default())
+            .ok()
+            .and_then(|enigo| enigo.location().ok())
+    } else {
+        None
+    };
+
+    if let Err(e) = element.hover() {
+        error!("Failed to hover element: {}", e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({
+                "error": format!("Failed to hover element: {}", e),
+                "kind": e.kind()
+            })),
+        ));
+    }
+    record_action(
+        &state,
+        &request.selector.app_name,
+        &app,
+        &element,
+        ScriptAction::Hover,
+    );
+
+    // give the app time to reveal hover-only controls
+    let duration = Duration::from_millis(request.duration_ms.unwrap_or(500));
+    tokio::time::sleep(duration.min(MAX_HOVER_DURATION)).await;
+
+    let options = SnapshotOptions {
+        max_depth: request.max_depth.unwrap_or(1),
+        ..SnapshotOptions::default()
+    };
+    let (mut snapshot, stats) = ElementSnapshot::capture_with_stats(&element, &options);
+    if let Ok(layout) = desktop.displays() {
+        snapshot.assign_displays(&layout);
+    }
+
+    if let Some((x, y)) = original_position {
+        match Enigo::new(&Settings::default()) {
+            Ok(mut enigo) => {
+                let _ = enigo.move_mouse(x, y, enigo::Coordinate::Abs);
+            }
+            Err(e) => error!("failed to restore pointer position: {}", e),
+        }
+    }
+
+    Ok(JsonResponse(HoverElementResponse {
+        success: true,
+        message: format!(
+            "Hovered element with role: {} and found {} children",
+            element.role(),
+            snapshot.children.len()
+        ),
+        element: json!(snapshot),
+        errors: json!(stats.errors),
+    }))
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct ClearCacheResponse {
+    success: bool,
+    message: String,
+}
+
+#[oasgen]
+async fn clear_cache_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<ClearCacheResponse>, (StatusCode, JsonResponse<Value>)> {
+    let desktop = match Desktop::new(false, false) {
+        Ok(d) => d,
+        Err(e) => {
+            error!("Failed to initialize desktop automation: {}", e);
+            return Err((
+                automation_status(&e),
+                JsonResponse(json!({
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
+                })),
+            ));
+        }
+    };
+
+    // Cached application handles are shared by every Desktop instance
+    desktop.clear_cache();
+    *state.element_cache.lock().await = None;
+    *state.snapshot_cache.lock().await = None;
+    state.app_snapshot_cache.lock().await.clear();
+
+    Ok(JsonResponse(ClearCacheResponse {
+        success: true,
+        message: "Cleared accessibility caches".to_string(),
+    }))
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct FetchChildrenRequest {
+    selector: ElementSelector,
+    /// How many levels below the element to include, defaults to 1
+    depth: Option<usize>,
+    /// Leave out elements outside of their window, defaults to false
+    visible_only: Option<bool>,
+    /// Points around the window that still count as inside it, defaults to 50
+    visible_margin: Option<f64>,
+    /// Cut values longer than this many characters, defaults to 2048
+    max_value_length: Option<usize>,
+    /// Stop adding elements once the snapshot reaches this many bytes
+    max_payload_bytes: Option<usize>,
+    /// Return the snapshot with keys and roles moved to a string table
+    intern: Option<bool>,
+    /// Return the element as base64 encoded gzip of its JSON
+    compress: Option<bool>,
+    /// Splice out unlabeled groups that only wrap a single child, defaults to false
+    collapse_groups: Option<bool>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct CompressionInfo {
+    /// Always "gzip+base64" for now
+    encoding: String,
+    /// Size of the JSON before compression
+    original_bytes: usize,
+    /// Size of the gzip stream, before base64 encoding
+    compressed_bytes: usize,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct FetchChildrenResponse {
+    success: bool,
+    /// Snapshot of the element and its descendants up to the requested depth
+    element: Value,
+    /// Number of elements left out because they were off screen
+    skipped_offscreen: usize,
+    /// Number of elements left out once max_payload_bytes was reached
+    dropped_over_budget: usize,
+    /// Elements whose children could not be read, with the error kind. Their
+    /// subtrees are missing from `element` rather than empty
+    errors: Value,
+    /// Set when the app failed several captures in a row, how long to wait
+    /// before asking for it again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+    /// Set when `element` is compressed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<CompressionInfo>,
+}
+
+// Elements are re-resolved through their locator on every call rather than
+// kept alive between requests, so no platform handles outlive a request
+#[oasgen]
+async fn fetch_children_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FetchChildrenRequest>,
+) -> Result<JsonResponse<FetchChildrenResponse>, (StatusCode, JsonResponse<Value>)> {
+    let app_name = request.selector.app_name.clone();
+    let intern = request.intern.unwrap_or(false);
+    let compress = request.compress.unwrap_or(false);
+    // Identical requests for the same app share a single capture
+    let key = serde_json::to_string(&request).unwrap_or_default();
+    let source = snapshot_source(&state, &app_name, &request.selector.locator).await;
+
+    let captured = state
+        .snapshot_queue
+        .run(&app_name, key, move || capture_children(request, source))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            let status = match e {
+                SnapshotTaskError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (
+                status,
+                JsonResponse(json!({ "error": e.to_string(), "kind": e.kind() })),
+            )
+        })?;
+    let (snapshot, stats, _) = captured.map_err(|(status, body)| (status, JsonResponse(body)))?;
+    let retry_after = state
+        .snapshot_queue
+        .record_outcome(&app_name, !stats.errors.is_empty());
+    if let Some(delay) = retry_after {
+        debug!(
+            "Captures of {} keep failing, retry after {:?}: {:?}",
+            app_name, delay, stats.errors
+        );
+    }
+
+    // Not cached, a subtree is no stand-in for the latest snapshot of the app
+
+    let element = if intern {
+        let interned = InternedSnapshot::new(&snapshot).map_err(|e| {
+            error!("Failed to intern snapshot: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to intern snapshot: {}", e)})),
+            )
+        })?;
+        json!(interned)
+    } else {
+        json!(*snapshot)
+    };
+
+    let (element, compression) = if compress {
+        let (encoded, info) = compress_element(element).await.map_err(|e| {
+            error!("Failed to compress snapshot: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to compress snapshot: {}", e)})),
+            )
+        })?;
+        (Value::String(encoded), Some(info))
+    } else {
+        (element, None)
+    };
+
+    Ok(JsonResponse(FetchChildrenResponse {
+        success: true,
+        element,
+        skipped_offscreen: stats.skipped_offscreen,
+        dropped_over_budget: stats.dropped_over_budget,
+        errors: json!(stats.errors),
+        retry_after_ms: retry_after.map(|delay| delay.as_millis() as u64),
+        compression,
+    }))
+}
+
+// Payloads above this size are compressed on the blocking pool
+const COMPRESS_INLINE_LIMIT: usize = 64 * 1024;
+
+async fn compress_element(element: Value) -> Result<(String, CompressionInfo), anyhow::Error> {
+    let json = serde_json::to_vec(&element)?;
+    if json.len() <= COMPRESS_INLINE_LIMIT {
+        return gzip_base64(json);
+    }
+    tokio::task::spawn_blocking(move || gzip_base64(json)).await?
+}
+
+fn gzip_base64(json: Vec<u8>) -> Result<(String, CompressionInfo), anyhow::Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json)?;
+    let compressed = encoder.finish()?;
+
+    let info = CompressionInfo {
+        encoding: "gzip+base64".to_string(),
+        original_bytes: json.len(),
+        compressed_bytes: compressed.len(),
+    };
+    Ok((general_purpose::STANDARD.encode(compressed), info))
+}
+
+// Runs on the blocking pool, see `SnapshotQueue`
+fn capture_children(
+    request: FetchChildrenRequest,
+    source: Option<AppSnapshotQuery>,
+) -> CapturedSnapshot {
+    let started = Instant::now();
+    let desktop = Desktop::new(
+        request.selector.use_background_apps.unwrap_or(false),
+        request.selector.activate_app.unwrap_or(false),
+    )
+    .map_err(|e| {
+        error!("Failed to initialize desktop automation: {}", e);
+        (
+            automation_status(&e),
+            json!({
+                "error": format!("Failed to initialize desktop automation: {}", e),
+                "kind": e.kind()
+            }),
+        )
+    })?;
+
+    let app = desktop
+        .application(&request.selector.app_name)
+        .map_err(|e| {
+            error!("Failed to find application: {}", e);
+            (
+                StatusCode::NOT_FOUND,
+                json!({
+                    "error": format!("Failed to find application: {}", e),
+                    "kind": e.kind()
+                }),
+            )
+        })?;
+
+    let element = locate(&app, &request.selector.locator, source.as_ref())
+        .map_err(|e| {
+            error!("Failed to find element: {}", e);
+            (
+                StatusCode::NOT_FOUND,
+                json!({
+                    "error": format!("Failed to find element: {}", e),
+                    "kind": e.kind()
+                }),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                json!({"error": "No element found", "kind": "element_not_found"}),
+            )
+        })?;
+
+    let defaults = SnapshotOptions::default();
+    let options = SnapshotOptions {
+        max_depth: request.depth.unwrap_or(1),
+        visible_only: request.visible_only.unwrap_or(false),
+        visible_margin: request.visible_margin.unwrap_or(defaults.visible_margin),
+        max_value_length: request.max_value_length.or(defaults.max_value_length),
+        max_payload_bytes: request.max_payload_bytes,
+        collapse_groups: request.collapse_groups.unwrap_or(false),
+        ..defaults
+    };
+    let (mut snapshot, stats) = ElementSnapshot::capture_with_stats(&element, &options);
+    // Platforms without a display layout leave elements without a display
+    if let Ok(layout) = desktop.displays() {
+        snapshot.assign_displays(&layout);
+    }
+    Ok((Arc::new(snapshot), stats, started))
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct FetchAttributesRequest {
+    selector: ElementSelector,
+    /// Attribute names to return, all attributes when empty or missing
+    names: Option<Vec<String>>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct FetchAttributesResponse {
+    success: bool,
+    attributes: Value,
+}
+
+#[oasgen]
+async fn fetch_attributes_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FetchAttributesRequest>,
+) -> Result<JsonResponse<FetchAttributesResponse>, (StatusCode, JsonResponse<Value>)> {
+    let desktop = match Desktop::new(
+        request.selector.use_background_apps.unwrap_or(false),
+        request.selector.activate_app.unwrap_or(false),
+    ) {
+        Ok(d) => d,
+        Err(e) => {
+            error!("Failed to initialize desktop automation: {}", e);
+            return Err((
+                automation_status(&e),
+                JsonResponse(json!({
+                    "error": format!("Failed to initialize desktop automation: {}", e),
+                    "kind": e.kind()
+                })),
+            ));
+        }
+    };
+
+    let app = match desktop.application(&request.selector.app_name) {
+        Ok(app) => app,
+        Err(e) => {
+            error!("Failed to find application: {}", e);
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({
+                    "error": format!("Failed to find application: {}", e),
+                    "kind": e.kind()
+                })),
+            ));
+        }
+    };
+
+    let source = snapshot_source(
+        &state,
+        &request.selector.app_name,
+        &request.selector.locator,
+    )
+    .await;
+    let element = locate(&app, &request.selector.locator, source.as_ref()).map_err(|e| {
+        error!("Failed to find element: {}", e);
+        (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": format!("Failed to find element: {}", e),
+                "kind": e.kind()
+            })),
+        )
+    })?;
+
+    let element = match element {
+        Some(element) => element,
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "No element found", "kind": "element_not_found"})),
+            ))
+        }
+    };
+
+    let attributes = element.attributes();
+    let mut all = serde_json::Map::new();
+    all.insert("role".to_string(), json!(attributes.role));
+    all.insert("label".to_string(), json!(attributes.label));
+    all.insert("value".to_string(), json!(attributes.value));
+    all.insert("description".to_string(), json!(attributes.description));
+    for (name, value) in attributes.properties {
+        all.insert(name, json!(value));
+    }
+
+    let attributes = match request.names {
+        Some(names) if !names.is_empty() => names
+            .into_iter()
+            .map(|name| {
+                let value = all.get(&name).cloned().unwrap_or(Value::Null);
+                (name, value)
+            })
+            .collect(),
+        _ => all,
+    };
+
+    Ok(JsonResponse(FetchAttributesResponse {
+        success: true,
+        attributes: Value::Object(attributes),
+    }))
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct LatestSnapshotResponse {
+    app_name: String,
+    /// Milliseconds since the snapshot was taken
+    age_ms: u64,
+    element: Value,
+}
+
+// Serves the snapshot cached by the snapshot route without touching the
+// accessibility APIs, callers decide from the age whether it is fresh enough
+#[oasgen]
+async fn latest_snapshot_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<LatestSnapshotResponse>, (StatusCode, JsonResponse<Value>)> {
+    let cached = state.snapshot_cache.lock().await.clone();
+
+    match cached {
+        Some((snapshot, taken_at, app_name)) => Ok(JsonResponse(LatestSnapshotResponse {
+            app_name,
+            age_ms: taken_at.elapsed().as_millis() as u64,
+            element: json!(*snapshot),
+        })),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "no snapshot has been taken yet"})),
+        )),
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ExportTreeQuery {
+    /// "dot" or "mermaid"
+    format: String,
+    /// ID of the element to start from, the whole snapshot when missing
+    root: Option<String>,
+    /// How many levels below the root to include, defaults to 5
+    max_depth: Option<usize>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct ExportTreeResponse {
+    /// App of the snapshot the graph was drawn from
+    app_name: String,
+    /// Milliseconds since that snapshot was taken
+    age_ms: u64,
+    graph: String,
+}
+
+/// Draw the latest snapshot as a Graphviz or Mermaid graph, see `export_tree`
+#[oasgen]
+async fn export_tree_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportTreeQuery>,
+) -> Result<JsonResponse<ExportTreeResponse>, (StatusCode, JsonResponse<Value>)> {
+    let format = match query.format.as_str() {
+        "dot" => TreeFormat::Dot,
+        "mermaid" => TreeFormat::Mermaid,
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({
+                    "error": format!("Unknown format '{}', expected dot or mermaid", other),
+                    "kind": "invalid_argument"
+                })),
+            ))
+        }
+    };
+    let Some((snapshot, taken_at, app_name)) = state.snapshot_cache.lock().await.clone() else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "no snapshot has been taken yet"})),
+        ));
+    };
+
+    let graph = export_tree(
+        &snapshot,
+        format,
+        query.root.as_deref(),
+        query.max_depth.unwrap_or(5),
+    )
+    .map_err(|e| {
+        (
+            snapshot_status(&e),
+            JsonResponse(json!({"error": e.to_string(), "kind": e.kind()})),
+        )
+    })?;
+    Ok(JsonResponse(ExportTreeResponse {
+        app_name,
+        age_ms: taken_at.elapsed().as_millis() as u64,
+        graph,
+    }))
+}
+
+#[derive(Debug, Clone, OaSchema, Deserialize, Serialize)]
+pub struct AppSnapshotQuery {
+    app: String,
+    /// Title of the window to capture, the whole app when missing
+    window: Option<String>,
+    /// How many levels below the app or window to include, defaults to 5
+    max_depth: Option<usize>,
+    /// Leave out elements outside of their window, defaults to false
+    visible_only: Option<bool>,
+    /// Points around the window that still count as inside it, defaults to 50
+    visible_margin: Option<f64>,
+    /// Serve the snapshot taken with the same options when it is at most
+    /// this old, instead of capturing again
+    max_age_ms: Option<u64>,
+}
+
+impl AppSnapshotQuery {
+    // Queries capturing the same tree share a key, whatever age they accept.
+    // Keys the cache, the capture queue and stream sessions alike
+    fn cache_key(&self) -> String {
+        let defaults = SnapshotOptions::default();
+        serde_json::to_string(&AppSnapshotQuery {
+            max_depth: Some(self.max_depth.unwrap_or(5)),
+            visible_only: Some(self.visible_only.unwrap_or(false)),
+            visible_margin: Some(self.visible_margin.unwrap_or(defaults.visible_margin)),
+            max_age_ms: None,
+            ..self.clone()
+        })
+        .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct AppSnapshotResponse {
+    app_name: String,
+    /// Milliseconds since the snapshot was taken, 0 for a fresh capture
+    age_ms: u64,
+    /// Whether the snapshot came from the cache, see `max_age_ms`
+    cached: bool,
+    element: Value,
+    /// Elements whose children could not be read, with the error kind
+    errors: Value,
+    /// Set when the app failed several captures in a row, how long to wait
+    /// before asking for it again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+}
+
+// Capture failures a client can act on get their own status: 403 asks for
+// accessibility permission, 504 means the app did not answer in time
+fn snapshot_status(e: &AutomationError) -> StatusCode {
+    match e {
+        AutomationError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+        AutomationError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        AutomationError::ElementNotFound(_) => StatusCode::NOT_FOUND,
+        _ => automation_status(e),
+    }
+}
+
+// Same queue as the children route, so polling both does not
+// double the load on the app
+#[oasgen]
+async fn app_snapshot_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AppSnapshotQuery>,
+) -> Result<JsonResponse<AppSnapshotResponse>, (StatusCode, JsonResponse<Value>)> {
+    let app_name = query.app.clone();
+
+    if let Some(max_age) = query.max_age_ms.map(Duration::from_millis) {
+        let cached = state
+            .app_snapshot_cache
+            .lock()
+            .await
+            .get(&query.cache_key())
+            .cloned();
+        if let Some((snapshot, taken_at, errors)) =
+            cached.filter(|(_, taken_at, _)| taken_at.elapsed() <= max_age)
+        {
+            return Ok(JsonResponse(AppSnapshotResponse {
+                app_name,
+                age_ms: taken_at.elapsed().as_millis() as u64,
+                cached: true,
+                element: json!(*snapshot),
+                errors,
+                retry_after_ms: None,
+            }));
+        }
+    }
+
+    let query_app = query.clone();
+    let key = query.cache_key();
+    let captured = state
+        .snapshot_queue
+        .run(&app_name, key, move || capture_app(query))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            let status = match e {
+                SnapshotTaskError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (
+                status,
+                JsonResponse(json!({ "error": e.to_string(), "kind": e.kind() })),
+            )
+        })?;
+    let (snapshot, stats, started) =
+        captured.map_err(|(status, body)| (status, JsonResponse(body)))?;
+    let retry_after = state
+        .snapshot_queue
+        .record_outcome(&app_name, !stats.errors.is_empty());
+
+    // Nothing below the root means the app never answered
+    if snapshot.unresponsive {
+        return Err((
+            StatusCode::GATEWAY_TIMEOUT,
+            JsonResponse(json!({
+                "error": format!("{} did not answer in time", app_name),
+                "kind": "timeout",
+                "retry_after_ms": retry_after.map(|delay| delay.as_millis() as u64)
+            })),
+        ));
+    }
+
+    cache_app_snapshot(&state, &query_app, &snapshot, json!(stats.errors)).await;
+    record_snapshot(&state, &query_app, snapshot.clone(), started);
+
+    Ok(JsonResponse(AppSnapshotResponse {
+        app_name,
+        age_ms: 0,
+        cached: false,
+        element: json!(*snapshot),
+        errors: json!(stats.errors),
+        retry_after_ms: retry_after.map(|delay| delay.as_millis() as u64),
+    }))
+}
+
+/// Keep a fresh capture as the latest snapshot and for later requests of the
+/// same query
+async fn cache_app_snapshot(
+    state: &AppState,
+    query: &AppSnapshotQuery,
+    snapshot: &Arc<ElementSnapshot>,
+    errors: Value,
+) {
+    let now = Instant::now();
+    *state.snapshot_cache.lock().await = Some((snapshot.clone(), now, query.app.clone()));
+    state
+        .app_snapshot_cache
+        .lock()
+        .await
+        .put(query.cache_key(), (snapshot.clone(), now, errors));
+}
+
+/// Hand a fresh capture, started at `started`, to the snapshot recorder, if
+/// recording is enabled
+fn record_snapshot(
+    state: &AppState,
+    query: &AppSnapshotQuery,
+    snapshot: Arc<ElementSnapshot>,
+    started: Instant,
+) {
+    let Some(recorder) = state.snapshot_recorder.clone() else {
+        return;
+    };
+    // Checked before asking the app for its focused element
+    if !recorder.is_due(&query.app, query.window.as_deref(), &snapshot) {
+        return;
+    }
+    // Stamped like frames, so the capture's own latency does not skew matching
+    let captured_at = wall_clock_time(started);
+    let app_name = query.app.clone();
+    let window_name = query.window.clone();
+    tokio::spawn(async move {
+        let focused = {
+            let snapshot = snapshot.clone();
+            tokio::task::spawn_blocking(move || focused_label(&snapshot))
+                .await
+                .ok()
+                .flatten()
+        };
+        if let Err(e) = recorder
+            .record(
+                captured_at,
+                &app_name,
+                window_name.as_deref(),
+                focused.as_deref(),
+                &snapshot,
+            )
+            .await
+        {
+            error!("Failed to record snapshot of {}: {}", app_name, e);
+        }
+    });
+}
+
+// Label of the focused element, when it is part of `snapshot`
+fn focused_label(snapshot: &ElementSnapshot) -> Option<String> {
+    let focused = Desktop::new(false, false).ok()?.focused_element().ok()?;
+    snapshot.find(&focused.id()?)?.label.clone()
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RecordedSnapshotsQuery {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    app_name: Option<String>,
+}
+
+fn snapshot_recorder(
+    state: &AppState,
+) -> Result<&Arc<SnapshotRecorder>, (StatusCode, JsonResponse<Value>)> {
+    state.snapshot_recorder.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": "Snapshot recording is disabled, start with --enable-operator-snapshot-recording"
+            })),
+        )
+    })
+}
+
+#[oasgen]
+async fn recorded_snapshots_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecordedSnapshotsQuery>,
+) -> Result<JsonResponse<Vec<UiSnapshotMeta>>, (StatusCode, JsonResponse<Value>)> {
+    snapshot_recorder(&state)?
+        .query_snapshots(query.start_time, query.end_time, query.app_name.as_deref())
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to query recorded snapshots: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({ "error": e.to_string() })),
+            )
+        })
+}
+
+#[oasgen]
+async fn recorded_snapshot_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match snapshot_recorder(&state)?.load_snapshot(id).await {
+        Ok(Some(snapshot)) => Ok(JsonResponse(json!(snapshot))),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({ "error": format!("No recorded snapshot {}", id) })),
+        )),
+        Err(e) => {
+            error!("Failed to load recorded snapshot {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({ "error": e.to_string() })),
+            ))
+        }
+    }
+}
+
+/// The recorded snapshot taken closest to a frame, or the nearest two when
+/// none was taken within the tolerance
+#[oasgen]
+async fn frame_snapshot_handler(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match snapshot_recorder(&state)?
+        .get_snapshot_for_frame(frame_id)
+        .await
+    {
+        Ok(Some(matched)) => Ok(JsonResponse(json!(matched))),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({ "error": format!("No frame {}", frame_id) })),
+        )),
+        Err(e) => {
+            error!("Failed to match a snapshot to frame {}: {}", frame_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({ "error": e.to_string() })),
+            ))
+        }
+    }
+}
+
+// Where a capture for `query` starts, the app or the window it names
+fn capture_root(
+    app: &UIElement,
+    query: &AppSnapshotQuery,
+) -> Result<Option<UIElement>, AutomationError> {
+    match &query.window {
+        Some(title) => app.locator(format!("window:{}", title).as_str())?.first(),
+        None => Ok(Some(app.clone())),
+    }
+}
+
+fn snapshot_options(query: &AppSnapshotQuery) -> SnapshotOptions {
+    let defaults = SnapshotOptions::default();
+    SnapshotOptions {
+        max_depth: query.max_depth.unwrap_or(5),
+        visible_only: query.visible_only.unwrap_or(false),
+        visible_margin: query.visible_margin.unwrap_or(defaults.visible_margin),
+        ..defaults
+    }
+}
+
+// Runs on the blocking pool, see `SnapshotQueue`
+fn capture_app(query: AppSnapshotQuery) -> CapturedSnapshot {
+    let started = Instant::now();
+    let failed = |what: &str, e: AutomationError| {
+        error!("{}: {}", what, e);
+        (
+            snapshot_status(&e),
+            json!({ "error": format!("{}: {}", what, e), "kind": e.kind() }),
+        )
+    };
+
+    let desktop = Desktop::new(false, false)
+        .map_err(|e| failed("Failed to initialize desktop automation", e))?;
+    let app = desktop
+        .application(&query.app)
+        .map_err(|e| failed("Failed to find application", e))?;
+    let root = capture_root(&app, &query)
+        .map_err(|e| failed("Failed to find window", e))?
+        .ok_or_else(|| {
+            let title = query.window.as_deref().unwrap_or_default();
+            (
+                StatusCode::NOT_FOUND,
+                json!({
+                    "error": format!("No window titled '{}'", title),
+                    "kind": "element_not_found"
+                }),
+            )
+        })?;
+
+    let (mut snapshot, stats) =
+        ElementSnapshot::capture_with_stats(&root, &snapshot_options(&query));
+    if let Ok(layout) = desktop.displays() {
+        snapshot.assign_displays(&layout);
+    }
+    Ok((Arc::new(snapshot), stats, started))
+}
+
+const SNAPSHOT_STREAM_INTERVAL: Duration = Duration::from_millis(500);
+const SNAPSHOT_STREAM_KEYFRAME_EVERY: u64 = 20;
+
+#[derive(Deserialize)]
+struct SnapshotStreamQuery {
+    app: String,
+    /// Title of the window to stream, the whole app when missing
+    window: Option<String>,
+    /// How many levels below the app or window to include, defaults to 5
+    max_depth: Option<usize>,
+    /// "full" sends a whole snapshot on every change instead of a diff
+    mode: Option<String>,
+    /// Comma separated roles such as "button,text_field". Other elements are
+    /// left out unless they contain one of these
+    roles: Option<String>,
+    /// Send a whole snapshot after this many diffs, defaults to 20
+    keyframe_every: Option<u64>,
+}
+
+async fn snapshot_stream_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SnapshotStreamQuery>,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_snapshot_stream(socket, state, query))
+}
+
+// Every client of the same app and scope reads from one polling session, and
+// diffs against what it was sent last. Messages are numbered so a client can
+// tell it missed one and wait for the next full snapshot
+async fn handle_snapshot_stream(
+    socket: WebSocket,
+    state: Arc<AppState>,
+    query: SnapshotStreamQuery,
+) {
+    let capture_query = AppSnapshotQuery {
+        app: query.app.clone(),
+        window: query.window.clone(),
+        max_depth: query.max_depth,
+        visible_only: None,
+        visible_margin: None,
+        max_age_ms: None,
+    };
+    let key = capture_query.cache_key();
+    let streams = state.snapshot_streams.clone();
+    let mut frames = streams.subscribe(&key, SNAPSHOT_STREAM_INTERVAL, move || {
+        capture_stream_frame(state.clone(), capture_query.clone())
+    });
+
+    let roles: Option<HashSet<Role>> = query.roles.as_deref().map(|roles| {
+        roles
+            .split(',')
+            .map(str::trim)
+            .filter(|role| !role.is_empty())
+            .map(Role::from_native)
+            .collect()
+    });
+    let full = query.mode.as_deref() == Some("full");
+    let keyframe_every = query
+        .keyframe_every
+        .unwrap_or(SNAPSHOT_STREAM_KEYFRAME_EVERY)
+        .max(1);
+
+    let (mut sender, mut receiver) = socket.split();
+    let mut previous: Option<ElementSnapshot> = None;
+    let mut seq: u64 = 0;
+    let mut since_keyframe = 0;
+    loop {
+        let frame = frames.borrow_and_update().clone();
+        let message = match frame {
+            Some(Ok(snapshot)) => {
+                let mut snapshot = (*snapshot).clone();
+                if let Some(roles) = &roles {
+                    snapshot.prune(|element| roles.contains(&element.role));
+                }
+                let message = match snapshot_update(previous.as_ref(), &snapshot) {
+                    SnapshotUpdate::Unchanged => None,
+                    SnapshotUpdate::Diff(diff) if !full && since_keyframe < keyframe_every => {
+                        since_keyframe += 1;
+                        Some(json!({ "type": "diff", "diff": diff }))
+                    }
+                    _ => {
+                        since_keyframe = 0;
+                        Some(json!({ "type": "full", "snapshot": snapshot }))
+                    }
+                };
+                previous = Some(snapshot);
+                message
+            }
+            Some(Err(error)) => Some(json!({ "type": "error", "error": error })),
+            None => None,
+        };
+
+        if let Some(mut message) = message {
+            seq += 1;
+            message["seq"] = json!(seq);
+            if let Err(e) = sender.send(Message::Text(message.to_string())).await {
+                debug!("Failed to send snapshot stream message: {}", e);
+                break;
+            }
+        }
+
+        tokio::select! {
+            changed = frames.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            incoming = receiver.next() => {
+                if matches!(incoming, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    break;
+                }
+            }
+        }
+    }
+
+    debug!("Snapshot stream of {} closed", query.app);
+}
+
+const SNAPSHOT_EVENTS_HEARTBEAT: Duration = Duration::from_secs(15);
+
+#[derive(Deserialize)]
+struct SnapshotEventsQuery {
+    app: String,
+    /// Title of the window to watch, the whole app when missing
+    window: Option<String>,
+    /// How many levels below the app or window to watch, defaults to 5
+    max_depth: Option<usize>,
+    /// Comma separated event names such as "window-opened", all when missing
+    events: Option<String>,
+}
+
+struct SnapshotEventsState {
+    frames: tokio::sync::watch::Receiver<Option<StreamFrame>>,
+    previous: Option<Arc<ElementSnapshot>>,
+    failing: bool,
+    wanted: Option<HashSet<String>>,
+    last_id: u64,
+    pending: std::collections::VecDeque<(&'static str, Value)>,
+}
+
+impl SnapshotEventsState {
+    fn push(&mut self, name: &'static str, data: Value) {
+        if self
+            .wanted
+            .as_ref()
+            .is_none_or(|wanted| wanted.contains(name))
+        {
+            self.pending.push_back((name, data));
+        }
+    }
+}
+
+// Events come from comparing the captures of the polling session shared with
+// the snapshot stream. Each client compares a capture against the last one it
+// looked at, so a slow client skips captures but never misses a window
+// closing. Events are not kept after they are sent, a client reconnecting with
+// Last-Event-ID gets a "reset" event and should re-read the snapshot
+async fn snapshot_events_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SnapshotEventsQuery>,
+    headers: HeaderMap,
+) -> Sse<impl futures::Stream<Item = Result<SseEvent, std::convert::Infallible>>> {
+    let capture_query = AppSnapshotQuery {
+        app: query.app,
+        window: query.window,
+        max_depth: query.max_depth,
+        visible_only: None,
+        visible_margin: None,
+        max_age_ms: None,
+    };
+    let key = capture_query.cache_key();
+    let streams = state.snapshot_streams.clone();
+    let mut frames = streams.subscribe(&key, SNAPSHOT_STREAM_INTERVAL, move || {
+        capture_stream_frame(state.clone(), capture_query.clone())
+    });
+
+    let previous = match frames.borrow_and_update().clone() {
+        Some(Ok(snapshot)) => Some(snapshot),
+        _ => None,
+    };
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| id.parse::<u64>().ok());
+    let mut events = SnapshotEventsState {
+        frames,
+        previous,
+        failing: false,
+        wanted: query.events.map(|events| {
+            events
+                .split(',')
+                .map(|event| event.trim().to_string())
+                .collect()
+        }),
+        last_id: last_event_id.unwrap_or(0),
+        pending: std::collections::VecDeque::new(),
+    };
+    if let Some(last_event_id) = last_event_id {
+        // Not filtered, a client must always learn that it missed events
+        events
+            .pending
+            .push_back(("reset", json!({ "last_event_id": last_event_id })));
+    }
+
+    let stream = futures::stream::unfold(events, |mut events| async move {
+        loop {
+            if let Some((name, data)) = events.pending.pop_front() {
+                events.last_id += 1;
+                let event = SseEvent::default()
+                    .id(events.last_id.to_string())
+                    .event(name)
+                    .data(data.to_string());
+                return Some((Ok(event), events));
+            }
+
+            // The session only ends when every client is gone, so this waits
+            // for the next capture
+            events.frames.changed().await.ok()?;
+            let frame = events.frames.borrow_and_update().clone();
+            match frame {
+                Some(Ok(snapshot)) => {
+                    events.failing = false;
+                    if let Some(previous) = events.previous.replace(snapshot.clone()) {
+                        for event in snapshot_events(&previous, &snapshot) {
+                            events.push(event.name(), json!(event));
+                        }
+                    }
+                }
+                // Only the first of several failures in a row is reported
+                Some(Err(error)) if !events.failing => {
+                    events.failing = true;
+                    events.push("error", error);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(SNAPSHOT_EVENTS_HEARTBEAT)
+            .text("heartbeat"),
+    )
+}
+
+async fn capture_stream_frame(
+    state: Arc<AppState>,
+    query: AppSnapshotQuery,
+) -> (StreamFrame, Option<Duration>) {
+    let app_name = query.app.clone();
+    let query_app = query.clone();
+    let key = query.cache_key();
+    let captured = state
+        .snapshot_queue
+        .run(&app_name, key, move || capture_app(query))
+        .await;
+
+    match captured {
+        Ok(Ok((snapshot, stats, started))) => {
+            let retry_after = state
+                .snapshot_queue
+                .record_outcome(&app_name, !stats.errors.is_empty());
+            cache_app_snapshot(&state, &query_app, &snapshot, json!(stats.errors)).await;
+            record_snapshot(&state, &query_app, snapshot.clone(), started);
+            (Ok(snapshot), retry_after)
+        }
+        Ok(Err((_, body))) => (
+            Err(body),
+            state.snapshot_queue.record_outcome(&app_name, true),
+        ),
+        Err(e) => (
+            Err(json!({ "error": e.to_string(), "kind": e.kind() })),
+            state.snapshot_queue.record_outcome(&app_name, true),
+        ),
+    }
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct SnapshotQueueResponse {
+    /// Snapshot captures waiting or running, per app
+    depth: HashMap<String, usize>,
+    /// Milliseconds to wait before capturing again, per app whose last
+    /// captures failed
+    retry_after_ms: HashMap<String, u64>,
+}
+
+#[oasgen]
+async fn snapshot_queue_handler(
+    State(state): State<Arc<AppState>>,
+) -> JsonResponse<SnapshotQueueResponse> {
+    JsonResponse(SnapshotQueueResponse {
+        depth: state.snapshot_queue.depth(),
+        retry_after_ms: state
+            .snapshot_queue
+            .backoff()
+            .into_iter()
+            .map(|(app, delay)| (app, delay.as_millis() as u64))
+            .collect(),
+    })
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct DisplaysResponse {
+    /// Connected displays, bounds in points from the top-left corner of the
+    /// primary display
+    displays: Value,
+}
+
+fn display_layout() -> Result<DisplayLayout, (StatusCode, JsonResponse<Value>)> {
+    Desktop::new(false, false)
+        .and_then(|desktop| desktop.displays())
+        .map_err(|e| {
+            error!("Failed to get display layout: {}", e);
+            (
+                automation_status(&e),
+                JsonResponse(json!({
+                    "error": format!("Failed to get display layout: {}", e),
+                    "kind": e.kind()
+                })),
+            )
+        })
+}
+
+#[oasgen]
+async fn displays_handler(
+) -> Result<JsonResponse<DisplaysResponse>, (StatusCode, JsonResponse<Value>)> {
+    let layout = display_layout()?;
+    Ok(JsonResponse(DisplaysResponse {
+        displays: json!(layout.displays),
+    }))
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct ConvertPointRequest {
+    x: f64,
+    y: f64,
+    /// Display the point is relative to, global coordinates when missing
+    from_display: Option<u32>,
+    /// Display to make the point relative to, global coordinates when missing
+    to_display: Option<u32>,
+    /// The input point is in pixels of `from_display` instead of points
+    from_physical: Option<bool>,
+    /// Return the point in pixels of `to_display` instead of points
+    to_physical: Option<bool>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct ConvertPointResponse {
+    x: f64,
+    y: f64,
+}
+
+#[oasgen]
+async fn convert_point_handler(
+    Json(request): Json<ConvertPointRequest>,
+) -> Result<JsonResponse<ConvertPointResponse>, (StatusCode, JsonResponse<Value>)> {
+    let invalid = |e: AutomationError| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": e.to_string(), "kind": e.kind()})),
+        )
+    };
+    let from = coordinate_space(request.from_display, request.from_physical).map_err(invalid)?;
+    let to = coordinate_space(request.to_display, request.to_physical).map_err(invalid)?;
+    let layout = display_layout()?;
+    let (x, y) = layout
+        .convert_point(from, to, request.x, request.y)
+        .map_err(invalid)?;
+    Ok(JsonResponse(ConvertPointResponse { x, y }))
+}
+
+fn coordinate_space(
+    display: Option<u32>,
+    physical: Option<bool>,
+) -> Result<CoordinateSpace, AutomationError> {
+    match (display, physical) {
+        (Some(id), Some(true)) => Ok(CoordinateSpace::Physical(id)),
+        (Some(id), _) => Ok(CoordinateSpace::Display(id)),
+        (None, Some(true)) => Err(AutomationError::InvalidArgument(
+            "physical coordinates need a display".to_string(),
+        )),
+        (None, _) => Ok(CoordinateSpace::Global),
+    }
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct OcrBlockRequest {
+    text: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+// A failed recording must not fail the action, which already happened
+fn record_action(
+    state: &AppState,
+    app_name: &str,
+    app: &UIElement,
+    element: &UIElement,
+    action: ScriptAction,
+) {
+    if let Err(e) = state.script_recorder.record(app_name, app, element, action) {
+        warn!("Failed to record action on {}: {}", app_name, e);
+    }
+}
+
+// The by-index routes only keep the app name next to their elements, so the app
+// is looked up again, and only while a script is being recorded
+fn record_indexed_action(
+    state: &AppState,
+    app_name: &str,
+    element: &UIElement,
+    action: ScriptAction,
+) {
+    if !state.script_recorder.is_recording() {
+        return;
+    }
+    match Desktop::new(false, false).and_then(|desktop| desktop.application(app_name)) {
+        Ok(app) => record_action(state, app_name, &app, element, action),
+        Err(e) => warn!("Failed to record action on {}: {}", app_name, e),
+    }
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct CorrelateOcrRequest {
+    blocks: Vec<OcrBlockRequest>,
+    /// Display the block bounds are relative to, global coordinates when missing
+    display: Option<u32>,
+    /// Block bounds are in pixels of `display` instead of points, as for OCR
+    /// run on a screenshot of the display
+    physical: Option<bool>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct CorrelateOcrResponse {
+    /// App of the snapshot the blocks were matched against
+    app_name: String,
+    /// Milliseconds since that snapshot was taken
+    age_ms: u64,
+    /// One entry per block, in order, with the matching elements best first
+    matches: Value,
+}
+
+/// Match OCR blocks against the latest snapshot, see `correlate_ocr`
+#[oasgen]
+async fn correlate_ocr_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CorrelateOcrRequest>,
+) -> Result<JsonResponse<CorrelateOcrResponse>, (StatusCode, JsonResponse<Value>)> {
+    let invalid = |e: AutomationError| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": e.to_string(), "kind": e.kind()})),
+        )
+    };
+    let Some((snapshot, taken_at, app_name)) = state.snapshot_cache.lock().await.clone() else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "no snapshot has been taken yet"})),
+        ));
+    };
+
+    let space = coordinate_space(request.display, request.physical).map_err(invalid)?;
+    let layout = match space {
+        CoordinateSpace::Global => DisplayLayout::default(),
+        _ => display_layout()?,
+    };
+    let blocks: Vec<OcrBlock> = request
+        .blocks
+        .into_iter()
+        .map(|block| OcrBlock {
+            text: block.text,
+            bounds: Bounds::from((block.x, block.y, block.width, block.height)),
+        })
+        .collect();
+    let matches = correlate_ocr(&snapshot, &blocks, space, &layout).map_err(invalid)?;
+
+    Ok(JsonResponse(CorrelateOcrResponse {
+        app_name,
+        age_ms: taken_at.elapsed().as_millis() as u64,
+        matches: json!(matches),
+    }))
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct RecordingResponse {
+    /// The steps recorded, see `AutomationScript`. Empty when starting
+    script: Value,
+}
+
+/// Start recording clicks and typing done through the API into a script,
+/// dropping the one being recorded if any
+#[oasgen]
+async fn start_recording_handler(
+    State(state): State<Arc<AppState>>,
+) -> JsonResponse<ActionResponse> {
+    state.script_recorder.start_recording();
+    JsonResponse(ActionResponse {
+        success: true,
+        message: "Recording actions".to_string(),
+    })
+}
+
+/// Stop recording and return the script
+#[oasgen]
+async fn stop_recording_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<RecordingResponse>, (StatusCode, JsonResponse<Value>)> {
+    if !state.script_recorder.is_recording() {
+        return Err((
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": "no recording is running"})),
+        ));
+    }
+    Ok(JsonResponse(RecordingResponse {
+        script: json!(state.script_recorder.stop_recording()),
+    }))
+}
+
+#[derive(Debug, OaSchema, Deserialize, Serialize)]
+pub struct ReplayScriptRequest {
+    /// A script as returned by the recording stop endpoint
+    script: Value,
+    /// See `ReplayOptions`, every field defaults
+    #[serde(default)]
+    options: Option<Value>,
+}
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct ReplayScriptResponse {
+    /// Whether every step succeeded
+    completed: bool,
+    /// Per step the number of attempts and the error it failed with, if any
+    steps: Value,
+}
+
+/// Replay a recorded script, looking up every element again
+#[oasgen]
+async fn replay_script_handler(
+    Json(request): Json<ReplayScriptRequest>,
+) -> Result<JsonResponse<ReplayScriptResponse>, (StatusCode, JsonResponse<Value>)> {
+    let invalid = |e: serde_json::Error| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": e.to_string(), "kind": "invalid_argument"})),
+        )
+    };
+    let script: AutomationScript = serde_json::from_value(request.script).map_err(invalid)?;
+    let options: ReplayOptions = match request.options {
+        Some(options) => serde_json::from_value(options).map_err(invalid)?,
+        None => ReplayOptions::default(),
+    };
+
+    // Steps wait and retry with blocking sleeps
+    let report = tokio::task::spawn_blocking(move || {
+        Desktop::new(false, true).map(|desktop| replay_script(&desktop, &script, &options))
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?
+    .map_err(|e| {
+        (
+            automation_status(&e),
+            JsonResponse(json!({
+                "error": format!("Failed to initialize desktop automation: {}", e),
+                "kind": e.kind()
+            })),
+        )
+    })?;
+
+    Ok(JsonResponse(ReplayScriptResponse {
+        completed: report.completed,
+        steps: json!(report.steps),
+    }))
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AppleScriptRequest {
+    /// AppleScript source, its `on run argv` handler gets `args`
+    source: Option<String>,
+    /// Name of a shortcut to run instead, with `args` as its input
+    shortcut: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    /// Defaults to 30 seconds
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppleScriptResponse {
+    /// The script result as JSON, values with no JSON counterpart such as
+    /// dates as their AppleScript source text
+    result: Value,
+}
+
+/// Origins of the desktop app's webview, on macOS and on Windows
+const APP_ORIGINS: [&str; 3] = [
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+];
+
+/// Whether a caller may run scripts: it has to be on this machine, and a
+/// browser page other than the app's must not have sent the request. CORS
+/// lets any page call the server, and the server may listen on every
+/// interface
+fn is_trusted_caller(addr: &SocketAddr, headers: &HeaderMap) -> bool {
+    let origin_allowed = match headers.get(axum::http::header::ORIGIN) {
+        None => true,
+        Some(origin) => origin
+            .to_str()
+            .is_ok_and(|origin| APP_ORIGINS.contains(&origin)),
+    };
+    addr.ip().to_canonical().is_loopback() && origin_allowed
+}
+
+/// Actions performed on the mock accessibility engine, oldest first.
+/// Registered only while one is in use
+async fn mock_actions_handler() -> JsonResponse<Vec<screenpipe_core::operator::MockAction>> {
+    JsonResponse(
+        screenpipe_core::operator::mock_engine()
+            .map(|engine| engine.action_log())
+            .unwrap_or_default(),
+    )
+}
+
+/// Run AppleScript or a shortcut, macOS only. Registered only with
+/// --enable-operator-applescript
+async fn apple_script_handler(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<AppleScriptRequest>,
+) -> Result<JsonResponse<AppleScriptResponse>, (StatusCode, JsonResponse<Value>)> {
+    if !is_trusted_caller(&addr, &headers) {
+        warn!(
+            "refused applescript request from {} with origin {:?}",
+            addr,
+            headers.get(axum::http::header::ORIGIN)
+        );
+        return Err((
+            StatusCode::FORBIDDEN,
+            JsonResponse(json!({
+                "error": "scripts can only be run from this machine",
+                "kind": "forbidden"
+            })),
+        ));
+    }
+    let source = match (request.source, request.shortcut) {
+        (Some(source), None) => AppleScriptSource::Source(source),
+        (None, Some(shortcut)) => AppleScriptSource::Shortcut(shortcut),
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({
+                    "error": "give either source or shortcut",
+                    "kind": "invalid_argument"
+                })),
+            ))
+        }
+    };
+    let timeout = Duration::from_millis(request.timeout_ms.unwrap_or(30_000));
+    let args = request.args;
+
+    let result = tokio::task::spawn_blocking(move || run_apple_script(&source, &args, timeout))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?
+        .map_err(|e| {
+            // The user has to allow automation of the app in System Settings
+            let status = match e {
+                AutomationError::AppleEventsDenied(_) => StatusCode::FORBIDDEN,
+                AutomationError::InvalidArgument(_) => StatusCode::BAD_REQUEST,
+                AutomationError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                _ => automation_status(&e),
+            };
+            (
+                status,
+                JsonResponse(json!({"error": e.to_string(), "kind": e.kind()})),
+            )
+        })?;
+
+    Ok(JsonResponse(AppleScriptResponse { result }))
+}
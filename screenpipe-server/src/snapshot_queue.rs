@@ -0,0 +1,244 @@
+//! Per-app queue for accessibility snapshot requests
+//!
+//! Accessibility APIs serve one request per app at a time anyway, so running
+//! several captures of the same app side by side only makes each of them slower.
+//! Captures are queued per app behind a concurrency limit, and a request that is
+//! identical to one already pending waits for that capture instead of starting
+//! its own.
+//!
+//! Every capture has a deadline. A platform call that hangs keeps its blocking
+//! thread and its app's slot until it returns, but the callers waiting on it get
+//! a timeout instead of waiting along.
+//!
+//! Apps whose captures keep failing, usually because they do not implement
+//! accessibility or access was not granted, get an increasing retry delay that
+//! callers are told about so they can stop polling them as often.
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+type PendingCapture<T> = Shared<BoxFuture<'static, Result<T, SnapshotTaskError>>>;
+
+/// Why a queued capture has no result
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotTaskError {
+    /// The capture did not finish within the deadline, counting the time spent
+    /// waiting for a slot
+    Timeout(Duration),
+    /// The capture panicked, with the panic message
+    Panicked(String),
+    /// The capture was dropped before it ran, e.g. during runtime shutdown
+    Cancelled,
+}
+
+impl SnapshotTaskError {
+    /// Machine readable name of the error, matching `AutomationError::kind`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SnapshotTaskError::Timeout(_) => "timeout",
+            SnapshotTaskError::Panicked(_) => "panicked",
+            SnapshotTaskError::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for SnapshotTaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotTaskError::Timeout(deadline) => {
+                write!(f, "snapshot capture timed out after {:?}", deadline)
+            }
+            SnapshotTaskError::Panicked(message) => {
+                write!(f, "snapshot capture panicked: {}", message)
+            }
+            SnapshotTaskError::Cancelled => write!(f, "snapshot capture was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotTaskError {}
+
+struct AppQueue<T> {
+    permits: Arc<Semaphore>,
+    // Captures waiting for a permit or running, keyed by request
+    pending: HashMap<String, PendingCapture<T>>,
+    // Captures in a row that failed
+    failures: u32,
+}
+
+pub struct SnapshotQueue<T> {
+    concurrency: usize,
+    timeout: Duration,
+    apps: Arc<Mutex<HashMap<String, AppQueue<T>>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> SnapshotQueue<T> {
+    /// Create a queue running at most `concurrency` captures per app at once
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            timeout: DEFAULT_TIMEOUT,
+            apps: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Give up on captures that take longer than `timeout`, 30 seconds by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run `capture` on the blocking pool once a slot for `app` is free
+    ///
+    /// Calls with the same `app` and `key` made while an earlier one is still
+    /// pending get that call's result and never run their own `capture`.
+    ///
+    /// A capture that times out keeps running, its result is dropped when it
+    /// finishes so nothing it returns reaches the caller. A capture whose
+    /// callers all went away still runs to the end or to its deadline, then
+    /// leaves the queue.
+    pub async fn run<F>(&self, app: &str, key: String, capture: F) -> Result<T, SnapshotTaskError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let pending = {
+            let mut apps = self.lock_apps();
+            let queue = apps.entry(app.to_string()).or_insert_with(|| AppQueue {
+                permits: Arc::new(Semaphore::new(self.concurrency)),
+                pending: HashMap::new(),
+                failures: 0,
+            });
+
+            match queue.pending.get(&key) {
+                Some(pending) => pending.clone(),
+                None => {
+                    let pending = self.start(queue.permits.clone(), app, &key, capture);
+                    queue.pending.insert(key, pending.clone());
+                    pending
+                }
+            }
+        };
+
+        pending.await
+    }
+
+    // Run the capture as its own task, so its entry leaves `pending` when it
+    // ends even if every caller waiting on it was dropped. The caller holds the
+    // lock until the entry is inserted, so the task cannot remove it earlier,
+    // and no other capture is queued under the key while the entry is there
+    fn start<F>(
+        &self,
+        permits: Arc<Semaphore>,
+        app: &str,
+        key: &str,
+        capture: F,
+    ) -> PendingCapture<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let timeout = self.timeout;
+        let capture = async move {
+            let permit = permits
+                .acquire_owned()
+                .await
+                .map_err(|_| SnapshotTaskError::Cancelled)?;
+            // The permit goes with the task, a capture that outlives its
+            // deadline still blocks the app until it returns
+            tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                capture()
+            })
+            .await
+            .map_err(join_error)
+        };
+
+        let apps = self.apps.clone();
+        let app = app.to_string();
+        let key = key.to_string();
+        let task = tokio::spawn(async move {
+            let result = tokio::time::timeout(timeout, capture)
+                .await
+                .unwrap_or(Err(SnapshotTaskError::Timeout(timeout)));
+
+            let mut apps = apps.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(queue) = apps.get_mut(&app) {
+                queue.pending.remove(&key);
+            }
+            result
+        });
+
+        async move { task.await.map_err(join_error)? }
+            .boxed()
+            .shared()
+    }
+
+    /// Record whether a capture of `app` failed and return how long callers
+    /// should wait before capturing it again
+    ///
+    /// The delay doubles with every failure in a row, from one second up to a
+    /// minute, and is cleared by the first capture that succeeds.
+    pub fn record_outcome(&self, app: &str, failed: bool) -> Option<Duration> {
+        let mut apps = self.lock_apps();
+        let queue = apps.get_mut(app)?;
+        queue.failures = if failed {
+            queue.failures.saturating_add(1)
+        } else {
+            0
+        };
+        retry_delay(queue.failures)
+    }
+
+    /// Current retry delay, per app whose last captures failed
+    pub fn backoff(&self) -> HashMap<String, Duration> {
+        self.lock_apps()
+            .iter()
+            .filter_map(|(app, queue)| Some((app.clone(), retry_delay(queue.failures)?)))
+            .collect()
+    }
+
+    /// Number of captures waiting or running, per app with any
+    pub fn depth(&self) -> HashMap<String, usize> {
+        self.lock_apps()
+            .iter()
+            .filter(|(_, queue)| !queue.pending.is_empty())
+            .map(|(app, queue)| (app.clone(), queue.pending.len()))
+            .collect()
+    }
+
+    // Every change to the map is a single insert or remove, so it stays
+    // consistent even if a holder panicked
+    fn lock_apps(&self) -> MutexGuard<'_, HashMap<String, AppQueue<T>>> {
+        self.apps.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn join_error(error: tokio::task::JoinError) -> SnapshotTaskError {
+    match error.try_into_panic() {
+        Ok(panic) => SnapshotTaskError::Panicked(panic_message(panic)),
+        Err(_) => SnapshotTaskError::Cancelled,
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+fn retry_delay(failures: u32) -> Option<Duration> {
+    let doublings = failures.checked_sub(1)?.min(6);
+    Some((BASE_RETRY_DELAY * 2u32.pow(doublings)).min(MAX_RETRY_DELAY))
+}
@@ -0,0 +1,265 @@
+//! Stores accessibility snapshots next to the screen recording
+//!
+//! Snapshots are gzipped JSON in the `ui_snapshots` table, with the columns
+//! needed to find one again kept uncompressed. A window whose tree did not
+//! change is not stored again, and one that keeps changing is stored at most
+//! once per `min_interval`. Only whole app or window captures are stored, the
+//! subtrees fetched through `/children` do not show what was on screen.
+//!
+//! Snapshots are matched to screen frames by timestamp. Both are stamped with
+//! `wall_clock_time` of the moment their capture started, rather than when
+//! they reached the database, so OCR, accessibility and compression time do
+//! not skew the match.
+
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use screenpipe_core::ElementSnapshot;
+use screenpipe_db::{DatabaseManager, UiSnapshotMeta};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+#[derive(Debug, Clone)]
+pub struct SnapshotRecorderConfig {
+    /// Snapshots older than this are deleted
+    pub retention: Duration,
+    /// The oldest snapshots are deleted once all of them take more than this
+    pub max_total_bytes: u64,
+    /// Shortest time between two stored snapshots of the same app window
+    pub min_interval: Duration,
+    /// Largest time between a frame and a snapshot still taken to show the
+    /// same moment
+    pub frame_tolerance: Duration,
+}
+
+impl Default for SnapshotRecorderConfig {
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_secs(7 * 24 * 60 * 60),
+            max_total_bytes: 1024 * 1024 * 1024,
+            min_interval: Duration::from_secs(5),
+            frame_tolerance: Duration::from_secs(2),
+        }
+    }
+}
+
+// Limits are enforced on this schedule, see `start_pruning`
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Wall clock time of `instant`
+///
+/// Measured back from now on the monotonic clock, so a wall clock adjusted
+/// since `instant` shifts frames and snapshots alike.
+pub fn wall_clock_time(instant: Instant) -> DateTime<Utc> {
+    let elapsed = chrono::Duration::from_std(instant.elapsed()).unwrap_or_default();
+    Utc::now() - elapsed
+}
+
+/// A stored snapshot near a frame
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotCandidate {
+    pub snapshot: UiSnapshotMeta,
+    /// Milliseconds from the frame to the snapshot, negative when the snapshot
+    /// was taken first
+    pub delta_ms: i64,
+}
+
+/// The snapshot showing the same moment as a frame
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "match", rename_all = "snake_case")]
+pub enum FrameSnapshot {
+    /// The closest snapshot was taken within the tolerance
+    Matched {
+        candidate: SnapshotCandidate,
+        element: ElementSnapshot,
+    },
+    /// No snapshot was taken within the tolerance, these are the closest
+    /// before and after the frame
+    Nearest { candidates: Vec<SnapshotCandidate> },
+}
+
+pub struct SnapshotRecorder {
+    db: Arc<DatabaseManager>,
+    config: SnapshotRecorderConfig,
+    /// When each app window was last stored, and the content hash stored
+    last_recorded: Mutex<HashMap<(String, Option<String>), (Instant, u64)>>,
+}
+
+impl SnapshotRecorder {
+    pub fn new(db: Arc<DatabaseManager>, config: SnapshotRecorderConfig) -> Self {
+        Self {
+            db,
+            config,
+            last_recorded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Delete snapshots past the retention or size limits every
+    /// `PRUNE_INTERVAL`, until the recorder is dropped
+    pub fn start_pruning(self: &Arc<Self>) -> JoinHandle<()> {
+        let recorder = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(recorder) = Weak::upgrade(&recorder) else {
+                    return;
+                };
+                match recorder.prune().await {
+                    Ok(deleted) => debug!("Deleted {} old accessibility snapshots", deleted),
+                    Err(e) => error!("Failed to delete old accessibility snapshots: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Delete snapshots past the retention period, then the oldest until the
+    /// rest fit `max_total_bytes`, returning how many were deleted
+    pub async fn prune(&self) -> Result<u64, anyhow::Error> {
+        let older_than = Utc::now() - chrono::Duration::from_std(self.config.retention)?;
+        Ok(self
+            .db
+            .prune_ui_snapshots(older_than, self.config.max_total_bytes as i64)
+            .await?)
+    }
+
+    /// Whether `record` would store `snapshot`, so callers can skip gathering
+    /// the rest of its details when it would not
+    pub fn is_due(
+        &self,
+        app_name: &str,
+        window_name: Option<&str>,
+        snapshot: &ElementSnapshot,
+    ) -> bool {
+        self.due(app_name, window_name, snapshot.content_hash())
+    }
+
+    fn due(&self, app_name: &str, window_name: Option<&str>, hash: u64) -> bool {
+        let last_recorded = self
+            .last_recorded
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let key = (app_name.to_string(), window_name.map(str::to_string));
+        !last_recorded.get(&key).is_some_and(|(at, last_hash)| {
+            *last_hash == hash || at.elapsed() < self.config.min_interval
+        })
+    }
+
+    /// Store `snapshot` of `app_name` captured at `captured_at`, returning its
+    /// ID, or None when it was skipped as unchanged or too soon after the
+    /// previous one of the same window
+    pub async fn record(
+        &self,
+        captured_at: DateTime<Utc>,
+        app_name: &str,
+        window_name: Option<&str>,
+        focused_label: Option<&str>,
+        snapshot: &ElementSnapshot,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let hash = snapshot.content_hash();
+        if !self.due(app_name, window_name, hash) {
+            return Ok(None);
+        }
+
+        let json = serde_json::to_vec(snapshot)?;
+        let data = tokio::task::spawn_blocking(move || gzip(&json)).await??;
+        let id = self
+            .db
+            .insert_ui_snapshot(
+                captured_at,
+                app_name,
+                window_name,
+                focused_label,
+                snapshot.element_count() as i64,
+                &data,
+            )
+            .await?;
+
+        // Only once stored, a failed insert is retried with the next capture
+        self.last_recorded
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                (app_name.to_string(), window_name.map(str::to_string)),
+                (Instant::now(), hash),
+            );
+        Ok(Some(id))
+    }
+
+    /// Stored snapshots taken between `from` and `to`, oldest first
+    pub async fn query_snapshots(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        app_name: Option<&str>,
+    ) -> Result<Vec<UiSnapshotMeta>, anyhow::Error> {
+        Ok(self.db.query_ui_snapshots(from, to, app_name).await?)
+    }
+
+    /// The stored snapshot with ID `id`, None if there is none
+    pub async fn load_snapshot(&self, id: i64) -> Result<Option<ElementSnapshot>, anyhow::Error> {
+        let Some(data) = self.db.get_ui_snapshot_data(id).await? else {
+            return Ok(None);
+        };
+        let json = tokio::task::spawn_blocking(move || gunzip(&data)).await??;
+        Ok(Some(ElementSnapshot::from_json(&json)?))
+    }
+
+    /// The snapshot matching frame `frame_id`, None if there is no such frame
+    ///
+    /// Only snapshots of the app the frame shows are considered.
+    pub async fn get_snapshot_for_frame(
+        &self,
+        frame_id: i64,
+    ) -> Result<Option<FrameSnapshot>, anyhow::Error> {
+        let Some((frame_time, app_name)) = self.db.get_frame_time_and_app(frame_id).await? else {
+            return Ok(None);
+        };
+
+        let mut candidates: Vec<SnapshotCandidate> = self
+            .db
+            .nearest_ui_snapshots(
+                frame_time,
+                app_name.as_deref().filter(|app| !app.is_empty()),
+            )
+            .await?
+            .into_iter()
+            .map(|snapshot| SnapshotCandidate {
+                delta_ms: (snapshot.timestamp - frame_time).num_milliseconds(),
+                snapshot,
+            })
+            .collect();
+
+        let tolerance = self.config.frame_tolerance.as_millis() as i64;
+        let closest = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| candidate.delta_ms.abs() <= tolerance)
+            .min_by_key(|(_, candidate)| candidate.delta_ms.abs())
+            .map(|(index, _)| index);
+        if let Some(index) = closest {
+            let candidate = candidates.swap_remove(index);
+            // Pruned in the meantime, fall through to the candidates left
+            if let Some(element) = self.load_snapshot(candidate.snapshot.id).await? {
+                return Ok(Some(FrameSnapshot::Matched { candidate, element }));
+            }
+        }
+        Ok(Some(FrameSnapshot::Nearest { candidates }))
+    }
+}
+
+fn gzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn gunzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
@@ -0,0 +1,94 @@
+//! Shared polling sessions behind the snapshot stream
+//!
+//! Every client streaming the same app reads from one session, so the app is
+//! captured once per interval however many clients are connected. Clients only
+//! ever see the latest capture, a slow client skips captures rather than
+//! queueing them up. A session stops once its last client is gone.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::watch;
+
+type SessionMap<T> = HashMap<String, Arc<watch::Sender<Option<T>>>>;
+
+pub struct SnapshotStreams<T> {
+    sessions: Arc<Mutex<SessionMap<T>>>,
+}
+
+impl<T> Default for SnapshotStreams<T> {
+    fn default() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> SnapshotStreams<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to the session for `key`, starting one that calls `capture`
+    /// every `interval` if there is none
+    ///
+    /// `capture` returns the new value and, when captures keep failing, a
+    /// longer delay to wait before the next one. The receiver holds `None`
+    /// until the first capture of a new session finishes.
+    pub fn subscribe<F, Fut>(
+        &self,
+        key: &str,
+        interval: Duration,
+        capture: F,
+    ) -> watch::Receiver<Option<T>>
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = (T, Option<Duration>)> + Send + 'static,
+    {
+        let mut sessions = lock(&self.sessions);
+        if let Some(sender) = sessions.get(key) {
+            return sender.subscribe();
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        let sender = Arc::new(sender);
+        sessions.insert(key.to_string(), sender.clone());
+
+        let sessions = self.sessions.clone();
+        let key = key.to_string();
+        tokio::spawn(async move {
+            loop {
+                let (value, retry_after) = capture().await;
+                sender.send_replace(Some(value));
+                let delay = retry_after.map_or(interval, |delay| delay.max(interval));
+                // Cut short when the last client leaves, a backoff can last a minute
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = sender.closed() => {}
+                }
+
+                // Checked under the lock so a client subscribing right now
+                // either finds this session running or starts a new one
+                let mut sessions = lock(&sessions);
+                if sender.receiver_count() == 0 {
+                    sessions.remove(&key);
+                    break;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    /// Number of sessions running
+    pub fn session_count(&self) -> usize {
+        lock(&self.sessions).len()
+    }
+}
+
+// Every change to the map is a single insert or remove, so it stays
+// consistent even if a holder panicked
+fn lock<T>(sessions: &Mutex<SessionMap<T>>) -> MutexGuard<'_, SessionMap<T>> {
+    sessions.lock().unwrap_or_else(PoisonError::into_inner)
+}
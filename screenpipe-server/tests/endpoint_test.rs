@@ -2,6 +2,7 @@
 mod tests {
     use axum::body::to_bytes;
     use axum::body::Body;
+    use axum::extract::connect_info::MockConnectInfo;
     use axum::http::{Request, StatusCode};
     use axum::Router;
     use chrono::DateTime;
@@ -29,6 +30,10 @@ mod tests {
     }
 
     async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+        setup_test_app_with(false).await
+    }
+
+    async fn setup_test_app_with(apple_script_enabled: bool) -> (Router, Arc<DatabaseManager>) {
         let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
 
         let audio_manager = Arc::new(
@@ -47,6 +52,10 @@ mod tests {
             false,
             false,
             false,
+            1,
+            std::time::Duration::from_secs(30),
+            None,
+            apple_script_enabled,
             audio_manager,
         );
 
@@ -780,4 +789,54 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_applescript_route_is_opt_in_and_local_only() {
+        let post = |origin: Option<&str>| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/experimental/operator/applescript")
+                .header("content-type", "application/json");
+            if let Some(origin) = origin {
+                request = request.header("origin", origin);
+            }
+            request.body(Body::from("{}")).unwrap()
+        };
+        let status = |router: Router, addr: &str, origin: Option<&str>| {
+            let addr: SocketAddr = addr.parse().unwrap();
+            async move {
+                router
+                    .layer(MockConnectInfo(addr))
+                    .oneshot(post(origin))
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        let (disabled, _) = setup_test_app().await;
+        assert_eq!(
+            status(disabled, "127.0.0.1:50000", None).await,
+            StatusCode::NOT_FOUND
+        );
+
+        let (app, _) = setup_test_app_with(true).await;
+        assert_eq!(
+            status(app.clone(), "192.168.1.5:50000", None).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(app.clone(), "127.0.0.1:50000", Some("https://example.com")).await,
+            StatusCode::FORBIDDEN
+        );
+        // Past the checks, rejected for giving neither source nor shortcut
+        assert_eq!(
+            status(app.clone(), "[::ffff:127.0.0.1]:50000", None).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(app, "[::1]:50000", Some("tauri://localhost")).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
 }
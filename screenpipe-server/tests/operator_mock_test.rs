@@ -0,0 +1,195 @@
+// The mock engine is installed once per process, so these tests live in their
+// own binary
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use screenpipe_audio::audio_manager::AudioManagerBuilder;
+    use screenpipe_core::operator::{use_mock_engine, MockEngine};
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::{PipeManager, SCServer};
+    use serde_json::{json, Value};
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tokio::sync::OnceCell;
+    use tower::ServiceExt;
+
+    static ENGINE: OnceCell<()> = OnceCell::const_new();
+
+    async fn setup_mock_app() -> Router {
+        ENGINE
+            .get_or_init(|| async {
+                let fixtures = tempfile::tempdir().unwrap();
+                let notes = json!({
+                    "id": "notes",
+                    "role": "application",
+                    "label": "Notes",
+                    "children": [
+                        {"id": "new_note", "role": "button", "label": "New Note"},
+                        {"id": "title", "role": "text_field", "label": "Title"},
+                        {
+                            "id": "list",
+                            "role": "list",
+                            "children": [
+                                {"id": "draft", "role": "row", "label": "Draft"},
+                                {"id": "draft", "role": "row", "label": "Draft"}
+                            ]
+                        }
+                    ]
+                });
+                std::fs::write(fixtures.path().join("notes.json"), notes.to_string()).unwrap();
+                use_mock_engine(MockEngine::from_dir(fixtures.path()).unwrap());
+            })
+            .await;
+
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let audio_manager = Arc::new(
+            AudioManagerBuilder::new()
+                .output_path("/tmp/screenpipe".into())
+                .build(db.clone())
+                .await
+                .unwrap(),
+        );
+        SCServer::new(
+            db,
+            SocketAddr::from(([127, 0, 0, 1], 23949)),
+            PathBuf::from(""),
+            Arc::new(PipeManager::new(PathBuf::from(""))),
+            false,
+            false,
+            false,
+            1,
+            std::time::Duration::from_secs(30),
+            None,
+            false,
+            audio_manager,
+        )
+        .create_router(true)
+        .await
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn post(uri: &str, body: Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mock_actions_route_lists_actions_on_fixtures() {
+        let app = setup_mock_app().await;
+
+        let (status, _) = send(
+            &app,
+            post(
+                "/experimental/operator/click",
+                json!({"selector": {"app_name": "Notes", "locator": "#new_note"}}),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, actions) = send(&app, get("/experimental/operator/mock/actions")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(actions
+            .as_array()
+            .unwrap()
+            .contains(&json!({"element": "new_note", "action": "click"})));
+    }
+
+    #[tokio::test]
+    async fn test_suffixed_ids_resolve_through_the_cached_snapshot() {
+        let app = setup_mock_app().await;
+
+        // Identical siblings share an ID, the snapshot tells them apart
+        let (status, snapshot) = send(&app, get("/experimental/operator/snapshot?app=Notes")).await;
+        assert_eq!(status, StatusCode::OK);
+        let rows = &snapshot["element"]["children"][2]["children"];
+        assert_eq!(rows[0]["id"], "draft");
+        assert_eq!(rows[1]["id"], "draft_2");
+
+        let click = |locator: &str| {
+            post(
+                "/experimental/operator/click",
+                json!({"selector": {"app_name": "Notes", "locator": locator}}),
+            )
+        };
+        let (status, _) = send(&app, click("#draft_2")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, actions) = send(&app, get("/experimental/operator/mock/actions")).await;
+        assert!(actions
+            .as_array()
+            .unwrap()
+            .contains(&json!({"element": "draft_2", "action": "click"})));
+
+        // Never given out by a snapshot
+        let (status, _) = send(&app, click("#draft_3")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_recording_covers_every_element_action() {
+        let app = setup_mock_app().await;
+        let selector = |locator: &str| json!({"app_name": "Notes", "locator": locator});
+
+        let (status, _) = send(
+            &app,
+            post("/experimental/operator/recording/start", json!({})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        for (uri, body) in [
+            (
+                "/experimental/operator/press-key",
+                json!({"selector": selector("#title"), "key_combo": "cmd+a"}),
+            ),
+            (
+                "/experimental/operator/scroll",
+                json!({"selector": selector("#list"), "direction": "down", "amount": 3.0}),
+            ),
+            (
+                "/experimental/operator/hover",
+                json!({"selector": selector("#new_note"), "duration_ms": 0}),
+            ),
+        ] {
+            let (status, body) = send(&app, post(uri, body)).await;
+            assert_eq!(status, StatusCode::OK, "{}: {}", uri, body);
+        }
+
+        let (status, recording) = send(
+            &app,
+            post("/experimental/operator/recording/stop", json!({})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let actions: Vec<&Value> = recording["script"]["steps"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|step| &step["action"])
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                &json!({"type": "press_key", "key": "cmd+a"}),
+                &json!({"type": "scroll", "direction": "down", "amount": 3.0}),
+                &json!({"type": "hover"}),
+            ]
+        );
+    }
+}
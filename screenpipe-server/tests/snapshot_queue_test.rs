@@ -0,0 +1,173 @@
+use screenpipe_server::snapshot_queue::{SnapshotQueue, SnapshotTaskError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_identical_requests_share_one_capture() {
+    let queue = Arc::new(SnapshotQueue::new(1));
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let requests = (0..3).map(|_| {
+        let queue = queue.clone();
+        let runs = runs.clone();
+        async move {
+            queue
+                .run("Finder", "same".to_string(), move || {
+                    std::thread::sleep(Duration::from_millis(100));
+                    runs.fetch_add(1, Ordering::SeqCst)
+                })
+                .await
+        }
+    });
+    let results = futures::future::join_all(requests).await;
+
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert!(results.iter().all(|result| result == &Ok(0)));
+    assert!(queue.depth().is_empty());
+}
+
+#[tokio::test]
+async fn test_abandoned_capture_leaves_queue() {
+    let queue = Arc::new(SnapshotQueue::new(1));
+    let (release, released) = std::sync::mpsc::channel::<()>();
+
+    let waiter = {
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            queue
+                .run("Finder", "a".to_string(), move || {
+                    let _ = released.recv();
+                    0
+                })
+                .await
+        })
+    };
+    while queue.depth().is_empty() {
+        tokio::task::yield_now().await;
+    }
+    // The client went away, the capture is still running
+    waiter.abort();
+    let _ = waiter.await;
+    assert_eq!(queue.depth().get("Finder"), Some(&1));
+
+    drop(release);
+    for _ in 0..100 {
+        if queue.depth().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(queue.depth().is_empty());
+
+    // Runs a capture of its own rather than joining the finished one
+    assert_eq!(queue.run("Finder", "a".to_string(), || 1).await, Ok(1));
+}
+
+#[tokio::test]
+async fn test_concurrency_limit_per_app() {
+    let queue = Arc::new(SnapshotQueue::new(1));
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    // Captures say when they start, then hold their permit until released
+    let (started_tx, mut started) = tokio::sync::mpsc::unbounded_channel();
+    let (release, release_rx) = std::sync::mpsc::channel::<()>();
+    let release_rx = Arc::new(Mutex::new(release_rx));
+
+    let requests = futures::future::join_all((0..3).map(|i| {
+        let queue = queue.clone();
+        let running = running.clone();
+        let peak = peak.clone();
+        let started_tx = started_tx.clone();
+        let release_rx = release_rx.clone();
+        async move {
+            queue
+                .run("Finder", format!("request {}", i), move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    started_tx.send(()).unwrap();
+                    release_rx.lock().unwrap().recv().unwrap();
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+                .await
+        }
+    }));
+    let requests = tokio::spawn(requests);
+
+    // Every request is queued before any capture can start
+    started.recv().await.unwrap();
+    assert_eq!(queue.depth().get("Finder"), Some(&3));
+
+    for _ in 0..3 {
+        release.send(()).unwrap();
+    }
+    requests.await.unwrap();
+    assert_eq!(peak.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_failing_app_backs_off() {
+    let queue = SnapshotQueue::new(1);
+    queue.run("Legacy", "a".to_string(), || ()).await.unwrap();
+    queue.run("Finder", "a".to_string(), || ()).await.unwrap();
+
+    assert_eq!(
+        queue.record_outcome("Legacy", true),
+        Some(Duration::from_secs(1))
+    );
+    assert_eq!(
+        queue.record_outcome("Legacy", true),
+        Some(Duration::from_secs(2))
+    );
+    for _ in 0..10 {
+        queue.record_outcome("Legacy", true);
+    }
+    assert_eq!(
+        queue.record_outcome("Legacy", true),
+        Some(Duration::from_secs(60))
+    );
+    assert_eq!(queue.record_outcome("Finder", false), None);
+    assert_eq!(queue.backoff().len(), 1);
+
+    assert_eq!(queue.record_outcome("Legacy", false), None);
+    assert!(queue.backoff().is_empty());
+}
+
+#[tokio::test]
+async fn test_hung_capture_times_out() {
+    let queue = SnapshotQueue::new(1).with_timeout(Duration::from_millis(100));
+
+    // Stands in for an accessibility call that does not return until the
+    // end of the test
+    let (release, released) = std::sync::mpsc::channel::<()>();
+    let started = std::time::Instant::now();
+    let result = queue
+        .run("Hung", "a".to_string(), move || {
+            let _ = released.recv();
+        })
+        .await;
+    assert_eq!(
+        result,
+        Err(SnapshotTaskError::Timeout(Duration::from_millis(100)))
+    );
+
+    // Queued behind the hung capture, which still holds the app's slot
+    let result = queue.run("Hung", "b".to_string(), || ()).await;
+    assert_eq!(result.unwrap_err().kind(), "timeout");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    assert_eq!(queue.run("Finder", "a".to_string(), || ()).await, Ok(()));
+    drop(release);
+}
+
+#[tokio::test]
+async fn test_panicking_capture_reports_panic() {
+    let queue: SnapshotQueue<()> = SnapshotQueue::new(1);
+    let result = queue
+        .run("Finder", "a".to_string(), || panic!("AX call failed"))
+        .await;
+    assert_eq!(
+        result,
+        Err(SnapshotTaskError::Panicked("AX call failed".to_string()))
+    );
+}
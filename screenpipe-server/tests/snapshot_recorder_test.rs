@@ -0,0 +1,211 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use screenpipe_core::{ElementSnapshot, Role};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::snapshot_recorder::{
+    wall_clock_time, FrameSnapshot, SnapshotRecorder, SnapshotRecorderConfig,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn snapshot(value: &str) -> ElementSnapshot {
+    let element = |id: &str, role: Role, label: &str| {
+        let mut element = ElementSnapshot::new(id, role);
+        element.label = Some(label.to_string());
+        element
+    };
+    let mut search = element("search", Role::TextField, "Search");
+    search.value = Some(value.to_string());
+    let mut window = element("window", Role::Window, "Downloads");
+    window.children = vec![search, element("ok", Role::Button, "OK")];
+    window
+}
+
+async fn setup_recorder(min_interval: Duration) -> SnapshotRecorder {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    SnapshotRecorder::new(
+        db,
+        SnapshotRecorderConfig {
+            min_interval,
+            ..SnapshotRecorderConfig::default()
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_record_and_load_snapshot() {
+    let recorder = setup_recorder(Duration::ZERO).await;
+    let tree = snapshot("invoice");
+
+    let id = recorder
+        .record(
+            Utc::now(),
+            "Finder",
+            Some("Downloads"),
+            Some("Search"),
+            &tree,
+        )
+        .await
+        .unwrap()
+        .expect("first snapshot is stored");
+
+    let stored = recorder
+        .query_snapshots(
+            Utc::now() - ChronoDuration::minutes(1),
+            Utc::now(),
+            Some("Finder"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, id);
+    assert_eq!(stored[0].window_name.as_deref(), Some("Downloads"));
+    assert_eq!(stored[0].focused_label.as_deref(), Some("Search"));
+    assert_eq!(stored[0].element_count, 3);
+
+    assert_eq!(recorder.load_snapshot(id).await.unwrap(), Some(tree));
+    assert_eq!(recorder.load_snapshot(id + 1).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_snapshot_is_stamped_when_capture_started() {
+    let recorder = setup_recorder(Duration::ZERO).await;
+
+    // A capture that took three seconds, recorded once it finished
+    let started = Instant::now() - Duration::from_secs(3);
+    recorder
+        .record(
+            wall_clock_time(started),
+            "Finder",
+            None,
+            None,
+            &snapshot("a"),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    let stored = recorder
+        .query_snapshots(
+            Utc::now() - ChronoDuration::minutes(1),
+            Utc::now(),
+            Some("Finder"),
+        )
+        .await
+        .unwrap();
+    let lag = Utc::now() - stored[0].timestamp;
+    assert!(lag >= ChronoDuration::milliseconds(2900), "{:?}", lag);
+    assert!(lag < ChronoDuration::seconds(10), "{:?}", lag);
+}
+
+#[tokio::test]
+async fn test_unchanged_and_frequent_snapshots_are_skipped() {
+    let recorder = setup_recorder(Duration::from_secs(60)).await;
+
+    assert!(recorder
+        .record(Utc::now(), "Finder", None, None, &snapshot("a"))
+        .await
+        .unwrap()
+        .is_some());
+    // Unchanged
+    assert!(recorder
+        .record(Utc::now(), "Finder", None, None, &snapshot("a"))
+        .await
+        .unwrap()
+        .is_none());
+    // Changed, but within the minimum interval
+    assert!(!recorder.is_due("Finder", None, &snapshot("b")));
+    assert!(recorder
+        .record(Utc::now(), "Finder", None, None, &snapshot("b"))
+        .await
+        .unwrap()
+        .is_none());
+    // Other windows of the app are tracked separately
+    assert!(recorder.is_due("Finder", Some("Downloads"), &snapshot("a")));
+    assert!(recorder
+        .record(
+            Utc::now(),
+            "Finder",
+            Some("Downloads"),
+            None,
+            &snapshot("a")
+        )
+        .await
+        .unwrap()
+        .is_some());
+    // Other apps are tracked separately
+    assert!(recorder
+        .record(Utc::now(), "Slack", None, None, &snapshot("a"))
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_snapshot_for_frame() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let recorder = SnapshotRecorder::new(
+        db.clone(),
+        SnapshotRecorderConfig {
+            min_interval: Duration::ZERO,
+            ..SnapshotRecorderConfig::default()
+        },
+    );
+    let now = Utc::now();
+    let seconds = ChronoDuration::seconds;
+
+    recorder
+        .record(now - seconds(10), "Finder", None, None, &snapshot("a"))
+        .await
+        .unwrap();
+    let after = recorder
+        .record(now + seconds(1), "Finder", None, None, &snapshot("b"))
+        .await
+        .unwrap()
+        .unwrap();
+    // Another app closer to the frame is not a candidate
+    recorder
+        .record(now, "Slack", None, None, &snapshot("c"))
+        .await
+        .unwrap();
+
+    db.insert_video_chunk("video.mp4", "monitor").await.unwrap();
+    let frame = db
+        .insert_frame("monitor", Some(now), None, Some("Finder"), None, true)
+        .await
+        .unwrap();
+    let late_frame = db
+        .insert_frame(
+            "monitor",
+            Some(now + seconds(30)),
+            None,
+            Some("Finder"),
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+
+    match recorder.get_snapshot_for_frame(frame).await.unwrap() {
+        Some(FrameSnapshot::Matched { candidate, element }) => {
+            assert_eq!(candidate.snapshot.id, after);
+            assert_eq!(candidate.delta_ms, 1000);
+            assert_eq!(element, snapshot("b"));
+        }
+        other => panic!("expected a match, got {:?}", other),
+    }
+
+    match recorder.get_snapshot_for_frame(late_frame).await.unwrap() {
+        Some(FrameSnapshot::Nearest { candidates }) => {
+            assert_eq!(candidates.len(), 1);
+            assert_eq!(candidates[0].snapshot.id, after);
+            assert_eq!(candidates[0].delta_ms, -29000);
+        }
+        other => panic!("expected candidates, got {:?}", other),
+    }
+
+    assert!(recorder
+        .get_snapshot_for_frame(late_frame + 1)
+        .await
+        .unwrap()
+        .is_none());
+}
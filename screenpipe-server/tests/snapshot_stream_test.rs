@@ -0,0 +1,82 @@
+use screenpipe_server::snapshot_stream::SnapshotStreams;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Captures run on the paused test clock, which only moves forward once every
+// task is waiting on a timer
+#[tokio::test(start_paused = true)]
+async fn test_clients_share_one_session() {
+    let streams = SnapshotStreams::new();
+    let captures = Arc::new(AtomicUsize::new(0));
+    let capture = {
+        let captures = captures.clone();
+        move || {
+            let captures = captures.clone();
+            async move { (captures.fetch_add(1, Ordering::SeqCst), None) }
+        }
+    };
+
+    let mut first = streams.subscribe("Finder", Duration::from_millis(50), capture.clone());
+    let mut second = streams.subscribe("Finder", Duration::from_millis(50), capture);
+    assert_eq!(streams.session_count(), 1);
+
+    first.changed().await.unwrap();
+    second.changed().await.unwrap();
+    assert_eq!(*first.borrow(), *second.borrow());
+
+    // At 0, 50 and 100 ms
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert_eq!(captures.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_session_stops_without_clients() {
+    let streams = SnapshotStreams::new();
+    let captures = Arc::new(AtomicUsize::new(0));
+    let capture = {
+        let captures = captures.clone();
+        move || {
+            let captures = captures.clone();
+            async move { (captures.fetch_add(1, Ordering::SeqCst), None) }
+        }
+    };
+
+    let mut client = streams.subscribe("Finder", Duration::from_millis(20), capture.clone());
+    client.changed().await.unwrap();
+    drop(client);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(streams.session_count(), 0);
+    assert_eq!(captures.load(Ordering::SeqCst), 1);
+
+    // A later client starts a fresh session
+    let mut client = streams.subscribe("Finder", Duration::from_millis(20), capture);
+    client.changed().await.unwrap();
+    assert_eq!(streams.session_count(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_failing_source_is_polled_less_often() {
+    let streams = SnapshotStreams::new();
+    let captures = Arc::new(AtomicUsize::new(0));
+    let capture = {
+        let captures = captures.clone();
+        move || {
+            let captures = captures.clone();
+            async move {
+                captures.fetch_add(1, Ordering::SeqCst);
+                ((), Some(Duration::from_secs(60)))
+            }
+        }
+    };
+
+    let client = streams.subscribe("Finder", Duration::from_millis(10), capture);
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(captures.load(Ordering::SeqCst), 1);
+
+    // Stops right away rather than at the end of the backoff
+    drop(client);
+    tokio::time::sleep(Duration::from_millis(1)).await;
+    assert_eq!(streams.session_count(), 0);
+}
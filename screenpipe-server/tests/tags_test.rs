@@ -6,7 +6,7 @@ use axum::{
 use screenpipe_audio::audio_manager::AudioManagerBuilder;
 use screenpipe_vision::OcrEngine;
 use serde_json::json;
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tower::ServiceExt;
 
 use screenpipe_db::DatabaseManager;
@@ -36,6 +36,10 @@ async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
         false,
         false,
         false,
+        1,
+        Duration::from_secs(30),
+        None,
+        false,
         audio_manager,
     );
 
@@ -4,6 +4,11 @@ use std::{env, path::PathBuf, process::Command};
 fn main() {
     #[cfg(target_os = "macos")]
     {
+        // ui_monitor is only used by the macOS accessibility backend
+        if env::var_os("CARGO_FEATURE_BACKEND_MACOS").is_none() {
+            return;
+        }
+
         let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
         let bin_path = PathBuf::from(&manifest_dir).join("bin");
 
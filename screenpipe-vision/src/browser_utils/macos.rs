@@ -4,8 +4,8 @@ use accessibility_sys::{
 };
 use anyhow::Result;
 use core_foundation::{
-    array::CFArray,
-    base::{CFRelease, CFTypeRef, TCFType},
+    array::{CFArray, CFArrayRef},
+    base::{CFType, CFTypeRef, TCFType},
     string::CFString,
 };
 use url::Url;
@@ -14,66 +14,61 @@ use super::BrowserUrlDetector;
 
 pub struct MacOSUrlDetector;
 
+// Copy functions return a reference we own, wrapping it under the create rule
+// hands that reference to the wrapper so it is released exactly once on drop
+unsafe fn copy_attribute(element: AXUIElementRef, attribute: &'static str) -> Option<CFType> {
+    let mut value: CFTypeRef = std::ptr::null_mut();
+    let status = AXUIElementCopyAttributeValue(
+        element,
+        CFString::from_static_string(attribute).as_concrete_TypeRef(),
+        &mut value,
+    );
+
+    if status != accessibility_sys::kAXErrorSuccess || value.is_null() {
+        return None;
+    }
+    Some(CFType::wrap_under_create_rule(value))
+}
+
+unsafe fn copy_string_attribute(
+    element: AXUIElementRef,
+    attribute: &'static str,
+) -> Option<String> {
+    copy_attribute(element, attribute)?
+        .downcast::<CFString>()
+        .map(|value| value.to_string())
+}
+
 impl MacOSUrlDetector {
     pub fn new() -> Self {
         Self
     }
 
-    unsafe fn find_url_field(&self, element: AXUIElementRef) -> Option<AXUIElementRef> {
-        let mut role: CFTypeRef = std::ptr::null_mut();
-        let status = AXUIElementCopyAttributeValue(
-            element,
-            CFString::from_static_string(kAXRoleAttribute).as_concrete_TypeRef(),
-            &mut role,
-        );
-
-        if status == accessibility_sys::kAXErrorSuccess {
-            let _cf_role = CFString::wrap_under_get_rule(role as _);
-            let role_str = _cf_role.to_string();
-
-            if role_str == kAXTextFieldRole {
-                let mut value: CFTypeRef = std::ptr::null_mut();
-                let status = AXUIElementCopyAttributeValue(
-                    element,
-                    CFString::from_static_string(kAXValueAttribute).as_concrete_TypeRef(),
-                    &mut value,
-                );
-
-                if status == accessibility_sys::kAXErrorSuccess {
-                    let _value_str = CFString::wrap_under_get_rule(value as _);
-                    let url_str = _value_str.to_string();
-                    let url_to_parse =
-                        if !url_str.starts_with("http://") && !url_str.starts_with("https://") {
-                            format!("https://{}", url_str)
-                        } else {
-                            url_str
-                        };
-
-                    if Url::parse(&url_to_parse).is_ok() {
-                        return Some(element);
-                    }
-                }
-            }
-        }
-
-        let mut children: CFTypeRef = std::ptr::null_mut();
-        let status = AXUIElementCopyAttributeValue(
-            element,
-            CFString::from_static_string(kAXChildrenAttribute).as_concrete_TypeRef(),
-            &mut children,
-        );
-
-        if status == accessibility_sys::kAXErrorSuccess {
-            let _children_array =
-                CFArray::<*const std::ffi::c_void>::wrap_under_get_rule(children as _);
-            for child in _children_array.iter() {
-                if let Some(found) = self.find_url_field(*child as AXUIElementRef) {
-                    return Some(found);
+    // Returns an owned reference, elements borrowed from a children array would
+    // be freed along with the array
+    unsafe fn find_url_field(&self, element: AXUIElementRef) -> Option<CFType> {
+        if copy_string_attribute(element, kAXRoleAttribute).as_deref() == Some(kAXTextFieldRole) {
+            if let Some(url_str) = copy_string_attribute(element, kAXValueAttribute) {
+                let url_to_parse =
+                    if !url_str.starts_with("http://") && !url_str.starts_with("https://") {
+                        format!("https://{}", url_str)
+                    } else {
+                        url_str
+                    };
+
+                if Url::parse(&url_to_parse).is_ok() {
+                    return Some(CFType::wrap_under_get_rule(element as CFTypeRef));
                 }
             }
         }
 
-        None
+        let children = copy_attribute(element, kAXChildrenAttribute)?;
+        // Get rule, `children` keeps owning the array
+        let children =
+            CFArray::<CFType>::wrap_under_get_rule(children.as_CFTypeRef() as CFArrayRef);
+        children
+            .iter()
+            .find_map(|child| self.find_url_field(child.as_CFTypeRef() as AXUIElementRef))
     }
 
     fn get_url_via_applescript(&self, script: &str) -> Result<Option<String>> {
@@ -91,49 +86,28 @@ impl MacOSUrlDetector {
 
     fn get_url_via_accessibility(&self, process_id: i32) -> Result<Option<String>> {
         unsafe {
-            let app_element = AXUIElementCreateApplication(process_id);
-
-            let mut focused_window: CFTypeRef = std::ptr::null_mut();
-            let status = AXUIElementCopyAttributeValue(
-                app_element,
-                CFString::from_static_string(kAXFocusedWindowAttribute).as_concrete_TypeRef(),
-                &mut focused_window,
-            );
-
-            if status != accessibility_sys::kAXErrorSuccess {
-                CFRelease(app_element as CFTypeRef);
-                return Ok(None);
-            }
-
-            let window_ref = focused_window as AXUIElementRef;
-            let address_bar = match self.find_url_field(window_ref) {
-                Some(bar) => bar,
-                None => {
-                    CFRelease(focused_window as CFTypeRef);
-                    CFRelease(app_element as CFTypeRef);
-                    return Ok(None);
-                }
-            };
-
-            let mut url_value: CFTypeRef = std::ptr::null_mut();
-            let status = AXUIElementCopyAttributeValue(
-                address_bar,
-                CFString::from_static_string(kAXValueAttribute).as_concrete_TypeRef(),
-                &mut url_value,
-            );
-
-            let result = if status == accessibility_sys::kAXErrorSuccess {
-                let url_str = CFString::wrap_under_get_rule(url_value as _);
-                Ok(Some(url_str.to_string()))
-            } else {
-                Ok(None)
+            let app_element = CFType::wrap_under_create_rule(AXUIElementCreateApplication(
+                process_id,
+            ) as CFTypeRef);
+
+            let focused_window = match copy_attribute(
+                app_element.as_CFTypeRef() as AXUIElementRef,
+                kAXFocusedWindowAttribute,
+            ) {
+                Some(window) => window,
+                None => return Ok(None),
             };
 
-            CFRelease(url_value as CFTypeRef);
-            CFRelease(focused_window as CFTypeRef);
-            CFRelease(app_element as CFTypeRef);
+            let address_bar =
+                match self.find_url_field(focused_window.as_CFTypeRef() as AXUIElementRef) {
+                    Some(bar) => bar,
+                    None => return Ok(None),
+                };
 
-            result
+            Ok(copy_string_attribute(
+                address_bar.as_CFTypeRef() as AXUIElementRef,
+                kAXValueAttribute,
+            ))
         }
     }
 }
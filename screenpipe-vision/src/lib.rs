@@ -5,7 +5,7 @@ pub mod custom_ocr;
 #[cfg(target_os = "windows")]
 pub mod microsoft;
 pub mod monitor;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", feature = "backend-macos"))]
 pub mod run_ui_monitoring_macos;
 pub mod tesseract;
 pub mod utils;
@@ -18,7 +18,7 @@ pub mod capture_screenshot_by_window;
 pub use custom_ocr::perform_ocr_custom;
 #[cfg(target_os = "windows")]
 pub use microsoft::perform_ocr_windows;
-#[cfg(target_os = "macos")]
-pub use run_ui_monitoring_macos::run_ui;
+#[cfg(all(target_os = "macos", feature = "backend-macos"))]
+pub use run_ui_monitoring_macos::{run_ui, ui_monitor_status, UiMonitorStatus};
 pub use tesseract::perform_ocr_tesseract;
 pub mod browser_utils;
@@ -1,20 +1,79 @@
 use crate::UIFrame;
 use anyhow::Result;
-use tracing::{debug, error, info, warn};
 use screenpipe_events::send_event;
+use serde::Serialize;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
 use tokio::fs::{File, OpenOptions};
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::process::Command;
 use tokio::signal;
 use tokio::time::{sleep, timeout, Duration};
+use tracing::{debug, error, info, warn};
 use which::which;
 
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+// A helper that stayed up this long crashed for a new reason, restart it quickly
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+/// State of the ui_monitor helper process
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UiMonitorStatus {
+    /// Process ID of the running helper
+    pub pid: Option<u32>,
+    /// Seconds since the running helper started
+    pub uptime_secs: Option<u64>,
+    /// Times the helper was started again after exiting
+    pub restarts: u32,
+}
+
+struct SupervisorState {
+    running: Option<(u32, Instant)>,
+    restarts: u32,
+}
+
+static SUPERVISOR: Mutex<SupervisorState> = Mutex::new(SupervisorState {
+    running: None,
+    restarts: 0,
+});
+
+/// Report whether the ui_monitor helper is running and how often it crashed
+pub fn ui_monitor_status() -> UiMonitorStatus {
+    let state = SUPERVISOR.lock().unwrap_or_else(PoisonError::into_inner);
+    UiMonitorStatus {
+        pid: state.running.map(|(pid, _)| pid),
+        uptime_secs: state
+            .running
+            .map(|(_, started)| started.elapsed().as_secs()),
+        restarts: state.restarts,
+    }
+}
+
+// Kills the helper's process group when dropped. run_ui is usually stopped by
+// dropping its future on shutdown, this takes the helper and anything it
+// spawned along instead of leaving them orphaned
+struct ProcessGroupGuard {
+    pgid: i32,
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::killpg(self.pgid, libc::SIGKILL);
+        }
+        SUPERVISOR
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .running = None;
+    }
+}
+
 pub async fn run_ui() -> Result<()> {
     info!("starting ui monitoring service...");
 
@@ -77,7 +136,7 @@ pub async fn run_ui() -> Result<()> {
 
         if fs::metadata(&named_pipe_clone.path).is_ok() {
             if let Err(e) = fs::remove_file(&named_pipe_clone.path) {
-                eprintln!("Failed to remove named pipe: {:?}", e);
+                warn!("Failed to remove named pipe: {:?}", e);
             }
         }
         is_running_clone.store(false, std::sync::atomic::Ordering::Relaxed);
@@ -151,15 +210,52 @@ pub async fn run_ui() -> Result<()> {
     });
 
     let named_pipe_clone = named_pipe.clone();
+    let mut restart_delay = MIN_RESTART_DELAY;
+    let mut started_before = false;
 
     while is_running.load(std::sync::atomic::Ordering::Relaxed) {
-        // Clone the PathBuf for each iteration
-        let mut child = Command::new(&ui_monitor_path)
+        if started_before {
+            SUPERVISOR
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .restarts += 1;
+        }
+        started_before = true;
+
+        let mut command = Command::new(&ui_monitor_path);
+        command
             .arg(named_pipe_clone.path.clone())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()
-            .expect("failed to start ui_monitor");
+            .kill_on_drop(true);
+        // Lead a new process group so the guard can kill its children too
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setpgid(0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                error!("failed to start ui_monitor: {}", e);
+                warn!("retrying ui_monitor in {:?}...", restart_delay);
+                sleep(restart_delay).await;
+                restart_delay = (restart_delay * 2).min(MAX_RESTART_DELAY);
+                continue;
+            }
+        };
+        let started = Instant::now();
+        let guard = child.id().map(|pid| {
+            SUPERVISOR
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .running = Some((pid, started));
+            ProcessGroupGuard { pgid: pid as i32 }
+        });
 
         info!("ui_monitor process started");
 
@@ -194,17 +290,17 @@ pub async fn run_ui() -> Result<()> {
 
         // Wait for the process to exit
         match child.wait().await {
-            Ok(status) => {
-                warn!("ui_monitor exited with status: {}", status);
-                warn!("restarting ui_monitor in 5 seconds...");
-                sleep(Duration::from_secs(5)).await;
-            }
-            Err(e) => {
-                error!("failed to wait for ui_monitor process: {}", e);
-                warn!("retrying ui_monitor in 5 seconds...");
-                sleep(Duration::from_secs(5)).await;
-            }
+            Ok(status) => warn!("ui_monitor exited with status: {}", status),
+            Err(e) => error!("failed to wait for ui_monitor process: {}", e),
+        }
+        drop(guard);
+
+        if started.elapsed() >= STABLE_UPTIME {
+            restart_delay = MIN_RESTART_DELAY;
         }
+        warn!("restarting ui_monitor in {:?}...", restart_delay);
+        sleep(restart_delay).await;
+        restart_delay = (restart_delay * 2).min(MAX_RESTART_DELAY);
     }
 
     Ok(())
@@ -232,13 +328,14 @@ struct NamedPipe {
 impl NamedPipe {
     async fn new(path: String) -> Result<Self> {
         // Create the named pipe
-        let c_path = std::ffi::CString::new(path.clone()).expect("CString::new failed");
+        let c_path = std::ffi::CString::new(path.clone())
+            .map_err(|e| anyhow::anyhow!("invalid named pipe path {:?}: {}", path, e))?;
         let result = unsafe { libc::mkfifo(c_path.as_ptr(), libc::S_IRUSR | libc::S_IWUSR) };
         if result != 0 {
-            panic!(
+            return Err(anyhow::anyhow!(
                 "Failed to create named pipe: {:?}",
                 std::io::Error::last_os_error()
-            );
+            ));
         }
 
         Ok(NamedPipe { path })
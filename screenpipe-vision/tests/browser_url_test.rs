@@ -0,0 +1,33 @@
+#[cfg(target_os = "macos")]
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::browser_utils::create_url_detector;
+    use std::process::Command;
+
+    // Every lookup copies and releases a handful of CF objects, a single over-
+    // or under-release shows up quickly when repeated. Run under address
+    // sanitizer to turn a bad release into a report instead of a flaky crash:
+    // RUSTFLAGS=-Zsanitizer=address cargo +nightly test -p screenpipe-vision --test browser_url_test
+    #[test]
+    fn test_repeated_url_lookups_balance_references() {
+        let detector = create_url_detector();
+
+        let mut pids = vec![std::process::id() as i32];
+        if let Ok(output) = Command::new("pgrep").arg("-x").arg("Finder").output() {
+            pids.extend(
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter_map(|line| line.trim().parse::<i32>().ok()),
+            );
+        }
+
+        for _ in 0..500 {
+            for &pid in &pids {
+                // Without accessibility permission this returns early, which
+                // still goes through the application element's release
+                let result = detector.get_active_url("Safari", pid);
+                assert!(result.is_ok());
+            }
+        }
+    }
+}